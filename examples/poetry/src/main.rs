@@ -31,7 +31,7 @@ USAGE:
 
 FLAGS:
     -s, --script             The script to run
-    -w, --watch              Watch the script file for changes
+    -w, --watch              Watch the script's directory for changes
     -h, --help               Prints help information
     -v, --version            Prints version information
 ",
@@ -101,18 +101,26 @@ fn main() {
     if args.watch {
         let mut hotwatch = Hotwatch::new_with_custom_delay(Duration::from_secs_f64(0.25))
             .expect("Failed to initialize file watcher");
+        let script_dir = script_path
+            .canonicalize()
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+            .expect("Failed to get the script's directory");
         hotwatch
-            .watch(&args.script, move |event: Event| {
-                // dbg!(&event);
+            .watch(&script_dir, move |event: Event| {
                 match event {
-                    Event::Create(script_path) | Event::Write(script_path) => {
+                    Event::Create(_) | Event::Write(_) => {
+                        // Modules imported by the script are reloaded before it's run again
+                        if let Err(e) = koto.reload_modified_modules(&[]) {
+                            eprintln!("Error while reloading modules: {}", e);
+                        }
                         compile_and_run(&mut koto, &script_path);
                     }
                     _ => {}
                 }
                 Flow::Continue
             })
-            .expect("failed to watch file!");
+            .expect("failed to watch directory!");
         hotwatch.run();
     }
 }
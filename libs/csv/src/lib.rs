@@ -0,0 +1,375 @@
+//! A Koto language module for working with CSV data
+
+use {
+    koto_runtime::{
+        core::io::File, external_error, is_external_instance, type_as_string, visit_external_value,
+        RuntimeResult, Value, ValueIterator, ValueIteratorOutput, ValueList, ValueMap, ValueVec,
+    },
+    std::{fs, io},
+};
+
+type RowReader = Box<dyn io::Read + Send + Sync>;
+
+// Options that are shared by csv.read, csv.rows, and csv.write
+struct CsvOptions {
+    delimiter: u8,
+    quote: u8,
+    headers: Option<bool>,
+    quote_style: csv::QuoteStyle,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            headers: None,
+            quote_style: csv::QuoteStyle::Necessary,
+        }
+    }
+}
+
+impl CsvOptions {
+    fn from_args(fn_name: &str, args: &[Value]) -> Result<Self, String> {
+        let mut result = Self::default();
+
+        let options = match args {
+            [] => return Ok(result),
+            [Value::Map(options)] => options,
+            [unexpected] => {
+                return Err(format!(
+                    "csv.{}: Expected a Map of options, found '{}'",
+                    fn_name,
+                    type_as_string(unexpected)
+                ))
+            }
+            _ => return Err(format!("csv.{}: Unexpected arguments", fn_name)),
+        };
+
+        for (key, value) in options.data().iter() {
+            match (key.to_string().as_str(), value) {
+                ("delimiter", Value::Str(s)) => {
+                    result.delimiter = single_byte(fn_name, "delimiter", s)?
+                }
+                ("quote", Value::Str(s)) => result.quote = single_byte(fn_name, "quote", s)?,
+                ("headers", Value::Bool(b)) => result.headers = Some(*b),
+                ("quote_style", Value::Str(s)) => {
+                    result.quote_style = match s.as_str() {
+                        "always" => csv::QuoteStyle::Always,
+                        "necessary" => csv::QuoteStyle::Necessary,
+                        "never" => csv::QuoteStyle::Never,
+                        "non_numeric" => csv::QuoteStyle::NonNumeric,
+                        unexpected => {
+                            return Err(format!(
+                                "csv.{}: Unexpected quote_style '{}', \
+                                 expected 'always', 'necessary', 'never', or 'non_numeric'",
+                                fn_name, unexpected
+                            ))
+                        }
+                    }
+                }
+                (option @ "delimiter", unexpected)
+                | (option @ "quote", unexpected)
+                | (option @ "quote_style", unexpected) => {
+                    return Err(format!(
+                        "csv.{}: Expected a String for the '{}' option, found '{}'",
+                        fn_name,
+                        option,
+                        type_as_string(unexpected)
+                    ))
+                }
+                ("headers", unexpected) => {
+                    return Err(format!(
+                        "csv.{}: Expected a Bool for the 'headers' option, found '{}'",
+                        fn_name,
+                        type_as_string(unexpected)
+                    ))
+                }
+                (unexpected, _) => {
+                    return Err(format!(
+                        "csv.{}: Unexpected option '{}'",
+                        fn_name, unexpected
+                    ))
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn make_reader(&self, input: RowReader) -> csv::Reader<RowReader> {
+        csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .has_headers(self.headers.unwrap_or(false))
+            .flexible(true)
+            .from_reader(input)
+    }
+}
+
+fn single_byte(fn_name: &str, option: &str, s: &str) -> Result<u8, String> {
+    match s.as_bytes() {
+        [byte] => Ok(*byte),
+        _ => Err(format!(
+            "csv.{}: Expected a single character for the '{}' option, found '{}'",
+            fn_name, option, s
+        )),
+    }
+}
+
+// Reads from a file handle with positioned reads
+//
+// Cloned file handles share their position, so each reader keeps track of its own offset,
+// allowing multiple readers of the same File to be used at the same time.
+struct FileReader {
+    file: fs::File,
+    offset: u64,
+}
+
+impl io::Read for FileReader {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let bytes_read = read_at(&self.file, buffer, self.offset)?;
+        self.offset += bytes_read as u64;
+        Ok(bytes_read)
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &fs::File, buffer: &mut [u8], offset: u64) -> io::Result<usize> {
+    use std::os::unix::fs::FileExt;
+    file.read_at(buffer, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &fs::File, buffer: &mut [u8], offset: u64) -> io::Result<usize> {
+    use std::os::windows::fs::FileExt;
+    file.seek_read(buffer, offset)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_at(mut file: &fs::File, buffer: &mut [u8], offset: u64) -> io::Result<usize> {
+    use std::io::{Read, Seek, SeekFrom};
+    file.seek(SeekFrom::Start(offset))?;
+    file.read(buffer)
+}
+
+// Makes a reader for the input, which can be either a String or a File
+fn make_input(fn_name: &str, input: &Value) -> Result<RowReader, String> {
+    match input {
+        Value::Str(s) => Ok(Box::new(io::Cursor::new(s.as_bytes().to_vec()))),
+        Value::Map(m) if is_external_instance::<File>(m) => {
+            // The file's handle is cloned so that the rows can be read lazily,
+            // with reading starting from the beginning of the file, as in File.read_to_string.
+            let mut result = None;
+            visit_external_value(m, |file: &mut File| {
+                result = Some(file.file.try_clone());
+                Ok(Value::Empty)
+            })
+            .map_err(|e| format!("csv.{}: {}", fn_name, e))?;
+
+            match result.unwrap() {
+                Ok(file) => Ok(Box::new(FileReader { file, offset: 0 })),
+                Err(e) => Err(format!(
+                    "csv.{}: Error while reading from file: {}",
+                    fn_name, e
+                )),
+            }
+        }
+        unexpected => Err(format!(
+            "csv.{}: Expected a String or File as input, found '{}'",
+            fn_name,
+            type_as_string(unexpected)
+        )),
+    }
+}
+
+fn record_to_list(record: &csv::StringRecord) -> Value {
+    Value::List(ValueList::with_data(
+        record
+            .iter()
+            .map(|field| Value::Str(field.into()))
+            .collect(),
+    ))
+}
+
+fn record_to_map(headers: &csv::StringRecord, record: &csv::StringRecord) -> Value {
+    let mut result = ValueMap::with_capacity(headers.len());
+    for (header, field) in headers.iter().zip(record.iter()) {
+        result.add_value(header, Value::Str(field.into()));
+    }
+    Value::Map(result)
+}
+
+// Returns an iterator over the rows of the input
+//
+// When headers are enabled then each row is a Map with the headers as keys,
+// otherwise rows are Lists of Strings.
+fn row_iter(
+    fn_name: &str,
+    input: &Value,
+    options: &CsvOptions,
+) -> Result<impl Iterator<Item = Result<Value, String>>, String> {
+    let mut reader = options.make_reader(make_input(fn_name, input)?);
+
+    let headers = if options.headers.unwrap_or(false) {
+        match reader.headers() {
+            Ok(headers) => Some(headers.clone()),
+            Err(e) => {
+                return Err(format!(
+                    "csv.{}: Error while reading headers: {}",
+                    fn_name, e
+                ))
+            }
+        }
+    } else {
+        None
+    };
+
+    let fn_name = fn_name.to_string();
+    Ok(reader.into_records().map(move |record| match record {
+        Ok(record) => Ok(match &headers {
+            Some(headers) => record_to_map(headers, &record),
+            None => record_to_list(&record),
+        }),
+        Err(e) => Err(format!("csv.{}: Error while reading row: {}", fn_name, e)),
+    }))
+}
+
+fn field_to_string(value: &Value) -> String {
+    match value {
+        Value::Empty => String::new(),
+        Value::Str(s) => s.to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn write_rows(rows: &[Value], options: &CsvOptions) -> Result<String, String> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .quote(options.quote)
+        .quote_style(options.quote_style)
+        .flexible(true)
+        .from_writer(Vec::new());
+
+    let write_error = |e: csv::Error| format!("csv.write: Error while writing row: {}", e);
+
+    // When writing Maps, the keys of the first row are used as headers
+    let headers = match rows.first() {
+        Some(Value::Map(first)) => {
+            let headers = first.data().keys().map(field_to_string).collect::<Vec<_>>();
+            if options.headers.unwrap_or(true) {
+                writer.write_record(&headers).map_err(write_error)?;
+            }
+            Some(headers)
+        }
+        _ => None,
+    };
+
+    for row in rows.iter() {
+        let fields = match (row, &headers) {
+            (Value::Map(row), Some(headers)) => {
+                let data = row.data();
+                headers
+                    .iter()
+                    .map(|header| {
+                        data.get(&Value::Str(header.as_str().into()))
+                            .map(field_to_string)
+                            .unwrap_or_default()
+                    })
+                    .collect::<Vec<_>>()
+            }
+            (Value::List(row), None) => row.data().iter().map(field_to_string).collect(),
+            (Value::Tuple(row), None) => row.data().iter().map(field_to_string).collect(),
+            (unexpected, Some(_)) => {
+                return Err(format!(
+                    "csv.write: Expected a Map for each row, found '{}'",
+                    type_as_string(unexpected)
+                ))
+            }
+            (unexpected, None) => {
+                return Err(format!(
+                    "csv.write: Expected a List or Tuple for each row, found '{}'",
+                    type_as_string(unexpected)
+                ))
+            }
+        };
+
+        writer.write_record(&fields).map_err(write_error)?;
+    }
+
+    match writer.into_inner() {
+        Ok(bytes) => String::from_utf8(bytes)
+            .map_err(|e| format!("csv.write: Error while writing rows: {}", e)),
+        Err(e) => Err(format!("csv.write: Error while writing rows: {}", e)),
+    }
+}
+
+fn read(args: &[Value]) -> Result<Value, String> {
+    match args {
+        [input, options @ ..] => {
+            let options = CsvOptions::from_args("read", options)?;
+            let rows = row_iter("read", input, &options)?.collect::<Result<ValueVec, _>>()?;
+            Ok(Value::List(ValueList::with_data(rows)))
+        }
+        _ => Err("csv.read: Expected a String or File as argument".to_string()),
+    }
+}
+
+fn rows(args: &[Value]) -> Result<Value, String> {
+    match args {
+        [input, options @ ..] => {
+            let options = CsvOptions::from_args("rows", options)?;
+            let mut rows = row_iter("rows", input, &options)?;
+            Ok(Value::Iterator(ValueIterator::make_external(move || {
+                rows.next().map(|row| match row {
+                    Ok(row) => Ok(ValueIteratorOutput::Value(row)),
+                    Err(e) => external_error!(e),
+                })
+            })))
+        }
+        _ => Err("csv.rows: Expected a String or File as argument".to_string()),
+    }
+}
+
+fn write(args: &[Value]) -> Result<Value, String> {
+    let (rows, options) = match args {
+        [rows, options @ ..] => (rows, CsvOptions::from_args("write", options)?),
+        _ => return Err("csv.write: Expected a List or Tuple of rows as argument".to_string()),
+    };
+
+    let output = match rows {
+        Value::List(rows) => write_rows(&rows.data(), &options)?,
+        Value::Tuple(rows) => write_rows(rows.data(), &options)?,
+        unexpected => {
+            return Err(format!(
+                "csv.write: Expected a List or Tuple of rows, found '{}'",
+                type_as_string(unexpected)
+            ))
+        }
+    };
+
+    Ok(Value::Str(output.into()))
+}
+
+fn to_runtime_result(result: Result<Value, String>) -> RuntimeResult {
+    match result {
+        Ok(value) => Ok(value),
+        Err(e) => external_error!(e),
+    }
+}
+
+pub fn make_module() -> ValueMap {
+    let mut result = ValueMap::new();
+
+    result.add_fn("read", |vm, args| {
+        to_runtime_result(read(vm.get_args(args)))
+    });
+    result.add_fn("rows", |vm, args| {
+        to_runtime_result(rows(vm.get_args(args)))
+    });
+    result.add_fn("write", |vm, args| {
+        to_runtime_result(write(vm.get_args(args)))
+    });
+
+    result
+}
@@ -1,41 +1,12 @@
 //! A Koto language module for working with JSON data
 
 use {
-    koto_runtime::{external_error, Value, ValueList, ValueMap, ValueVec},
-    koto_serialize::SerializableValue,
-    serde_json::Value as JsonValue,
+    koto_runtime::{external_error, Value, ValueMap},
+    koto_serialize::{DeserializableValue, SerializableValue, SerializeOptions},
 };
 
-fn json_value_to_koto_value(value: &serde_json::Value) -> Result<Value, String> {
-    let result = match value {
-        JsonValue::Null => Value::Empty,
-        JsonValue::Bool(b) => Value::Bool(*b),
-        JsonValue::Number(n) => match n.as_f64() {
-            Some(n64) => Value::Number(n64),
-            None => return Err(format!("Number is out of range for an f64: {}", n)),
-        },
-        JsonValue::String(s) => Value::Str(s.as_str().into()),
-        JsonValue::Array(a) => {
-            match a
-                .iter()
-                .map(|entry| json_value_to_koto_value(entry))
-                .collect::<Result<ValueVec, String>>()
-            {
-                Ok(result) => Value::List(ValueList::with_data(result)),
-                Err(e) => return Err(e),
-            }
-        }
-        JsonValue::Object(o) => {
-            let mut map = ValueMap::with_capacity(o.len());
-            for (key, value) in o.iter() {
-                map.add_value(key, json_value_to_koto_value(value)?);
-            }
-            Value::Map(map)
-        }
-    };
-
-    Ok(result)
-}
+// The options that can be passed to to_string
+const SUPPORTED_OPTIONS: &[&str] = &["pretty", "sort_keys"];
 
 pub fn make_module() -> ValueMap {
     use Value::*;
@@ -43,25 +14,39 @@ pub fn make_module() -> ValueMap {
     let mut result = ValueMap::new();
 
     result.add_fn("from_string", |vm, args| match vm.get_args(args) {
-        [Str(s)] => match serde_json::from_str(&s) {
-            Ok(value) => match json_value_to_koto_value(&value) {
-                Ok(result) => Ok(result),
-                Err(e) => external_error!("json.from_string: Error while parsing input: {}", e),
-            },
-            Err(e) => external_error!(
-                "json.from_string: Error while parsing input: {}",
-                e.to_string()
-            ),
+        [Str(s)] => match serde_json::from_str::<DeserializableValue>(s) {
+            Ok(result) => Ok(result.0),
+            Err(e) => external_error!("json.from_string: Error while parsing input: {}", e),
         },
         _ => external_error!("json.from_string expects a string as argument"),
     });
 
-    result.add_fn("to_string", |vm, args| match vm.get_args(args) {
-        [value] => match serde_json::to_string_pretty(&SerializableValue(value)) {
+    result.add_fn("to_string", |vm, args| {
+        let (value, options) = match vm.get_args(args) {
+            [value, options @ ..] => {
+                match SerializeOptions::from_args("json.to_string", options, SUPPORTED_OPTIONS) {
+                    Ok(options) => (value, options),
+                    Err(e) => return external_error!(e),
+                }
+            }
+            _ => {
+                return external_error!(
+                    "json.to_string expects a value, and an optional map of options as arguments"
+                )
+            }
+        };
+
+        let serializable = SerializableValue::with_options(value, options);
+        let result = if options.pretty {
+            serde_json::to_string_pretty(&serializable)
+        } else {
+            serde_json::to_string(&serializable)
+        };
+
+        match result {
             Ok(result) => Ok(Str(result.into())),
             Err(e) => external_error!("json.to_string: {}", e),
-        },
-        _ => external_error!("json.to_string expects a single argument"),
+        }
     });
 
     result
@@ -11,10 +11,13 @@ fn run_script(script: &str, path: Option<PathBuf>, should_fail_at_runtime: bool)
     koto.set_script_path(path);
 
     let mut prelude = koto.context().prelude.clone();
+    prelude.add_map("csv", koto_csv::make_module());
     prelude.add_map("json", koto_json::make_module());
     prelude.add_map("random", koto_random::make_module());
+    prelude.add_map("regex", koto_regex::make_module());
     prelude.add_map("tempfile", koto_tempfile::make_module());
     prelude.add_map("toml", koto_toml::make_module());
+    prelude.add_map("yaml", koto_yaml::make_module());
 
     match koto.compile(&script) {
         Ok(_) => match koto.run() {
@@ -61,8 +64,11 @@ macro_rules! lib_test {
 mod lib_tests {
     use super::*;
 
+    lib_test!(csv);
     lib_test!(json);
     lib_test!(random);
+    lib_test!(regex);
     lib_test!(tempfile);
     lib_test!(toml);
+    lib_test!(yaml);
 }
@@ -0,0 +1,259 @@
+//! A Koto language module for working with regular expressions
+
+use {
+    koto_derive::koto_impl,
+    koto_runtime::{
+        external_error, type_as_string, visit_external_value, RuntimeFunction, RuntimeResult,
+        Value, ValueIterator, ValueIteratorOutput, ValueMap, Vm,
+    },
+};
+
+pub fn make_module() -> ValueMap {
+    use Value::*;
+
+    let mut result = ValueMap::new();
+
+    result.add_fn("new", |vm, args| match vm.get_args(args) {
+        [Str(pattern)] => match regex::Regex::new(pattern) {
+            Ok(regex) => Ok(Map(Regex(regex).make_koto_map())),
+            Err(e) => external_error!("regex.new: {}", e),
+        },
+        [unexpected] => external_error!(
+            "regex.new: Expected a String as argument, found '{}'",
+            type_as_string(unexpected)
+        ),
+        _ => external_error!("regex.new: Expected a String as argument"),
+    });
+
+    result
+}
+
+#[derive(Debug)]
+struct Regex(regex::Regex);
+
+#[koto_impl(name = "Regex")]
+impl Regex {
+    #[koto_method]
+    fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+
+    #[koto_method]
+    fn find(&self, text: &str) -> Value {
+        self.0.find(text).map_or(Value::Empty, make_match)
+    }
+
+    #[koto_method]
+    fn find_all(&self, text: &str) -> Value {
+        let text = text.to_string();
+        let mut matches = Matches::new(self.0.clone());
+
+        Value::Iterator(ValueIterator::make_external(move || {
+            matches
+                .next_match(&text)
+                .map(|m| Ok(ValueIteratorOutput::Value(make_match(m))))
+        }))
+    }
+
+    #[koto_method]
+    fn captures(&self, text: &str) -> Value {
+        self.0
+            .captures(text)
+            .map_or(Value::Empty, |captures| make_captures(&self.0, &captures))
+    }
+
+    #[koto_method]
+    fn split(&self, text: &str) -> Value {
+        let text = text.to_string();
+        let mut matches = Matches::new(self.0.clone());
+        let mut start = Some(0);
+
+        Value::Iterator(ValueIterator::make_external(move || {
+            let piece_start = start?;
+            let piece = match matches.next_match(&text) {
+                Some(m) => {
+                    start = Some(m.end());
+                    &text[piece_start..m.start()]
+                }
+                None => {
+                    start = None;
+                    &text[piece_start..]
+                }
+            };
+            Some(Ok(ValueIteratorOutput::Value(Value::Str(piece.into()))))
+        }))
+    }
+}
+
+impl Regex {
+    // The replace functions need access to the VM to call replacement functions,
+    // so they're added to the map separately from the koto_impl methods.
+    fn make_koto_map(self) -> ValueMap {
+        let mut result = self.make_value_map();
+
+        result.add_instance_fn("replace", |vm, args| {
+            let args = vm.get_args(args).to_vec();
+            replace(vm, &args, "replace", 1)
+        });
+        result.add_instance_fn("replace_all", |vm, args| {
+            let args = vm.get_args(args).to_vec();
+            replace(vm, &args, "replace_all", 0)
+        });
+
+        result
+    }
+}
+
+// Iterates over the non-overlapping matches in a string
+//
+// The matching rules follow regex::Regex::find_iter, with empty matches that immediately follow a
+// previous match being skipped. The matches can't borrow the text, so the position in the text
+// is tracked between calls.
+struct Matches {
+    regex: regex::Regex,
+    position: usize,
+    last_match_end: Option<usize>,
+}
+
+impl Matches {
+    fn new(regex: regex::Regex) -> Self {
+        Self {
+            regex,
+            position: 0,
+            last_match_end: None,
+        }
+    }
+
+    fn next_match<'t>(&mut self, text: &'t str) -> Option<regex::Match<'t>> {
+        loop {
+            if self.position > text.len() {
+                return None;
+            }
+
+            let m = self.regex.find_at(text, self.position)?;
+
+            if m.start() == m.end() {
+                self.position = next_char_boundary(text, m.end());
+                if self.last_match_end == Some(m.end()) {
+                    continue;
+                }
+            } else {
+                self.position = m.end();
+            }
+
+            self.last_match_end = Some(m.end());
+            return Some(m);
+        }
+    }
+}
+
+fn next_char_boundary(text: &str, index: usize) -> usize {
+    index + text[index..].chars().next().map_or(1, char::len_utf8)
+}
+
+// Matches are represented as maps containing the matched text, and its start and end positions
+//
+// The positions are byte indices into the searched string.
+fn make_match(m: regex::Match) -> Value {
+    let mut result = ValueMap::with_capacity(3);
+    result.add_value("text", Value::Str(m.as_str().into()));
+    result.add_value("start", Value::Number(m.start().into()));
+    result.add_value("end", Value::Number(m.end().into()));
+    Value::Map(result)
+}
+
+// Captures are represented as maps, with an entry for each group's index, and an additional entry
+// for each named group
+//
+// The whole match is at index 0, and groups that didn't participate in the match are empty.
+fn make_captures(regex: &regex::Regex, captures: &regex::Captures) -> Value {
+    let mut result = ValueMap::with_capacity(captures.len());
+
+    for (i, name) in regex.capture_names().enumerate() {
+        let group = captures
+            .get(i)
+            .map_or(Value::Empty, |m| Value::Str(m.as_str().into()));
+
+        result.insert(Value::Number(i.into()), group.clone());
+        if let Some(name) = name {
+            result.add_value(name, group);
+        }
+    }
+
+    Value::Map(result)
+}
+
+// Replaces matches in a string, either with a replacement string or with the results of calling
+// a replacement function
+//
+// A limit of 0 causes all matches to be replaced.
+fn replace(vm: &mut Vm, args: &[Value], fn_name: &str, limit: usize) -> RuntimeResult {
+    use Value::*;
+
+    let (instance, text, replacement) = match args {
+        [Map(instance), Str(text), replacement @ Str(_)]
+        | [Map(instance), Str(text), replacement @ Function(_)] => (instance, text, replacement),
+        [Map(_), unexpected_text, unexpected_replacement] => {
+            return external_error!(
+                "Regex.{}: Expected a String and a String or Function as arguments, \
+                 found '{}' and '{}'",
+                fn_name,
+                type_as_string(unexpected_text),
+                type_as_string(unexpected_replacement)
+            )
+        }
+        _ => {
+            return external_error!(
+                "Regex.{}: Expected a String and a String or Function as arguments",
+                fn_name
+            )
+        }
+    };
+
+    // The regex is cloned so that the instance isn't locked while replacement functions are
+    // being called.
+    let mut regex = None;
+    visit_external_value(instance, |instance: &mut Regex| {
+        regex = Some(instance.0.clone());
+        Ok(Empty)
+    })?;
+    let regex = regex.unwrap();
+
+    let result = match replacement {
+        Str(replacement) => regex
+            .replacen(text, limit, replacement.as_str())
+            .to_string(),
+        Function(f) => replace_with_function(vm, &regex, text, f, limit)?,
+        _ => unreachable!(),
+    };
+
+    Ok(Str(result.into()))
+}
+
+fn replace_with_function(
+    vm: &mut Vm,
+    regex: &regex::Regex,
+    text: &str,
+    f: &RuntimeFunction,
+    limit: usize,
+) -> Result<String, koto_runtime::Error> {
+    let mut vm = vm.spawn_shared_vm();
+    let mut result = String::with_capacity(text.len());
+    let mut last_end = 0;
+
+    let limit = if limit == 0 { usize::MAX } else { limit };
+    for captures in regex.captures_iter(text).take(limit) {
+        let m = captures.get(0).unwrap();
+        result.push_str(&text[last_end..m.start()]);
+
+        match vm.run_function(f, &[make_captures(regex, &captures)])? {
+            Value::Str(s) => result.push_str(&s),
+            other => result.push_str(&vm.value_to_string(&other)?),
+        }
+
+        last_end = m.end();
+    }
+
+    result.push_str(&text[last_end..]);
+    Ok(result)
+}
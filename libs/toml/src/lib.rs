@@ -1,72 +1,79 @@
 //! A Koto language module for working with TOML data
 
 use {
-    koto_runtime::{external_error, Value, ValueList, ValueMap, ValueVec},
-    koto_serialize::SerializableValue,
-    toml::Value as Toml,
+    koto_runtime::{external_error, Value, ValueMap},
+    koto_serialize::{DeserializableValue, SerializableValue, SerializeOptions},
 };
 
-fn toml_to_koto_value(value: &Toml) -> Result<Value, String> {
-    let result = match value {
-        Toml::Boolean(b) => Value::Bool(*b),
-        Toml::Integer(i) => {
-            let result = *i as f64;
-            if result as i64 == *i {
-                Value::Number(result)
+// The toml crate deserializes datetimes as maps containing a single entry with this key,
+// with the datetime's string representation as the entry's value.
+const DATETIME_FIELD: &str = "$__toml_private_datetime";
+
+// Replaces the maps that represent datetimes with the datetimes' strings
+fn unwrap_datetimes(value: &mut Value) {
+    match value {
+        Value::Map(map) => {
+            let datetime = if map.len() == 1 {
+                map.data().get_with_string(DATETIME_FIELD).cloned()
             } else {
-                return Err(format!("Number is out of range for an f64: {}", i));
-            }
-        }
-        Toml::Float(f) => Value::Number(*f),
-        Toml::String(s) => Value::Str(s.as_str().into()),
-        Toml::Array(a) => {
-            match a
-                .iter()
-                .map(|entry| toml_to_koto_value(entry))
-                .collect::<Result<ValueVec, String>>()
-            {
-                Ok(result) => Value::List(ValueList::with_data(result)),
-                Err(e) => return Err(e),
-            }
-        }
-        Toml::Table(o) => {
-            let mut map = ValueMap::with_capacity(o.len());
-            for (key, value) in o.iter() {
-                map.add_value(key, toml_to_koto_value(value)?);
+                None
+            };
+
+            match datetime {
+                Some(datetime) => *value = datetime,
+                None => map.data_mut().values_mut().for_each(unwrap_datetimes),
             }
-            Value::Map(map)
         }
-        Toml::Datetime(dt) => Value::Str(dt.to_string().into()),
-    };
-
-    Ok(result)
+        Value::List(list) => list.data_mut().iter_mut().for_each(unwrap_datetimes),
+        _ => {}
+    }
 }
 
+// The options that can be passed to to_string
+const SUPPORTED_OPTIONS: &[&str] = &["pretty", "sort_keys"];
+
 pub fn make_module() -> ValueMap {
     use Value::*;
 
     let mut result = ValueMap::new();
 
     result.add_fn("from_string", |vm, args| match vm.get_args(args) {
-        [Str(s)] => match toml::from_str(s) {
-            Ok(toml) => match toml_to_koto_value(&toml) {
-                Ok(result) => Ok(result),
-                Err(e) => external_error!("toml.from_string: Error while parsing input: {}", e),
-            },
-            Err(e) => external_error!(
-                "toml.from_string: Error while parsing input: {}",
-                e.to_string()
-            ),
+        [Str(s)] => match toml::from_str::<DeserializableValue>(s) {
+            Ok(DeserializableValue(mut result)) => {
+                unwrap_datetimes(&mut result);
+                Ok(result)
+            }
+            Err(e) => external_error!("toml.from_string: Error while parsing input: {}", e),
         },
         _ => external_error!("toml.from_string expects a string as argument"),
     });
 
-    result.add_fn("to_string", |vm, args| match vm.get_args(args) {
-        [value] => match toml::to_string_pretty(&SerializableValue(value)) {
+    result.add_fn("to_string", |vm, args| {
+        let (value, options) = match vm.get_args(args) {
+            [value, options @ ..] => {
+                match SerializeOptions::from_args("toml.to_string", options, SUPPORTED_OPTIONS) {
+                    Ok(options) => (value, options),
+                    Err(e) => return external_error!(e),
+                }
+            }
+            _ => {
+                return external_error!(
+                    "toml.to_string expects a value, and an optional map of options as arguments"
+                )
+            }
+        };
+
+        let serializable = SerializableValue::with_options(value, options);
+        let result = if options.pretty {
+            toml::to_string_pretty(&serializable)
+        } else {
+            toml::to_string(&serializable)
+        };
+
+        match result {
             Ok(result) => Ok(Str(result.into())),
             Err(e) => external_error!("toml.to_string: {}", e),
-        },
-        _ => external_error!("toml.to_string expects a single argument"),
+        }
     });
 
     result
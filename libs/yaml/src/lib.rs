@@ -0,0 +1,99 @@
+//! A Koto language module for working with YAML data
+
+use {
+    koto_runtime::{external_error, Value, ValueList, ValueMap, ValueVec},
+    koto_serialize::{DeserializableValue, SerializableValue, SerializeOptions},
+    serde::Deserialize,
+};
+
+// The options that can be passed to to_string and to_string_all
+//
+// serde_yaml doesn't have a compact output format, so the `pretty` option isn't supported.
+const SUPPORTED_OPTIONS: &[&str] = &["sort_keys"];
+
+fn value_to_yaml(value: &Value, options: SerializeOptions) -> Result<String, String> {
+    serde_yaml::to_string(&SerializableValue::with_options(value, options))
+        .map_err(|e| e.to_string())
+}
+
+pub fn make_module() -> ValueMap {
+    use Value::*;
+
+    let mut result = ValueMap::new();
+
+    result.add_fn("from_string", |vm, args| match vm.get_args(args) {
+        [Str(s)] => match serde_yaml::from_str::<DeserializableValue>(s) {
+            Ok(result) => Ok(result.0),
+            Err(e) => external_error!("yaml.from_string: Error while parsing input: {}", e),
+        },
+        _ => external_error!("yaml.from_string expects a string as argument"),
+    });
+
+    result.add_fn("from_string_all", |vm, args| match vm.get_args(args) {
+        [Str(s)] => {
+            let mut documents = ValueVec::new();
+            for document in serde_yaml::Deserializer::from_str(s) {
+                match DeserializableValue::deserialize(document) {
+                    Ok(value) => documents.push(value.0),
+                    Err(e) => {
+                        return external_error!(
+                            "yaml.from_string_all: Error while parsing input: {}",
+                            e
+                        )
+                    }
+                }
+            }
+            Ok(List(ValueList::with_data(documents)))
+        }
+        _ => external_error!("yaml.from_string_all expects a string as argument"),
+    });
+
+    result.add_fn("to_string", |vm, args| match vm.get_args(args) {
+        [value, options @ ..] => {
+            let options =
+                match SerializeOptions::from_args("yaml.to_string", options, SUPPORTED_OPTIONS) {
+                    Ok(options) => options,
+                    Err(e) => return external_error!(e),
+                };
+
+            match value_to_yaml(value, options) {
+                Ok(result) => Ok(Str(result.into())),
+                Err(e) => external_error!("yaml.to_string: {}", e),
+            }
+        }
+        _ => external_error!(
+            "yaml.to_string expects a value, and an optional map of options as arguments"
+        ),
+    });
+
+    result.add_fn("to_string_all", |vm, args| {
+        let (documents, options) = match vm.get_args(args) {
+            [List(documents), options @ ..] => (documents.data().to_vec(), options),
+            [Tuple(documents), options @ ..] => (documents.data().to_vec(), options),
+            _ => {
+                return external_error!(
+                    "yaml.to_string_all expects a List or Tuple of documents, \
+                     and an optional map of options as arguments"
+                )
+            }
+        };
+
+        let options =
+            match SerializeOptions::from_args("yaml.to_string_all", options, SUPPORTED_OPTIONS) {
+                Ok(options) => options,
+                Err(e) => return external_error!(e),
+            };
+
+        let mut result = String::new();
+        for document in documents.iter() {
+            match value_to_yaml(document, options) {
+                Ok(document) => result.push_str(&document),
+                Err(e) => return external_error!("yaml.to_string_all: {}", e),
+            }
+        }
+
+        Ok(Str(result.into()))
+    });
+
+    result
+}
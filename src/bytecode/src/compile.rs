@@ -1,8 +1,8 @@
 use {
-    crate::{DebugInfo, FunctionFlags, Op},
+    crate::{DebugInfo, FunctionDebugInfo, FunctionFlags, Op},
     koto_parser::{
         AssignOp, AssignTarget, Ast, AstFor, AstIf, AstIndex, AstNode, AstOp, AstTry,
-        ConstantIndex, Function, LookupNode, MatchArm, Node, Scope, Span,
+        ConstantIndex, Function, LookupNode, MatchArm, Node, Scope, Span, StringNode,
     },
     smallvec::SmallVec,
     std::convert::TryFrom,
@@ -310,6 +310,8 @@ pub struct Compiler {
     debug_info: DebugInfo,
     frame_stack: Vec<Frame>,
     span_stack: Vec<Span>,
+    // The name to associate with the next compiled function, see compile_function
+    function_name: Option<ConstantIndex>,
     settings: CompilerSettings,
 }
 
@@ -380,16 +382,17 @@ impl Compiler {
                 }
                 result
             }
-            Node::Number(constant) => {
+            Node::Float(constant) => {
                 let result = self.get_result_register(result_register)?;
                 if let Some(result) = result {
-                    let constant = *constant;
-                    if constant <= u8::MAX as u32 {
-                        self.push_op(LoadNumber, &[result.register, constant as u8]);
-                    } else {
-                        self.push_op(LoadNumberLong, &[result.register]);
-                        self.push_bytes(&constant.to_le_bytes());
-                    }
+                    self.load_constant(result.register, *constant, LoadFloat, LoadFloatLong);
+                }
+                result
+            }
+            Node::Int(constant) => {
+                let result = self.get_result_register(result_register)?;
+                if let Some(result) = result {
+                    self.load_constant(result.register, *constant, LoadInt, LoadIntLong);
                 }
                 result
             }
@@ -400,6 +403,9 @@ impl Compiler {
                 }
                 result
             }
+            Node::InterpolatedString(nodes) => {
+                self.compile_interpolated_string(result_register, nodes, ast)?
+            }
             Node::Num2(elements) => self.compile_make_num2(result_register, &elements, ast)?,
             Node::Num4(elements) => self.compile_make_num4(result_register, &elements, ast)?,
             Node::List(elements) => self.compile_make_list(result_register, &elements, ast)?,
@@ -642,6 +648,25 @@ impl Compiler {
                     }
                 }
             }
+            Node::Throw(expression) => {
+                let expression_register = self
+                    .compile_node(ResultRegister::Any, ast.node(*expression), ast)?
+                    .unwrap();
+
+                self.push_op(Throw, &[expression_register.register]);
+
+                if expression_register.is_temporary {
+                    self.pop_register()?;
+                }
+
+                // Execution doesn't continue after a throw, but a result register might still be
+                // expected, e.g. when the throw is used as a match arm.
+                let result = self.get_result_register(result_register)?;
+                if let Some(result) = result {
+                    self.push_op(SetEmpty, &[result.register]);
+                }
+                result
+            }
             Node::Yield(expression) => {
                 let result = self.get_result_register(result_register)?;
 
@@ -652,7 +677,10 @@ impl Compiler {
                 self.push_op(Yield, &[expression_register.register]);
 
                 if let Some(result) = result {
+                    // The yield expression evaluates to the yielded value, unless the generator
+                    // was resumed as a coroutine, in which case the resume value is loaded.
                     self.push_op(Copy, &[result.register, expression_register.register]);
+                    self.push_op(LoadResumeValue, &[result.register]);
                 }
 
                 if expression_register.is_temporary {
@@ -826,6 +854,14 @@ impl Compiler {
             None => ResultRegister::Any,
         };
 
+        if let (AssignOp::Equal, Node::Id(id), Node::Function(_)) = (
+            op,
+            &ast.node(target.target_index).node,
+            &ast.node(expression).node,
+        ) {
+            self.function_name = Some(*id);
+        }
+
         let value_register = match op {
             AssignOp::Equal => {
                 self.compile_node(value_result_register, ast.node(expression), ast)?
@@ -1199,7 +1235,7 @@ impl Compiler {
                 let import_register = self.assign_local_register(*import_id)?;
 
                 for id in item.iter() {
-                    self.compile_access(import_register, access_register, *id);
+                    self.compile_import_nested(import_register, access_register, *id);
                     access_register = import_register;
                 }
 
@@ -1243,7 +1279,7 @@ impl Compiler {
                 self.compile_import_id(result_register, *import_id);
 
                 for nested_item in nested.iter() {
-                    self.compile_access(result_register, result_register, *nested_item);
+                    self.compile_import_nested(result_register, result_register, *nested_item);
                 }
             }
         }
@@ -1271,6 +1307,22 @@ impl Compiler {
         }
     }
 
+    fn compile_import_nested(
+        &mut self,
+        result_register: u8,
+        module_register: u8,
+        id: ConstantIndex,
+    ) {
+        use Op::*;
+
+        if id <= u8::MAX as u32 {
+            self.push_op(ImportNested, &[result_register, module_register, id as u8]);
+        } else {
+            self.push_op(ImportNestedLong, &[result_register, module_register]);
+            self.push_bytes(&id.to_le_bytes());
+        }
+    }
+
     fn compile_try_expression(
         &mut self,
         result_register: ResultRegister,
@@ -1582,6 +1634,61 @@ impl Compiler {
         Ok(result)
     }
 
+    fn compile_interpolated_string(
+        &mut self,
+        result_register: ResultRegister,
+        nodes: &[StringNode],
+        ast: &Ast,
+    ) -> CompileNodeResult {
+        if nodes.len() > u8::MAX as usize {
+            return compiler_error!(
+                self,
+                "compile_interpolated_string: too many string sections: {}",
+                nodes.len()
+            );
+        }
+
+        let result = match self.get_result_register(result_register)? {
+            Some(result) => {
+                let stack_count = self.frame().register_stack.len();
+
+                for node in nodes.iter() {
+                    let node_register = self.push_register()?;
+                    match node {
+                        StringNode::Literal(constant) => self.load_string(node_register, *constant),
+                        StringNode::Expression(expression) => {
+                            self.compile_node(
+                                ResultRegister::Fixed(node_register),
+                                ast.node(*expression),
+                                ast,
+                            )?;
+                        }
+                    }
+                }
+
+                let start_register = self.peek_register(nodes.len() - 1)?;
+                self.push_op(
+                    Op::StringConcat,
+                    &[result.register, start_register, nodes.len() as u8],
+                );
+
+                self.truncate_register_stack(stack_count)?;
+                Some(result)
+            }
+            None => {
+                // Compile the expressions for side-effects
+                for node in nodes.iter() {
+                    if let StringNode::Expression(expression) = node {
+                        self.compile_node(ResultRegister::None, ast.node(*expression), ast)?;
+                    }
+                }
+                None
+            }
+        };
+
+        Ok(result)
+    }
+
     fn compile_make_num2(
         &mut self,
         result_register: ResultRegister,
@@ -1826,6 +1933,9 @@ impl Compiler {
                     let value = match maybe_value_node {
                         Some(value_node) => {
                             let value_node = ast.node(*value_node);
+                            if matches!(value_node.node, Node::Function(_)) {
+                                self.function_name = Some(*key);
+                            }
                             self.compile_node(ResultRegister::Any, value_node, ast)?
                                 .unwrap()
                         }
@@ -1871,6 +1981,8 @@ impl Compiler {
     ) -> CompileNodeResult {
         use Op::*;
 
+        let function_name = self.function_name.take();
+
         let result = match self.get_result_register(result_register)? {
             Some(result) => {
                 let arg_count = match u8::try_from(function.args.len()) {
@@ -1908,6 +2020,7 @@ impl Compiler {
                 );
 
                 let function_size_ip = self.push_offset_placeholder();
+                let function_start_ip = self.bytes.len();
 
                 let local_count = match u8::try_from(function.local_count) {
                     Ok(x) => x,
@@ -1947,6 +2060,12 @@ impl Compiler {
 
                 self.update_offset_placeholder(function_size_ip);
 
+                self.debug_info.push_function(FunctionDebugInfo {
+                    start_ip: function_start_ip,
+                    end_ip: self.bytes.len(),
+                    name: function_name,
+                });
+
                 for (i, capture) in captures.iter().enumerate() {
                     if let Some(local_register) = self.frame().get_local_register(*capture) {
                         self.push_op(Capture, &[result.register, i as u8, local_register]);
@@ -2509,8 +2628,10 @@ impl Compiler {
                 | Node::BoolFalse
                 | Node::Number0
                 | Node::Number1
-                | Node::Number(_)
-                | Node::Str(_) => {
+                | Node::Float(_)
+                | Node::Int(_)
+                | Node::Str(_)
+                | Node::InterpolatedString(_) => {
                     let pattern = self.push_register()?;
                     self.compile_node(ResultRegister::Fixed(pattern), pattern_node, ast)?;
                     let comparison = self.push_register()?;
@@ -2925,12 +3046,14 @@ impl Compiler {
     }
 
     fn load_string(&mut self, result_register: u8, index: ConstantIndex) {
-        use Op::*;
+        self.load_constant(result_register, index, Op::LoadString, Op::LoadStringLong);
+    }
 
+    fn load_constant(&mut self, result_register: u8, index: ConstantIndex, op: Op, long_op: Op) {
         if index <= u8::MAX as u32 {
-            self.push_op(LoadString, &[result_register, index as u8]);
+            self.push_op(op, &[result_register, index as u8]);
         } else {
-            self.push_op(LoadStringLong, &[result_register]);
+            self.push_op(long_op, &[result_register]);
             self.push_bytes(&index.to_le_bytes());
         }
     }
@@ -26,9 +26,13 @@ pub enum Instruction {
     },
     SetNumber {
         register: u8,
-        value: f64,
+        value: i64,
     },
-    LoadNumber {
+    LoadFloat {
+        register: u8,
+        constant: ConstantIndex,
+    },
+    LoadInt {
         register: u8,
         constant: ConstantIndex,
     },
@@ -48,6 +52,11 @@ pub enum Instruction {
         register: u8,
         constant: ConstantIndex,
     },
+    ImportNested {
+        register: u8,
+        module: u8,
+        constant: ConstantIndex,
+    },
     MakeTuple {
         register: u8,
         start: u8,
@@ -300,6 +309,17 @@ pub enum Instruction {
         register: u8,
         constant: ConstantIndex,
     },
+    StringConcat {
+        register: u8,
+        start: u8,
+        count: u8,
+    },
+    Throw {
+        register: u8,
+    },
+    LoadResumeValue {
+        register: u8,
+    },
 }
 
 impl fmt::Display for Instruction {
@@ -312,11 +332,13 @@ impl fmt::Display for Instruction {
             SetEmpty { .. } => write!(f, "SetEmpty"),
             SetBool { .. } => write!(f, "SetBool"),
             SetNumber { .. } => write!(f, "SetNumber"),
-            LoadNumber { .. } => write!(f, "LoadNumber"),
+            LoadFloat { .. } => write!(f, "LoadFloat"),
+            LoadInt { .. } => write!(f, "LoadInt"),
             LoadString { .. } => write!(f, "LoadString"),
             LoadGlobal { .. } => write!(f, "LoadGlobal"),
             SetGlobal { .. } => write!(f, "SetGlobal"),
             Import { .. } => write!(f, "Import"),
+            ImportNested { .. } => write!(f, "ImportNested"),
             MakeTuple { .. } => write!(f, "MakeTuple"),
             MakeTempTuple { .. } => write!(f, "MakeTempTuple"),
             MakeList { .. } => write!(f, "MakeList"),
@@ -372,6 +394,9 @@ impl fmt::Display for Instruction {
             TryStart { .. } => write!(f, "TryStart"),
             TryEnd => write!(f, "TryEnd"),
             Debug { .. } => write!(f, "Debug"),
+            StringConcat { .. } => write!(f, "StringConcat"),
+            Throw { .. } => write!(f, "Throw"),
+            LoadResumeValue { .. } => write!(f, "LoadResumeValue"),
         }
     }
 }
@@ -392,11 +417,12 @@ impl fmt::Debug for Instruction {
             SetNumber { register, value } => {
                 write!(f, "SetNumber\tresult: {}\tvalue: {}", register, value)
             }
-            LoadNumber { register, constant } => write!(
-                f,
-                "LoadNumber\tresult: {}\tconstant: {}",
-                register, constant
-            ),
+            LoadFloat { register, constant } => {
+                write!(f, "LoadFloat\tresult: {}\tconstant: {}", register, constant)
+            }
+            LoadInt { register, constant } => {
+                write!(f, "LoadInt\t\tresult: {}\tconstant: {}", register, constant)
+            }
             LoadString { register, constant } => write!(
                 f,
                 "LoadString\tresult: {}\tconstant: {}",
@@ -413,6 +439,15 @@ impl fmt::Debug for Instruction {
             Import { register, constant } => {
                 write!(f, "Import\t\tresult: {}\tconstant: {}", register, constant)
             }
+            ImportNested {
+                register,
+                module,
+                constant,
+            } => write!(
+                f,
+                "ImportNested\tresult: {}\tmodule: {}\tconstant: {}",
+                register, module, constant
+            ),
             MakeTuple {
                 register,
                 start,
@@ -738,6 +773,19 @@ impl fmt::Debug for Instruction {
             Debug { register, constant } => {
                 write!(f, "Debug\t\tregister: {}\tconstant: {}", register, constant)
             }
+            StringConcat {
+                register,
+                start,
+                count,
+            } => write!(
+                f,
+                "StringConcat\tresult: {}\tstart: {}\tcount: {}",
+                register, start, count
+            ),
+            Throw { register } => write!(f, "Throw\t\tregister: {}", register),
+            LoadResumeValue { register } => {
+                write!(f, "LoadResumeValue\tregister: {}", register)
+            }
         }
     }
 }
@@ -839,21 +887,29 @@ impl Iterator for InstructionReader {
             }),
             Op::Set0 => Some(SetNumber {
                 register: get_byte!(),
-                value: 0.0,
+                value: 0,
             }),
             Op::Set1 => Some(SetNumber {
                 register: get_byte!(),
-                value: 1.0,
+                value: 1,
             }),
             Op::SetNumberU8 => Some(SetNumber {
                 register: get_byte!(),
-                value: get_byte!() as f64,
+                value: get_byte!() as i64,
             }),
-            Op::LoadNumber => Some(LoadNumber {
+            Op::LoadFloat => Some(LoadFloat {
                 register: get_byte!(),
                 constant: get_byte!() as ConstantIndex,
             }),
-            Op::LoadNumberLong => Some(LoadNumber {
+            Op::LoadFloatLong => Some(LoadFloat {
+                register: get_byte!(),
+                constant: get_u32!() as ConstantIndex,
+            }),
+            Op::LoadInt => Some(LoadInt {
+                register: get_byte!(),
+                constant: get_byte!() as ConstantIndex,
+            }),
+            Op::LoadIntLong => Some(LoadInt {
                 register: get_byte!(),
                 constant: get_u32!() as ConstantIndex,
             }),
@@ -889,6 +945,16 @@ impl Iterator for InstructionReader {
                 register: get_byte!(),
                 constant: get_u32!() as ConstantIndex,
             }),
+            Op::ImportNested => Some(ImportNested {
+                register: get_byte!(),
+                module: get_byte!(),
+                constant: get_byte!() as ConstantIndex,
+            }),
+            Op::ImportNestedLong => Some(ImportNested {
+                register: get_byte!(),
+                module: get_byte!(),
+                constant: get_u32!() as ConstantIndex,
+            }),
             Op::MakeTuple => Some(MakeTuple {
                 register: get_byte!(),
                 start: get_byte!(),
@@ -1172,6 +1238,17 @@ impl Iterator for InstructionReader {
                 register: get_byte!(),
                 constant: get_u32!() as ConstantIndex,
             }),
+            Op::StringConcat => Some(StringConcat {
+                register: get_byte!(),
+                start: get_byte!(),
+                count: get_byte!(),
+            }),
+            Op::Throw => Some(Throw {
+                register: get_byte!(),
+            }),
+            Op::LoadResumeValue => Some(LoadResumeValue {
+                register: get_byte!(),
+            }),
             _ => Some(Error {
                 message: format!("Unexpected opcode {:?} found at instruction {}", op, op_ip),
             }),
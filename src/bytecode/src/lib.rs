@@ -3,15 +3,16 @@
 //! Contains Koto's compiler and its bytecode operations
 
 use {
-    koto_parser::{ConstantPool, Span},
+    koto_parser::{ConstantIndex, ConstantPool, Span},
     std::{path::PathBuf, sync::Arc},
 };
 
 mod compile;
 mod instruction_reader;
 mod loader;
+mod serialize;
 
-pub use {compile::*, instruction_reader::*, loader::*};
+pub use {compile::*, instruction_reader::*, loader::*, serialize::*};
 
 /// The operation identifiers used in Koto bytecode
 ///
@@ -27,8 +28,10 @@ pub enum Op {
     Set0,             // register
     Set1,             // register
     SetNumberU8,      // register, number
-    LoadNumber,       // register, constant
-    LoadNumberLong,   // register, constant[4]
+    LoadFloat,        // register, constant
+    LoadFloatLong,    // register, constant[4]
+    LoadInt,          // register, constant
+    LoadIntLong,      // register, constant[4]
     LoadString,       // register, constant
     LoadStringLong,   // register, constant[4]
     LoadGlobal,       // register, constant
@@ -97,13 +100,11 @@ pub enum Op {
     TryStart,         // catch arg register, catch body offset[2]
     TryEnd,           //
     Debug,            // register, constant[4]
-    Unused78,
-    Unused79,
-    Unused80,
-    Unused81,
-    Unused82,
-    Unused83,
-    Unused84,
+    StringConcat,     // register, start register, count
+    Throw,            // register
+    LoadResumeValue,  // register
+    ImportNested,     // register, module register, constant
+    ImportNestedLong, // register, module register, constant[4]
     Unused85,
     Unused86,
     Unused87,
@@ -320,10 +321,24 @@ impl FunctionFlags {
     }
 }
 
+/// Debug information about a function that was compiled in a [Chunk]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionDebugInfo {
+    /// The ip of the first instruction in the function's body
+    pub start_ip: usize,
+    /// The ip following the last instruction in the function's body
+    pub end_ip: usize,
+    /// The constant index of the function's name
+    ///
+    /// Functions are named when they're assigned to an id or used as a map entry.
+    pub name: Option<ConstantIndex>,
+}
+
 /// Debug information for a Koto program
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct DebugInfo {
     source_map: Vec<(usize, Span)>,
+    functions: Vec<FunctionDebugInfo>,
     /// The source of the program that the debug info was derived from
     pub source: String,
 }
@@ -356,6 +371,20 @@ impl DebugInfo {
         }
         result
     }
+
+    fn push_function(&mut self, function: FunctionDebugInfo) {
+        self.functions.push(function);
+    }
+
+    /// Returns the innermost function that contains the given instruction pointer
+    ///
+    /// `None` is returned if the instruction is in the chunk's top level code.
+    pub fn get_function(&self, ip: usize) -> Option<&FunctionDebugInfo> {
+        self.functions
+            .iter()
+            .filter(|function| function.start_ip <= ip && ip < function.end_ip)
+            .max_by_key(|function| function.start_ip)
+    }
 }
 
 /// A compiled chunk of bytecode, along with its associated constants and metadata
@@ -400,6 +429,14 @@ impl Chunk {
             debug_info,
         }
     }
+
+    /// Returns the name of the innermost function that contains the given instruction pointer
+    pub fn function_name(&self, ip: usize) -> Option<&str> {
+        self.debug_info
+            .get_function(ip)
+            .and_then(|function| function.name)
+            .map(|name| self.constants.get_str(name))
+    }
 }
 
 /// Returns a [String] displaying the instructions contained in the compiled [Chunk]
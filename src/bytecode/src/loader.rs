@@ -1,7 +1,14 @@
 use {
-    crate::{Chunk, Compiler, CompilerError, CompilerSettings},
+    crate::{Chunk, Compiler, CompilerError, CompilerSettings, DeserializationError},
     koto_parser::{Parser, ParserError},
-    std::{collections::HashMap, fmt, path::PathBuf, sync::Arc},
+    std::{
+        collections::HashMap,
+        ffi::OsStr,
+        fmt,
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::SystemTime,
+    },
 };
 
 /// Errors that can be returned from [Loader] operations
@@ -9,6 +16,7 @@ use {
 pub enum LoaderError {
     ParserError(ParserError),
     CompilerError(CompilerError),
+    DeserializationError(DeserializationError),
     IoError(String),
 }
 
@@ -24,6 +32,12 @@ impl From<CompilerError> for LoaderError {
     }
 }
 
+impl From<DeserializationError> for LoaderError {
+    fn from(e: DeserializationError) -> Self {
+        Self::DeserializationError(e)
+    }
+}
+
 impl fmt::Display for LoaderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use LoaderError::*;
@@ -31,18 +45,45 @@ impl fmt::Display for LoaderError {
         match self {
             ParserError(e) => f.write_str(&e.to_string()),
             CompilerError(e) => f.write_str(&e.message),
+            DeserializationError(e) => f.write_str(&e.to_string()),
             IoError(e) => f.write_str(&e),
         }
     }
 }
 
+// A compiled module, along with the modification time of its file when it was compiled
+#[derive(Clone)]
+struct CachedModule {
+    chunk: Arc<Chunk>,
+    modified: Option<SystemTime>,
+}
+
 /// Helper for loading, compiling, and caching Koto modules
 #[derive(Clone, Default)]
 pub struct Loader {
-    chunks: HashMap<PathBuf, Arc<Chunk>>,
+    chunks: HashMap<PathBuf, CachedModule>,
+    allowed_module_dir: Option<PathBuf>,
+    module_paths: Vec<PathBuf>,
 }
 
 impl Loader {
+    /// Restricts [Loader::compile_module] to loading files from within the given directory
+    pub fn set_allowed_module_dir(&mut self, dir: Option<PathBuf>) {
+        self.allowed_module_dir = dir;
+    }
+
+    /// Sets the directories that are searched for modules by [Loader::compile_module]
+    ///
+    /// The search paths are checked in order, after the directory of the importing script.
+    pub fn set_module_paths(&mut self, paths: Vec<PathBuf>) {
+        self.module_paths = paths;
+    }
+
+    /// The directories that are searched for modules, see [Loader::set_module_paths]
+    pub fn module_paths(&self) -> &[PathBuf] {
+        &self.module_paths
+    }
+
     fn compile(
         &mut self,
         script: &str,
@@ -81,11 +122,45 @@ impl Loader {
         self.compile(script, script_path.clone(), CompilerSettings::default())
     }
 
+    /// Compiles the module with the given name
+    ///
+    /// The module is looked for in the directory of the provided path (or in the current
+    /// working directory if no path is provided), and then in each of the module search paths,
+    /// see [Loader::set_module_paths].
     pub fn compile_module(
         &mut self,
         name: &str,
         load_from_path: Option<PathBuf>,
     ) -> Result<(Arc<Chunk>, PathBuf), LoaderError> {
+        let module_path = self.find_module(name, load_from_path)?;
+        self.load_module(name, module_path)
+    }
+
+    /// Compiles a module that's nested within a package, e.g. `strings` in `import utils.strings`
+    ///
+    /// The package path is a module path that was returned by [Loader::compile_module].
+    /// Nested modules are looked for in the package's directory, which is either the directory
+    /// containing the package's main file, or a directory with a matching name next to the
+    /// package's file.
+    pub fn compile_nested_module(
+        &mut self,
+        name: &str,
+        package_path: &Path,
+    ) -> Result<(Arc<Chunk>, PathBuf), LoaderError> {
+        let module_path = self.find_nested_module(name, package_path)?;
+        self.load_module(name, module_path)
+    }
+
+    /// Finds the path of the module with the given name, without compiling it
+    ///
+    /// The module is looked for in the same places as in [Loader::compile_module]. The returned
+    /// path is the module's source or compiled file, or the directory of a package that doesn't
+    /// have a main file.
+    pub fn find_module(
+        &self,
+        name: &str,
+        load_from_path: Option<PathBuf>,
+    ) -> Result<PathBuf, LoaderError> {
         // Get either the directory of the provided path, or the current working directory
         let path = match &load_from_path {
             Some(path) => match path.canonicalize() {
@@ -106,45 +181,247 @@ impl Loader {
             },
         };
 
-        let mut load_module_from_path = |module_path: PathBuf| match self.chunks.get(&module_path) {
-            Some(chunk) => Ok((chunk.clone(), module_path.clone())),
-            None => match std::fs::read_to_string(&module_path) {
-                Ok(script) => {
-                    let chunk = self.compile(
-                        &script,
-                        Some(module_path.clone()),
-                        CompilerSettings::default(),
-                    )?;
-
-                    self.chunks.insert(module_path.clone(), chunk.clone());
-                    Ok((chunk, module_path))
+        let mut search_dirs = vec![path];
+        search_dirs.extend(
+            self.module_paths
+                .iter()
+                .map(|path| path.canonicalize().unwrap_or_else(|_| path.clone())),
+        );
+
+        find_module_in_dirs(name, &search_dirs)
+    }
+
+    /// Finds the path of a module that's nested within a package, without compiling it
+    ///
+    /// The module is looked for in the package's directory, see [Loader::compile_nested_module].
+    pub fn find_nested_module(
+        &self,
+        name: &str,
+        package_path: &Path,
+    ) -> Result<PathBuf, LoaderError> {
+        let package_dir = if package_path.is_dir() {
+            package_path.to_path_buf()
+        } else if package_path.file_stem() == Some(OsStr::new("main")) {
+            match package_path.parent() {
+                Some(parent_dir) => parent_dir.to_path_buf(),
+                None => {
+                    return Err(LoaderError::IoError(
+                        "Failed to get parent of package path".to_string(),
+                    ))
                 }
-                Err(_) => Err(LoaderError::IoError(format!(
-                    "File not found: {}",
-                    module_path.to_string_lossy()
-                ))),
-            },
+            }
+        } else {
+            package_path.with_extension("")
         };
 
-        let extension = "koto";
-        let named_path = path.join(name);
+        find_module_in_dirs(name, &[package_dir])
+    }
 
-        // first, check for a neighbouring file with a matching name
-        let module_path = named_path.with_extension(extension);
-        if module_path.exists() {
-            load_module_from_path(module_path)
-        } else {
-            // alternatively, check for a neighbouring directory with a matching name,
-            // containing a main file
-            let module_path = named_path.join("main").with_extension(extension);
-            if module_path.exists() {
-                load_module_from_path(module_path)
-            } else {
-                Err(LoaderError::IoError(format!(
-                    "Unable to find module '{}'",
+    fn load_module(
+        &mut self,
+        name: &str,
+        module_path: PathBuf,
+    ) -> Result<(Arc<Chunk>, PathBuf), LoaderError> {
+        if let Some(allowed_dir) = &self.allowed_module_dir {
+            if !is_path_in_dir(&module_path, allowed_dir) {
+                return Err(LoaderError::IoError(format!(
+                    "Module '{}' is outside of the allowed directory",
                     name
-                )))
+                )));
+            }
+        }
+
+        if let Some(cached) = self.chunks.get(&module_path) {
+            return Ok((cached.chunk.clone(), module_path));
+        }
+
+        let modified = modified_time(&module_path);
+        let chunk = self.compile_module_file(&module_path)?;
+        self.chunks.insert(
+            module_path.clone(),
+            CachedModule {
+                chunk: chunk.clone(),
+                modified,
+            },
+        );
+        Ok((chunk, module_path))
+    }
+
+    fn compile_module_file(&mut self, module_path: &Path) -> Result<Arc<Chunk>, LoaderError> {
+        let chunk = if module_path.is_dir() {
+            self.compile(
+                "",
+                Some(module_path.to_path_buf()),
+                CompilerSettings::default(),
+            )?
+        } else if is_compiled_path(module_path) {
+            match std::fs::read(module_path) {
+                Ok(data) => Arc::new(Chunk::deserialize(&data, Some(module_path.to_path_buf()))?),
+                Err(_) => return Err(file_not_found_error(module_path)),
+            }
+        } else {
+            match std::fs::read_to_string(module_path) {
+                Ok(script) => self.compile(
+                    &script,
+                    Some(module_path.to_path_buf()),
+                    CompilerSettings::default(),
+                )?,
+                Err(_) => return Err(file_not_found_error(module_path)),
+            }
+        };
+
+        Ok(chunk)
+    }
+
+    /// Returns the paths of cached modules whose files have changed since they were compiled
+    ///
+    /// Modules that have been modified can be recompiled with [Loader::recompile_module].
+    pub fn modified_modules(&self) -> Vec<PathBuf> {
+        let mut result = self
+            .chunks
+            .iter()
+            .filter(|(path, cached)| !path.is_dir() && modified_time(path) != cached.modified)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        result.sort();
+        result
+    }
+
+    /// Recompiles the module at the given path, replacing the module's cached chunk
+    ///
+    /// If compilation fails then the previously compiled chunk stays in the cache, and the module
+    /// won't be included in [Loader::modified_modules] until its file changes again.
+    pub fn recompile_module(&mut self, path: &Path) -> Result<(Arc<Chunk>, PathBuf), LoaderError> {
+        let module_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let modified = modified_time(&module_path);
+
+        match self.compile_module_file(&module_path) {
+            Ok(chunk) => {
+                self.chunks.insert(
+                    module_path.clone(),
+                    CachedModule {
+                        chunk: chunk.clone(),
+                        modified,
+                    },
+                );
+                Ok((chunk, module_path))
+            }
+            Err(error) => {
+                if let Some(cached) = self.chunks.get_mut(&module_path) {
+                    cached.modified = modified;
+                }
+                Err(error)
             }
         }
     }
+
+    /// Removes a module from the cache, so that it will be recompiled when it's next loaded
+    ///
+    /// Returns true if the module was in the cache.
+    pub fn invalidate_module(&mut self, path: &Path) -> bool {
+        let module_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.chunks.remove(&module_path).is_some()
+    }
+
+    /// Adds the modules that were cached by another loader, if they're not already cached
+    pub fn merge_cache(&mut self, other: &Loader) {
+        for (path, cached) in other.chunks.iter() {
+            if !self.chunks.contains_key(path) {
+                self.chunks.insert(path.clone(), cached.clone());
+            }
+        }
+    }
+
+    /// Loads a chunk that was previously compiled and serialized with [Chunk::serialize]
+    pub fn load_compiled(
+        &mut self,
+        data: &[u8],
+        script_path: &Option<PathBuf>,
+    ) -> Result<Arc<Chunk>, LoaderError> {
+        Ok(Arc::new(Chunk::deserialize(data, script_path.clone())?))
+    }
+}
+
+/// The file extension used for Koto scripts
+pub const SOURCE_EXTENSION: &str = "koto";
+
+/// The file extension used for compiled Koto chunks
+pub const COMPILED_EXTENSION: &str = "kotoc";
+
+// Checks that a path is within a directory, after resolving links and relative components
+fn is_path_in_dir(path: &Path, dir: &Path) -> bool {
+    match (path.canonicalize(), dir.canonicalize()) {
+        (Ok(path), Ok(dir)) => path.starts_with(dir),
+        _ => false,
+    }
+}
+
+fn is_compiled_path(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new(COMPILED_EXTENSION))
+}
+
+// Searches the directories in order for a module with the given name
+fn find_module_in_dirs(name: &str, search_dirs: &[PathBuf]) -> Result<PathBuf, LoaderError> {
+    for dir in search_dirs.iter() {
+        let named_path = dir.join(name);
+
+        // first, check for a file with a matching name
+        if let Some(module_path) = find_module_file(&named_path) {
+            return Ok(module_path);
+        }
+
+        // alternatively, check for a directory with a matching name, containing a main file
+        if let Some(module_path) = find_module_file(&named_path.join("main")) {
+            return Ok(module_path);
+        }
+
+        // finally, a directory without a main file is loaded as an empty package,
+        // which allows its nested modules to be imported
+        if named_path.is_dir() {
+            return Ok(named_path);
+        }
+    }
+
+    let searched = search_dirs
+        .iter()
+        .map(|dir| format!("  {}", dir.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Err(LoaderError::IoError(format!(
+        "Unable to find module '{}', searched in:\n{}",
+        name, searched
+    )))
+}
+
+// Finds the source or compiled file for a module, without extension.
+//
+// A compiled file is preferred, unless the source file has been modified more recently.
+fn find_module_file(path_without_extension: &Path) -> Option<PathBuf> {
+    let source_path = path_without_extension.with_extension(SOURCE_EXTENSION);
+    let compiled_path = path_without_extension.with_extension(COMPILED_EXTENSION);
+
+    let modified = |path: &Path| path.metadata().and_then(|metadata| metadata.modified());
+
+    match (source_path.exists(), compiled_path.exists()) {
+        (true, true) => match (modified(&source_path), modified(&compiled_path)) {
+            (Ok(source_time), Ok(compiled_time)) if source_time > compiled_time => {
+                Some(source_path)
+            }
+            _ => Some(compiled_path),
+        },
+        (true, false) => Some(source_path),
+        (false, true) => Some(compiled_path),
+        (false, false) => None,
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    path.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn file_not_found_error(path: &Path) -> LoaderError {
+    LoaderError::IoError(format!("File not found: {}", path.to_string_lossy()))
 }
@@ -0,0 +1,538 @@
+use {
+    crate::{Chunk, DebugInfo, FunctionDebugInfo, Instruction, InstructionReader},
+    koto_parser::{Constant, ConstantIndex, ConstantPool, ConstantPoolBuilder, Position, Span},
+    std::{collections::HashSet, convert::TryInto, fmt, path::PathBuf, sync::Arc},
+};
+
+/// The identifier at the start of serialized chunks
+const MAGIC: &[u8; 4] = b"KOTO";
+
+/// The version of the serialized chunk format
+///
+/// This needs to be incremented whenever the serialized layout or the bytecode format changes
+/// (e.g. when an [Op](crate::Op) is added or reordered).
+pub const BYTECODE_FORMAT_VERSION: u16 = 6;
+
+const CONSTANT_F64: u8 = 0;
+const CONSTANT_I64: u8 = 1;
+const CONSTANT_STR: u8 = 2;
+
+/// An error that can occur while deserializing a [Chunk]
+#[derive(Clone, Debug)]
+pub struct DeserializationError {
+    pub message: String,
+}
+
+impl DeserializationError {
+    fn new(message: &str) -> Self {
+        Self {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for DeserializationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid compiled chunk: {}", self.message)
+    }
+}
+
+impl Chunk {
+    /// Serializes the chunk into a versioned binary format
+    ///
+    /// The chunk's bytecode, constants, and debug info are included, the source path isn't.
+    /// See [Chunk::deserialize].
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.write_u16(BYTECODE_FORMAT_VERSION);
+
+        writer.write_u32(self.bytes.len() as u32);
+        writer.bytes.extend_from_slice(&self.bytes);
+
+        writer.write_u32(self.constants.len() as u32);
+        for constant in self.constants.iter() {
+            match constant {
+                Constant::F64(n) => {
+                    writer.bytes.push(CONSTANT_F64);
+                    writer.bytes.extend_from_slice(&n.to_le_bytes());
+                }
+                Constant::I64(n) => {
+                    writer.bytes.push(CONSTANT_I64);
+                    writer.bytes.extend_from_slice(&n.to_le_bytes());
+                }
+                Constant::Str(s) => {
+                    writer.bytes.push(CONSTANT_STR);
+                    writer.write_str(s);
+                }
+            }
+        }
+
+        writer.write_u32(self.debug_info.source_map.len() as u32);
+        for (ip, span) in self.debug_info.source_map.iter() {
+            writer.write_u32(*ip as u32);
+            writer.write_u32(span.start.line);
+            writer.write_u32(span.start.column);
+            writer.write_u32(span.end.line);
+            writer.write_u32(span.end.column);
+        }
+
+        writer.write_u32(self.debug_info.functions.len() as u32);
+        for function in self.debug_info.functions.iter() {
+            writer.write_u32(function.start_ip as u32);
+            writer.write_u32(function.end_ip as u32);
+            match function.name {
+                Some(name) => {
+                    writer.bytes.push(1);
+                    writer.write_u32(name);
+                }
+                None => writer.bytes.push(0),
+            }
+        }
+
+        writer.write_str(&self.debug_info.source);
+
+        writer.bytes
+    }
+
+    /// Deserializes a chunk that was produced by [Chunk::serialize]
+    ///
+    /// An error is returned if the data is invalid, or if it was serialized with a different
+    /// version of the bytecode format. The chunk's instructions are checked so that invalid
+    /// constant indices or jump targets are reported here rather than when the chunk is run.
+    pub fn deserialize(
+        data: &[u8],
+        source_path: Option<PathBuf>,
+    ) -> Result<Self, DeserializationError> {
+        let mut reader = Reader { data, position: 0 };
+
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(DeserializationError::new("missing header"));
+        }
+
+        let version = reader.read_u16()?;
+        if version != BYTECODE_FORMAT_VERSION {
+            return Err(DeserializationError {
+                message: format!(
+                    "unsupported format version {} (expected version {})",
+                    version, BYTECODE_FORMAT_VERSION
+                ),
+            });
+        }
+
+        let bytes_len = reader.read_u32()? as usize;
+        let bytes = reader.read_bytes(bytes_len)?.to_vec();
+
+        let constant_count = reader.read_u32()?;
+        let mut constants = ConstantPoolBuilder::new();
+        for _ in 0..constant_count {
+            match reader.read_u8()? {
+                CONSTANT_F64 => {
+                    let n = f64::from_le_bytes(reader.read_bytes(8)?.try_into().unwrap());
+                    constants.add_f64(n);
+                }
+                CONSTANT_I64 => {
+                    let n = i64::from_le_bytes(reader.read_bytes(8)?.try_into().unwrap());
+                    constants.add_i64(n);
+                }
+                CONSTANT_STR => {
+                    let s = reader.read_str()?;
+                    constants.add_string(s);
+                }
+                _ => return Err(DeserializationError::new("unexpected constant type")),
+            }
+        }
+        let constants: ConstantPool = constants.build();
+        if constants.len() != constant_count as usize {
+            return Err(DeserializationError::new("duplicate constants"));
+        }
+
+        let source_map_len = reader.read_u32()?;
+        let mut debug_info = DebugInfo::default();
+        for _ in 0..source_map_len {
+            let ip = reader.read_u32()? as usize;
+            let start = Position {
+                line: reader.read_u32()?,
+                column: reader.read_u32()?,
+            };
+            let end = Position {
+                line: reader.read_u32()?,
+                column: reader.read_u32()?,
+            };
+            debug_info.source_map.push((ip, Span { start, end }));
+        }
+
+        let function_count = reader.read_u32()?;
+        for _ in 0..function_count {
+            let start_ip = reader.read_u32()? as usize;
+            let end_ip = reader.read_u32()? as usize;
+            let name = match reader.read_u8()? {
+                0 => None,
+                1 => Some(reader.read_u32()?),
+                _ => return Err(DeserializationError::new("invalid function name")),
+            };
+            debug_info.functions.push(FunctionDebugInfo {
+                start_ip,
+                end_ip,
+                name,
+            });
+        }
+
+        debug_info.source = reader.read_str()?.to_string();
+
+        if reader.position != data.len() {
+            return Err(DeserializationError::new("unexpected trailing data"));
+        }
+
+        validate_functions(&debug_info.functions, &constants, bytes.len())?;
+
+        let chunk = Arc::new(Chunk::new(bytes, constants, source_path, debug_info));
+        validate_instructions(chunk.clone())?;
+        Ok(Arc::try_unwrap(chunk).expect("The chunk should no longer be shared"))
+    }
+}
+
+// Checks that each function's instruction range is within the chunk's bytes,
+// and that function names refer to string constants.
+fn validate_functions(
+    functions: &[FunctionDebugInfo],
+    constants: &ConstantPool,
+    bytes_len: usize,
+) -> Result<(), DeserializationError> {
+    for function in functions.iter() {
+        if function.start_ip > function.end_ip || function.end_ip > bytes_len {
+            return Err(DeserializationError {
+                message: format!(
+                    "invalid function range {}..{}",
+                    function.start_ip, function.end_ip
+                ),
+            });
+        }
+
+        if let Some(name) = function.name {
+            if !matches!(constants.get(name), Some(Constant::Str(_))) {
+                return Err(DeserializationError {
+                    message: format!("invalid function name at constant index {}", name),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Checks that each of the chunk's instructions can be decoded, that constants referred to by
+// instructions are in the constant pool with the expected types, and that jumps land on the
+// start of an instruction (or at the end of the chunk).
+fn validate_instructions(chunk: Arc<Chunk>) -> Result<(), DeserializationError> {
+    use Instruction::*;
+
+    let check_constant = |index: ConstantIndex, expected_type: &str| {
+        let found_type = match chunk.constants.get(index) {
+            Some(Constant::F64(_)) => "float",
+            Some(Constant::I64(_)) => "int",
+            Some(Constant::Str(_)) => "string",
+            None => {
+                return Err(DeserializationError {
+                    message: format!("constant index {} is out of bounds", index),
+                })
+            }
+        };
+
+        if found_type == expected_type {
+            Ok(())
+        } else {
+            Err(DeserializationError {
+                message: format!(
+                    "expected a {} constant at index {}, found a {}",
+                    expected_type, index, found_type
+                ),
+            })
+        }
+    };
+
+    let mut reader = InstructionReader::new(chunk.clone());
+    let mut instruction_ips = HashSet::new();
+    // The position of each instruction that jumps, along with the jump's target
+    let mut jumps = Vec::new();
+
+    loop {
+        let instruction_ip = reader.ip;
+        let instruction = match reader.next() {
+            Some(instruction) => instruction,
+            None => break,
+        };
+        instruction_ips.insert(instruction_ip);
+        let ip = reader.ip;
+
+        match instruction {
+            Error { message } => return Err(DeserializationError { message }),
+            LoadFloat { constant, .. } => check_constant(constant, "float")?,
+            LoadInt { constant, .. } => check_constant(constant, "int")?,
+            LoadString { constant, .. }
+            | LoadGlobal { constant, .. }
+            | Import { constant, .. }
+            | ImportNested { constant, .. }
+            | Debug { constant, .. }
+            | SetGlobal {
+                global: constant, ..
+            }
+            | MapInsert { key: constant, .. }
+            | Access { key: constant, .. } => check_constant(constant, "string")?,
+            Jump { offset }
+            | JumpIf { offset, .. }
+            | Function { size: offset, .. }
+            | IterNext {
+                jump_offset: offset,
+                ..
+            }
+            | IterNextTemp {
+                jump_offset: offset,
+                ..
+            }
+            | IterNextQuiet {
+                jump_offset: offset,
+                ..
+            }
+            | TryStart {
+                catch_offset: offset,
+                ..
+            } => jumps.push((instruction_ip, ip.checked_add(offset))),
+            JumpBack { offset } | JumpBackIf { offset, .. } => {
+                jumps.push((instruction_ip, ip.checked_sub(offset)))
+            }
+            _ => {}
+        }
+    }
+
+    for (instruction_ip, target) in jumps {
+        match target {
+            Some(target) if target == chunk.bytes.len() || instruction_ips.contains(&target) => {}
+            _ => {
+                return Err(DeserializationError {
+                    message: format!("invalid jump target for instruction at {}", instruction_ip),
+                })
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn write_u16(&mut self, n: u16) {
+        self.bytes.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.bytes.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_u32(s.len() as u32);
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], DeserializationError> {
+        match self.data.get(self.position..self.position + count) {
+            Some(result) => {
+                self.position += count;
+                Ok(result)
+            }
+            None => Err(DeserializationError::new("unexpected end of data")),
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DeserializationError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, DeserializationError> {
+        Ok(u16::from_le_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, DeserializationError> {
+        Ok(u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    fn read_str(&mut self) -> Result<&'a str, DeserializationError> {
+        let len = self.read_u32()? as usize;
+        std::str::from_utf8(self.read_bytes(len)?)
+            .map_err(|_| DeserializationError::new("invalid string data"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{Loader, Op},
+    };
+
+    fn compile(script: &str) -> Chunk {
+        let mut loader = Loader::default();
+        loader
+            .compile_script(script, &None)
+            .unwrap()
+            .as_ref()
+            .clone()
+    }
+
+    #[test]
+    fn round_trip() {
+        let chunk = compile(
+            "
+x = [1, 2.5, 9007199254740993, \"hello\"]
+f = |n| n * 42
+f x.size()
+",
+        );
+
+        let serialized = chunk.serialize();
+        let deserialized = Chunk::deserialize(&serialized, None).unwrap();
+
+        assert_eq!(chunk, deserialized);
+        assert_eq!(
+            chunk.debug_info.get_source_span(4),
+            deserialized.debug_info.get_source_span(4)
+        );
+
+        let function_ip = chunk.debug_info.functions[0].start_ip;
+        assert_eq!(deserialized.function_name(function_ip), Some("f"));
+    }
+
+    #[test]
+    fn mismatched_version() {
+        let mut serialized = compile("1 + 1").serialize();
+        serialized[MAGIC.len()] = serialized[MAGIC.len()].wrapping_add(1);
+        assert!(Chunk::deserialize(&serialized, None).is_err());
+    }
+
+    #[test]
+    fn truncated_data() {
+        let serialized = compile("1 + 1").serialize();
+        let truncated = &serialized[..serialized.len() - 1];
+        assert!(Chunk::deserialize(truncated, None).is_err());
+    }
+
+    fn serialize_bytes(bytes: Vec<u8>, constants: ConstantPool) -> Vec<u8> {
+        Chunk::new(bytes, constants, None, DebugInfo::default()).serialize()
+    }
+
+    #[test]
+    fn round_trip_with_jumps() {
+        let chunk = compile(
+            "
+x = 0
+for i in 0..10
+  if i % 2 == 0 then continue
+  x += i
+x
+",
+        );
+
+        let deserialized = Chunk::deserialize(&chunk.serialize(), None).unwrap();
+        assert_eq!(chunk, deserialized);
+    }
+
+    #[test]
+    fn constant_index_out_of_bounds() {
+        let serialized = serialize_bytes(
+            vec![Op::LoadString as u8, 0, 0, Op::Return as u8, 0],
+            ConstantPool::default(),
+        );
+        assert!(Chunk::deserialize(&serialized, None).is_err());
+    }
+
+    #[test]
+    fn mismatched_constant_type() {
+        let mut builder = ConstantPoolBuilder::new();
+        let index = builder.add_string("hello") as u8;
+        let serialized = serialize_bytes(
+            vec![Op::LoadFloat as u8, 0, index, Op::Return as u8, 0],
+            builder.build(),
+        );
+        assert!(Chunk::deserialize(&serialized, None).is_err());
+    }
+
+    #[test]
+    fn invalid_jump_target() {
+        // The jump lands in the middle of the Return instruction
+        let serialized = serialize_bytes(
+            vec![Op::Jump as u8, 1, 0, Op::Return as u8, 0],
+            ConstantPool::default(),
+        );
+        assert!(Chunk::deserialize(&serialized, None).is_err());
+
+        // Jumping to the end of the chunk is allowed
+        let serialized = serialize_bytes(
+            vec![Op::Jump as u8, 2, 0, Op::Return as u8, 0],
+            ConstantPool::default(),
+        );
+        assert!(Chunk::deserialize(&serialized, None).is_ok());
+    }
+
+    #[test]
+    fn invalid_function_debug_info() {
+        let serialize_with_function = |function: FunctionDebugInfo| {
+            let mut builder = ConstantPoolBuilder::new();
+            builder.add_f64(1.0);
+            let mut debug_info = DebugInfo::default();
+            debug_info.functions.push(function);
+            Chunk::new(vec![Op::Return as u8, 0], builder.build(), None, debug_info).serialize()
+        };
+
+        // The function's name isn't in the constant pool
+        let serialized = serialize_with_function(FunctionDebugInfo {
+            start_ip: 0,
+            end_ip: 2,
+            name: Some(1),
+        });
+        assert!(Chunk::deserialize(&serialized, None).is_err());
+
+        // The function's name isn't a string
+        let serialized = serialize_with_function(FunctionDebugInfo {
+            start_ip: 0,
+            end_ip: 2,
+            name: Some(0),
+        });
+        assert!(Chunk::deserialize(&serialized, None).is_err());
+
+        // The function ends after the end of the chunk
+        let serialized = serialize_with_function(FunctionDebugInfo {
+            start_ip: 0,
+            end_ip: 3,
+            name: None,
+        });
+        assert!(Chunk::deserialize(&serialized, None).is_err());
+
+        let serialized = serialize_with_function(FunctionDebugInfo {
+            start_ip: 0,
+            end_ip: 2,
+            name: None,
+        });
+        assert!(Chunk::deserialize(&serialized, None).is_ok());
+    }
+
+    #[test]
+    fn jump_back_out_of_bounds() {
+        let serialized = serialize_bytes(
+            vec![Op::JumpBack as u8, 10, 0, Op::Return as u8, 0],
+            ConstantPool::default(),
+        );
+        assert!(Chunk::deserialize(&serialized, None).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use {
+    koto::runtime::{Breakpoint, DebugCommand, DebugFrame, DebugPause, Debugger, PauseReason},
+    std::{
+        io::{self, BufRead, Write},
+        path::PathBuf,
+    },
+};
+
+const PROMPT: &str = "(debug) ";
+
+const HELP: &str = "\
+Commands:
+    c, continue            Continue running until the next breakpoint
+    s, step                Step to the next line, following function calls
+    n, next                Step to the next line in the current function
+    o, out                 Step out of the current function
+    b, break [path:]line   Add a breakpoint
+    d, delete [path:]line  Remove a breakpoint
+    l, list                List the breakpoints
+    bt, backtrace          Show the call stack
+    r, registers [frame]   Show the registers of a frame in the call stack
+    cap, captures [frame]  Show the captured values of a frame in the call stack
+    q, quit                Stop running the script
+    h, help                Show this help";
+
+/// Makes a debugger that's controlled by commands from stdin
+///
+/// The debugger starts paused so that breakpoints can be added before the script runs.
+pub fn make_debugger() -> Debugger {
+    let mut debugger = Debugger::new(|pause, breakpoints| {
+        print_location(pause);
+
+        let stdin = io::stdin();
+        loop {
+            print!("{}", PROMPT);
+            io::stdout().flush().unwrap();
+
+            let mut input = String::new();
+            match stdin.lock().read_line(&mut input) {
+                Ok(0) | Err(_) => return DebugCommand::Stop,
+                Ok(_) => {}
+            }
+
+            let mut words = input.split_whitespace();
+            let command = words.next().unwrap_or("");
+            let arg = words.next();
+
+            match command {
+                "c" | "continue" => return DebugCommand::Continue,
+                "s" | "step" => return DebugCommand::StepIn,
+                "n" | "next" => return DebugCommand::StepOver,
+                "o" | "out" => return DebugCommand::StepOut,
+                "q" | "quit" => return DebugCommand::Stop,
+                "b" | "break" => match arg.and_then(parse_breakpoint) {
+                    Some(breakpoint) => {
+                        if !breakpoints.contains(&breakpoint) {
+                            breakpoints.push(breakpoint);
+                        }
+                    }
+                    None => println!("Expected a breakpoint, e.g. 'break 12'"),
+                },
+                "d" | "delete" => match arg.and_then(parse_breakpoint) {
+                    Some(breakpoint) => {
+                        let count = breakpoints.len();
+                        breakpoints.retain(|b| *b != breakpoint);
+                        if breakpoints.len() == count {
+                            println!("Breakpoint not found");
+                        }
+                    }
+                    None => println!("Expected a breakpoint, e.g. 'delete 12'"),
+                },
+                "l" | "list" => {
+                    for breakpoint in breakpoints.iter() {
+                        match &breakpoint.path {
+                            Some(path) => println!("{}:{}", path.display(), breakpoint.line),
+                            None => println!("{}", breakpoint.line),
+                        }
+                    }
+                }
+                "bt" | "backtrace" => {
+                    for (i, frame) in pause.call_stack.iter().enumerate() {
+                        println!("#{} {}", i, frame_location(frame));
+                    }
+                }
+                "r" | "registers" | "cap" | "captures" => {
+                    let frame_index = arg.and_then(|arg| arg.parse::<usize>().ok()).unwrap_or(0);
+                    match pause.call_stack.get(frame_index) {
+                        Some(frame) => {
+                            let values = if command.starts_with('r') {
+                                &frame.registers
+                            } else {
+                                &frame.captures
+                            };
+                            for (i, value) in values.iter().enumerate() {
+                                println!("{}: {}", i, value);
+                            }
+                        }
+                        None => println!("Frame {} not found", frame_index),
+                    }
+                }
+                "h" | "help" => println!("{}", HELP),
+                "" => {}
+                other => println!("Unknown command '{}', use 'help' to see commands", other),
+            }
+        }
+    });
+
+    debugger.pause();
+    debugger
+}
+
+fn parse_breakpoint(arg: &str) -> Option<Breakpoint> {
+    match arg.rfind(':') {
+        Some(separator) => {
+            let path = PathBuf::from(&arg[..separator]);
+            let path = path.canonicalize().unwrap_or(path);
+            let line = arg[separator + 1..].parse().ok()?;
+            Some(Breakpoint::new(Some(path), line))
+        }
+        None => Some(Breakpoint::new(None, arg.parse().ok()?)),
+    }
+}
+
+fn frame_location(frame: &DebugFrame) -> String {
+    let path = match frame.source_path() {
+        Some(path) => path.display().to_string(),
+        None => "<script>".to_string(),
+    };
+    match frame.line() {
+        Some(line) => format!("{}:{}", path, line),
+        None => path,
+    }
+}
+
+fn print_location(pause: &DebugPause) {
+    if let Some(frame) = pause.call_stack.first() {
+        let reason = match pause.reason {
+            PauseReason::Breakpoint => "Breakpoint",
+            PauseReason::Step => "Step",
+        };
+        println!("{} - {}", reason, frame_location(frame));
+
+        if let Some(line) = frame.line() {
+            if let Some(source_line) = frame
+                .chunk
+                .debug_info
+                .source
+                .lines()
+                .nth(line.saturating_sub(1) as usize)
+            {
+                println!(" {} | {}", line, source_line);
+            }
+        }
+    }
+}
@@ -1,9 +1,16 @@
+mod debugger;
 mod repl;
 
 use {
-    koto::{Koto, KotoSettings},
+    koto::{bytecode::COMPILED_EXTENSION, Koto, KotoSettings},
     repl::Repl,
-    std::fs,
+    std::{
+        env,
+        ffi::OsStr,
+        fs,
+        path::{Path, PathBuf},
+        process,
+    },
 };
 
 #[cfg(all(jemalloc, not(target_env = "msvc")))]
@@ -25,17 +32,36 @@ FLAGS:
     -i, --show_instructions  Show compiled instructions annotated with source lines
     -b, --show_bytecode      Show the script's compiled bytecode
     -t, --tests              Run the script's tests before running the script
+    -c, --compile <output>   Compile the script to a .kotoc file rather than running it
+    -d, --debug              Run the script with the step debugger
+    -f, --format             Format the provided scripts rather than running them
+        --check              Check that the scripts are formatted, rather than formatting them
+        --line-width <width> The maximum line width for formatted scripts (default: 100)
     -h, --help               Prints help information
     -v, --version            Prints version information
 
 ARGS:
-    <script>     The koto script to run
-    <args>...    Arguments to pass into the script
+    <script>     The koto script to run, or a compiled .kotoc file
+    <args>...    Arguments to pass into the script, or additional scripts to format
+
+ENVIRONMENT:
+    KOTO_PATH    A list of directories to search for imported modules,
+                 separated in the same way as the platform's PATH variable
 ",
         version = version_string()
     )
 }
 
+// The directories listed in KOTO_PATH, which are searched for imported modules
+fn module_paths_from_env() -> Vec<PathBuf> {
+    match env::var_os("KOTO_PATH") {
+        Some(paths) => env::split_paths(&paths)
+            .filter(|path| !path.as_os_str().is_empty())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
 #[derive(Default)]
 struct KotoArgs {
     help: bool,
@@ -43,6 +69,11 @@ struct KotoArgs {
     run_tests: bool,
     show_bytecode: bool,
     show_annotated: bool,
+    debug: bool,
+    format: bool,
+    check: bool,
+    line_width: Option<usize>,
+    compile_output: Option<String>,
     script: Option<String>,
     script_args: Vec<String>,
 }
@@ -55,6 +86,15 @@ fn parse_arguments() -> Result<KotoArgs, String> {
     let run_tests = args.contains(["-t", "--tests"]);
     let show_bytecode = args.contains(["-b", "--show_bytecode"]);
     let show_annotated = args.contains(["-i", "--show_instructions"]);
+    let debug = args.contains(["-d", "--debug"]);
+    let format = args.contains(["-f", "--format"]);
+    let check = args.contains("--check");
+    let line_width = args
+        .opt_value_from_str("--line-width")
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
+    let compile_output = args
+        .opt_value_from_str(["-c", "--compile"])
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
 
     let script = args
         .subcommand()
@@ -78,11 +118,64 @@ fn parse_arguments() -> Result<KotoArgs, String> {
         run_tests,
         show_bytecode,
         show_annotated,
+        debug,
+        format,
+        check,
+        line_width,
+        compile_output,
         script,
         script_args,
     })
 }
 
+fn run_script(koto: &mut Koto, script_args: &[String]) {
+    if let Err(e) = koto.run_with_args(script_args) {
+        eprintln!("{}", e);
+    }
+}
+
+// Formats the scripts in place, or checks that they're formatted if `check` is true
+//
+// Returns true if all of the scripts were successfully formatted or checked.
+fn format_scripts(
+    script_paths: &[String],
+    check: bool,
+    settings: &koto_format::FormatSettings,
+) -> bool {
+    let mut success = true;
+
+    for script_path in script_paths.iter() {
+        let script = match fs::read_to_string(script_path) {
+            Ok(script) => script,
+            Err(e) => {
+                eprintln!("Unable to load '{}': {}", script_path, e);
+                success = false;
+                continue;
+            }
+        };
+
+        match koto_format::format_with_settings(&script, settings) {
+            Ok(formatted) if formatted == script => {}
+            Ok(_) if check => {
+                println!("'{}' is not formatted", script_path);
+                success = false;
+            }
+            Ok(formatted) => {
+                if let Err(e) = fs::write(script_path, formatted) {
+                    eprintln!("Error while writing to '{}': {}", script_path, e);
+                    success = false;
+                }
+            }
+            Err(e) => {
+                eprintln!("Error while formatting '{}': {}", script_path, e);
+                success = false;
+            }
+        }
+    }
+
+    success
+}
+
 fn main() {
     let args = match parse_arguments() {
         Ok(args) => args,
@@ -102,6 +195,23 @@ fn main() {
         return;
     }
 
+    if args.format {
+        let mut script_paths: Vec<String> = args.script.into_iter().collect();
+        script_paths.extend(args.script_args);
+
+        let mut format_settings = koto_format::FormatSettings::default();
+        if let Some(line_width) = args.line_width {
+            format_settings.line_width = line_width;
+        }
+
+        if script_paths.is_empty() {
+            println!("{}\n\nExpected scripts to format", help_string());
+        } else if !format_scripts(&script_paths, args.check, &format_settings) {
+            process::exit(1);
+        }
+        return;
+    }
+
     let mut settings = KotoSettings::default();
     settings.run_tests = args.run_tests;
     settings.show_bytecode = args.show_bytecode;
@@ -109,21 +219,45 @@ fn main() {
 
     if let Some(script_path) = args.script {
         let mut koto = Koto::with_settings(settings);
+        koto.set_module_paths(module_paths_from_env());
 
         let mut prelude = koto.context().prelude.clone();
+        prelude.add_map("csv", koto_csv::make_module());
         prelude.add_map("json", koto_json::make_module());
         prelude.add_map("random", koto_random::make_module());
+        prelude.add_map("regex", koto_regex::make_module());
         prelude.add_map("tempfile", koto_tempfile::make_module());
         prelude.add_map("toml", koto_toml::make_module());
+        prelude.add_map("yaml", koto_yaml::make_module());
+
+        if args.debug {
+            koto.set_debugger(Some(debugger::make_debugger()));
+        }
+
+        if Path::new(&script_path).extension() == Some(OsStr::new(COMPILED_EXTENSION)) {
+            let data = fs::read(&script_path).expect("Unable to load compiled script");
+            koto.set_script_path(Some(script_path.into()));
+            match koto.load_compiled(&data) {
+                Ok(_) => run_script(&mut koto, &args.script_args),
+                Err(e) => eprintln!("{}", e),
+            }
+            return;
+        }
 
         let script = fs::read_to_string(&script_path).expect("Unable to load script");
         koto.set_script_path(Some(script_path.into()));
         match koto.compile(&script) {
-            Ok(_) => match koto.run_with_args(&args.script_args) {
-                Ok(_) => {}
-                Err(e) => {
-                    eprintln!("{}", e);
+            Ok(chunk) => match args.compile_output {
+                Some(output_path) => {
+                    // The source is left out of the compiled chunk,
+                    // runtime errors will still refer to the source's line numbers.
+                    let mut chunk = chunk.as_ref().clone();
+                    chunk.debug_info.source.clear();
+                    if let Err(e) = fs::write(&output_path, chunk.serialize()) {
+                        eprintln!("Error while writing to '{}': {}", output_path, e);
+                    }
                 }
+                None => run_script(&mut koto, &args.script_args),
             },
             Err(e) => {
                 eprintln!("{}", koto.format_loader_error(e, &script));
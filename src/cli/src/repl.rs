@@ -32,12 +32,16 @@ impl Repl {
         settings.repl_mode = true;
 
         let mut koto = Koto::with_settings(settings);
+        koto.set_module_paths(crate::module_paths_from_env());
 
         let mut prelude = koto.context().prelude.clone();
+        prelude.add_map("csv", koto_csv::make_module());
         prelude.add_map("json", koto_json::make_module());
         prelude.add_map("random", koto_random::make_module());
+        prelude.add_map("regex", koto_regex::make_module());
         prelude.add_map("tempfile", koto_tempfile::make_module());
         prelude.add_map("toml", koto_toml::make_module());
+        prelude.add_map("yaml", koto_yaml::make_module());
 
         Self {
             koto,
@@ -231,7 +235,11 @@ impl Repl {
 
             match self.koto.compile(&input) {
                 Ok(_) => {
-                    match self.koto.run() {
+                    match self
+                        .koto
+                        .run()
+                        .and_then(|result| self.koto.value_to_string(&result))
+                    {
                         Ok(result) => writeln!(stdout, "{}", result).unwrap(),
                         Err(error) => self.print_error(stdout, tty, &error),
                     }
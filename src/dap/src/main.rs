@@ -0,0 +1,169 @@
+//! A Debug Adapter Protocol server for Koto scripts
+//!
+//! The server communicates with the client over stdin and stdout. Output from the script is
+//! forwarded to the client as `output` events.
+
+mod protocol;
+mod server;
+mod variables;
+
+use {
+    protocol::{read_message, MessageWriter},
+    serde_json::json,
+    server::Server,
+    std::{
+        fs::File,
+        io::{self, Read, Write},
+        sync::{Arc, Mutex},
+        thread,
+    },
+};
+
+fn main() {
+    let (protocol_output, script_output) = redirect_stdout();
+
+    let writer = Arc::new(Mutex::new(MessageWriter::new(protocol_output)));
+    if let Some(script_output) = script_output {
+        forward_script_output(script_output, writer.clone());
+    }
+
+    let mut server = Server::new(writer);
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+
+    loop {
+        match read_message(&mut input) {
+            Ok(Some(message)) => {
+                if message["type"] == "request" && !server.handle_request(&message) {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(error) => {
+                eprintln!("Error while reading message: {}", error);
+                break;
+            }
+        }
+    }
+
+    // The script may still be running, so exit without waiting for it to finish
+    std::process::exit(0);
+}
+
+// Scripts print directly to stdout, so stdout is redirected to a pipe to keep the script's
+// output separate from the protocol's output.
+//
+// Returns the output to use for the protocol, and the read end of the script's output pipe.
+#[cfg(unix)]
+fn redirect_stdout() -> (Box<dyn Write + Send>, Option<File>) {
+    use std::os::unix::io::FromRawFd;
+
+    // Safety: the file descriptors are checked for errors before being used,
+    // and ownership of each descriptor is passed to a single File.
+    unsafe {
+        let protocol_fd = libc::dup(libc::STDOUT_FILENO);
+        if protocol_fd < 0 {
+            return (Box::new(io::stdout()), None);
+        }
+
+        let mut pipe_fds = [0; 2];
+        if libc::pipe(pipe_fds.as_mut_ptr()) != 0 {
+            libc::close(protocol_fd);
+            return (Box::new(io::stdout()), None);
+        }
+
+        libc::dup2(pipe_fds[1], libc::STDOUT_FILENO);
+        libc::close(pipe_fds[1]);
+
+        (
+            Box::new(File::from_raw_fd(protocol_fd)),
+            Some(File::from_raw_fd(pipe_fds[0])),
+        )
+    }
+}
+
+// On other platforms the script's output isn't redirected,
+// so scripts that print to stdout will interfere with the protocol.
+#[cfg(not(unix))]
+fn redirect_stdout() -> (Box<dyn Write + Send>, Option<File>) {
+    (Box::new(io::stdout()), None)
+}
+
+fn forward_script_output(mut script_output: File, writer: Arc<Mutex<MessageWriter>>) {
+    thread::spawn(move || {
+        let mut buffer = [0; 1024];
+        // Bytes from the end of a read that are part of an incomplete UTF-8 character,
+        // which are held back until the rest of the character has been read.
+        let mut pending = Vec::new();
+
+        loop {
+            let count = match script_output.read(&mut buffer) {
+                Ok(count) if count > 0 => count,
+                _ => break,
+            };
+
+            pending.extend_from_slice(&buffer[..count]);
+            let complete = complete_utf8_len(&pending);
+            if complete > 0 {
+                send_script_output(&writer, &pending[..complete]);
+                pending.drain(..complete);
+            }
+        }
+
+        if !pending.is_empty() {
+            send_script_output(&writer, &pending);
+        }
+    });
+}
+
+fn send_script_output(writer: &Mutex<MessageWriter>, output: &[u8]) {
+    writer.lock().unwrap().send_event(
+        "output",
+        json!({
+            "category": "stdout",
+            "output": String::from_utf8_lossy(output),
+        }),
+    );
+}
+
+// Returns the length of the bytes, excluding an incomplete UTF-8 character at the end
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+    // A UTF-8 character is at most 4 bytes long, so only the last 3 bytes need to be checked
+    for (i, byte) in bytes.iter().enumerate().rev().take(3) {
+        let char_len = match byte {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            // Continuation bytes, or invalid bytes
+            _ => continue,
+        };
+
+        return if bytes.len() - i < char_len {
+            i
+        } else {
+            bytes.len()
+        };
+    }
+
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incomplete_utf8_at_end() {
+        let bytes = "aé€😀".as_bytes();
+        assert_eq!(complete_utf8_len(bytes), bytes.len());
+
+        // 'é' is 2 bytes, '€' is 3 bytes, and '😀' is 4 bytes
+        for (len, expected) in &[(2, 1), (4, 3), (5, 3), (6, 6), (7, 6), (9, 6), (10, 10)] {
+            assert_eq!(complete_utf8_len(&bytes[..*len]), *expected);
+        }
+
+        assert_eq!(complete_utf8_len(&[]), 0);
+    }
+}
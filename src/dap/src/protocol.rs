@@ -0,0 +1,55 @@
+pub use koto_protocol::read_message;
+
+use {
+    koto_protocol::write_message,
+    serde_json::{json, Value as Json},
+    std::io::Write,
+};
+
+/// Writes DAP messages to the output, keeping track of message sequence numbers
+pub struct MessageWriter {
+    output: Box<dyn Write + Send>,
+    seq: i64,
+}
+
+impl MessageWriter {
+    pub fn new(output: Box<dyn Write + Send>) -> Self {
+        Self { output, seq: 0 }
+    }
+
+    pub fn send_response(&mut self, request: &Json, body: Json) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    pub fn send_error_response(&mut self, request: &Json, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }));
+    }
+
+    pub fn send_event(&mut self, event: &str, body: Json) {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }));
+    }
+
+    fn send(&mut self, mut message: Json) {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+
+        // Errors are ignored here, if the client has gone away then the input will be closed
+        let _ = write_message(&mut self.output, &message);
+    }
+}
@@ -0,0 +1,620 @@
+use {
+    crate::{protocol::MessageWriter, variables::Variables},
+    koto::{
+        runtime::{Breakpoint, DebugCommand, DebugFrame, DebugPause, Debugger, PauseReason},
+        Koto,
+    },
+    serde_json::{json, Value as Json},
+    std::{
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc, Mutex,
+        },
+        thread,
+        time::Duration,
+    },
+};
+
+// Scripts run in a single thread
+const THREAD_ID: i64 = 1;
+
+// Messages sent from the server to the debugger while execution is paused
+enum PausedCommand {
+    Resume(DebugCommand),
+    SetBreakpoints(PathBuf, Vec<u32>),
+}
+
+struct LaunchArgs {
+    program: PathBuf,
+    args: Vec<String>,
+    stop_on_entry: bool,
+}
+
+/// The state that's shared between the server and the script's debugger
+#[derive(Default)]
+struct SharedState {
+    // The VM's state while execution is paused
+    pause: Mutex<Option<DebugPause>>,
+    // Set when the client has requested that execution should be paused
+    pause_requested: AtomicBool,
+}
+
+/// A DAP server that runs a single Koto script
+pub struct Server {
+    writer: Arc<Mutex<MessageWriter>>,
+    state: Arc<SharedState>,
+    launch_args: Option<LaunchArgs>,
+    configuration_done: bool,
+    breakpoints: Vec<Breakpoint>,
+    debugger: Option<Arc<Mutex<Debugger>>>,
+    paused_sender: Option<mpsc::Sender<PausedCommand>>,
+    variables: Variables,
+}
+
+impl Server {
+    pub fn new(writer: Arc<Mutex<MessageWriter>>) -> Self {
+        Self {
+            writer,
+            state: Arc::new(SharedState::default()),
+            launch_args: None,
+            configuration_done: false,
+            breakpoints: vec![],
+            debugger: None,
+            paused_sender: None,
+            variables: Variables::default(),
+        }
+    }
+
+    /// Handles a request from the client, returning false when the session has finished
+    pub fn handle_request(&mut self, request: &Json) -> bool {
+        let arguments = &request["arguments"];
+
+        let result = match request["command"].as_str().unwrap_or("") {
+            "initialize" => {
+                self.respond(
+                    request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                    }),
+                );
+                self.writer
+                    .lock()
+                    .unwrap()
+                    .send_event("initialized", json!({}));
+                Ok(())
+            }
+            "launch" => self.launch(request, arguments),
+            "setBreakpoints" => self.set_breakpoints(request, arguments),
+            "configurationDone" => {
+                self.configuration_done = true;
+                self.respond(request, json!({}));
+                self.start_if_ready();
+                Ok(())
+            }
+            "threads" => {
+                self.respond(
+                    request,
+                    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+                );
+                Ok(())
+            }
+            "stackTrace" => self.stack_trace(request),
+            "scopes" => {
+                let frame_index = arguments["frameId"].as_u64().unwrap_or(0) as usize;
+                let scopes = self.variables.scopes(frame_index);
+                self.respond(request, json!({ "scopes": scopes }));
+                Ok(())
+            }
+            "variables" => match self.state.pause.lock().unwrap().as_ref() {
+                Some(pause) => {
+                    let reference = arguments["variablesReference"].as_u64().unwrap_or(0);
+                    let variables = self.variables.variables(reference as usize, pause);
+                    self.writer
+                        .lock()
+                        .unwrap()
+                        .send_response(request, json!({ "variables": variables }));
+                    Ok(())
+                }
+                None => Err("Variables are only available while paused".to_string()),
+            },
+            "continue" => self.resume(request, DebugCommand::Continue),
+            "next" => self.resume(request, DebugCommand::StepOver),
+            "stepIn" => self.resume(request, DebugCommand::StepIn),
+            "stepOut" => self.resume(request, DebugCommand::StepOut),
+            "pause" => {
+                self.state.pause_requested.store(true, Ordering::SeqCst);
+                self.with_running_debugger(|debugger| debugger.pause());
+                self.respond(request, json!({}));
+                Ok(())
+            }
+            "disconnect" | "terminate" => {
+                self.respond(request, json!({}));
+                return false;
+            }
+            other => Err(format!("Unsupported request '{}'", other)),
+        };
+
+        if let Err(message) = result {
+            self.writer
+                .lock()
+                .unwrap()
+                .send_error_response(request, &message);
+        }
+
+        true
+    }
+
+    fn respond(&self, request: &Json, body: Json) {
+        self.writer.lock().unwrap().send_response(request, body);
+    }
+
+    fn launch(&mut self, request: &Json, arguments: &Json) -> Result<(), String> {
+        let program = arguments["program"]
+            .as_str()
+            .ok_or_else(|| "Missing 'program' in launch arguments".to_string())?;
+
+        let args = match arguments["args"].as_array() {
+            Some(args) => args
+                .iter()
+                .filter_map(|arg| arg.as_str().map(String::from))
+                .collect(),
+            None => vec![],
+        };
+
+        self.launch_args = Some(LaunchArgs {
+            program: canonicalize(Path::new(program)),
+            args,
+            stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+        });
+
+        self.respond(request, json!({}));
+        self.start_if_ready();
+        Ok(())
+    }
+
+    fn set_breakpoints(&mut self, request: &Json, arguments: &Json) -> Result<(), String> {
+        let path = arguments["source"]["path"]
+            .as_str()
+            .ok_or_else(|| "Missing source path".to_string())?;
+        let path = canonicalize(Path::new(path));
+
+        let lines = match arguments["breakpoints"].as_array() {
+            Some(breakpoints) => breakpoints
+                .iter()
+                .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                .map(|line| line as u32)
+                .collect::<Vec<_>>(),
+            None => vec![],
+        };
+
+        replace_breakpoints(&mut self.breakpoints, &path, &lines);
+
+        if self.state.pause.lock().unwrap().is_some() {
+            self.send_paused_command(PausedCommand::SetBreakpoints(path, lines.clone()));
+        } else {
+            let breakpoints = self.breakpoints.clone();
+            self.with_running_debugger(move |debugger| debugger.breakpoints = breakpoints);
+        }
+
+        let verified = lines
+            .iter()
+            .map(|line| json!({ "verified": true, "line": line }))
+            .collect::<Vec<_>>();
+        self.respond(request, json!({ "breakpoints": verified }));
+        Ok(())
+    }
+
+    fn stack_trace(&mut self, request: &Json) -> Result<(), String> {
+        let pause = self.state.pause.lock().unwrap();
+        let pause = pause
+            .as_ref()
+            .ok_or_else(|| "The stack trace is only available while paused".to_string())?;
+
+        let frames = pause
+            .call_stack
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                let (line, column) = match frame.span {
+                    Some(span) => (span.start.line, span.start.column),
+                    None => (0, 0),
+                };
+                let source = match frame.source_path() {
+                    Some(path) => json!({
+                        "name": path.file_name().map(|name| name.to_string_lossy()),
+                        "path": path,
+                    }),
+                    None => Json::Null,
+                };
+
+                json!({
+                    "id": i,
+                    "name": frame_name(frame),
+                    "source": source,
+                    "line": line,
+                    "column": column,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        self.writer.lock().unwrap().send_response(
+            request,
+            json!({ "stackFrames": frames, "totalFrames": frames.len() }),
+        );
+        Ok(())
+    }
+
+    fn resume(&mut self, request: &Json, command: DebugCommand) -> Result<(), String> {
+        if self.state.pause.lock().unwrap().is_none() {
+            return Err("The script isn't paused".to_string());
+        }
+
+        self.variables.clear();
+        self.respond(request, json!({ "allThreadsContinued": true }));
+        self.send_paused_command(PausedCommand::Resume(command));
+        Ok(())
+    }
+
+    fn send_paused_command(&self, command: PausedCommand) {
+        if let Some(sender) = &self.paused_sender {
+            // The script may have already finished, in which case the command can be ignored
+            let _ = sender.send(command);
+        }
+    }
+
+    // Calls the function with the debugger while the script is running
+    //
+    // The debugger is locked by the VM while it's checking for breakpoints, and while execution
+    // is paused, so it's necessary to wait until the lock is available. If execution becomes
+    // paused in the meantime then the function is skipped.
+    fn with_running_debugger(&self, f: impl FnOnce(&mut Debugger)) {
+        if let Some(debugger) = &self.debugger {
+            loop {
+                if let Ok(mut debugger) = debugger.try_lock() {
+                    f(&mut debugger);
+                    break;
+                }
+                if self.state.pause.lock().unwrap().is_some() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    fn start_if_ready(&mut self) {
+        let launch_args = match (&self.launch_args, self.configuration_done) {
+            (Some(_), true) if self.debugger.is_none() => self.launch_args.take().unwrap(),
+            _ => return,
+        };
+
+        let (paused_sender, paused_receiver) = mpsc::channel();
+        let (debugger_sender, debugger_receiver) = mpsc::channel();
+        self.paused_sender = Some(paused_sender);
+
+        let mut debugger = make_debugger(self.writer.clone(), self.state.clone(), paused_receiver);
+        debugger.breakpoints = self.breakpoints.clone();
+        if launch_args.stop_on_entry {
+            debugger.pause();
+        }
+
+        let writer = self.writer.clone();
+
+        thread::spawn(move || {
+            let mut koto = Koto::new();
+
+            let mut prelude = koto.context().prelude.clone();
+            prelude.add_map("csv", koto_csv::make_module());
+            prelude.add_map("json", koto_json::make_module());
+            prelude.add_map("random", koto_random::make_module());
+            prelude.add_map("regex", koto_regex::make_module());
+            prelude.add_map("tempfile", koto_tempfile::make_module());
+            prelude.add_map("toml", koto_toml::make_module());
+            prelude.add_map("yaml", koto_yaml::make_module());
+
+            koto.set_debugger(Some(debugger));
+            debugger_sender.send(koto.debugger()).unwrap();
+
+            let exit_code = match run_script(&mut koto, &launch_args) {
+                Ok(()) => 0,
+                Err(error) => {
+                    writer.lock().unwrap().send_event(
+                        "output",
+                        json!({ "category": "stderr", "output": format!("{}\n", error) }),
+                    );
+                    1
+                }
+            };
+
+            let mut writer = writer.lock().unwrap();
+            writer.send_event("exited", json!({ "exitCode": exit_code }));
+            writer.send_event("terminated", json!({}));
+        });
+
+        self.debugger = debugger_receiver.recv().unwrap();
+    }
+}
+
+fn run_script(koto: &mut Koto, launch_args: &LaunchArgs) -> Result<(), String> {
+    let script = std::fs::read_to_string(&launch_args.program).map_err(|e| {
+        format!(
+            "Unable to load '{}': {}",
+            launch_args.program.to_string_lossy(),
+            e
+        )
+    })?;
+
+    koto.set_script_path(Some(launch_args.program.clone()));
+    match koto.compile(&script) {
+        Ok(_) => koto.run_with_args(&launch_args.args).map(|_| ()),
+        Err(e) => Err(koto.format_loader_error(e, &script)),
+    }
+}
+
+// Makes a debugger that notifies the client when execution pauses,
+// and then waits for commands from the server.
+fn make_debugger(
+    writer: Arc<Mutex<MessageWriter>>,
+    state: Arc<SharedState>,
+    receiver: mpsc::Receiver<PausedCommand>,
+) -> Debugger {
+    let mut first_pause = true;
+
+    Debugger::new(move |pause, breakpoints| {
+        let reason = if state.pause_requested.swap(false, Ordering::SeqCst) {
+            "pause"
+        } else {
+            match pause.reason {
+                PauseReason::Breakpoint => "breakpoint",
+                PauseReason::Step if first_pause => "entry",
+                PauseReason::Step => "step",
+            }
+        };
+        first_pause = false;
+
+        *state.pause.lock().unwrap() = Some(pause.clone());
+
+        writer.lock().unwrap().send_event(
+            "stopped",
+            json!({
+                "reason": reason,
+                "threadId": THREAD_ID,
+                "allThreadsStopped": true,
+            }),
+        );
+
+        let command = loop {
+            match receiver.recv() {
+                Ok(PausedCommand::SetBreakpoints(path, lines)) => {
+                    replace_breakpoints(breakpoints, &path, &lines)
+                }
+                Ok(PausedCommand::Resume(command)) => break command,
+                Err(_) => break DebugCommand::Stop,
+            }
+        };
+
+        *state.pause.lock().unwrap() = None;
+        command
+    })
+}
+
+fn replace_breakpoints(breakpoints: &mut Vec<Breakpoint>, path: &Path, lines: &[u32]) {
+    breakpoints.retain(|breakpoint| breakpoint.path.as_deref() != Some(path));
+    breakpoints.extend(
+        lines
+            .iter()
+            .map(|line| Breakpoint::new(Some(path.to_path_buf()), *line)),
+    );
+}
+
+// Returns the frame's function name, with frames in unnamed functions shown as `<anonymous>`,
+// and frames in a module's top-level code shown as `<module>`.
+fn frame_name(frame: &DebugFrame) -> &str {
+    match frame.function_name() {
+        Some(name) => name,
+        None if frame.chunk.debug_info.get_function(frame.ip).is_some() => "<anonymous>",
+        None => "<module>",
+    }
+}
+
+fn canonicalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::protocol::read_message,
+        std::{
+            fs,
+            io::{self, Write},
+            time::Instant,
+        },
+    };
+
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct TestClient {
+        server: Server,
+        output: SharedOutput,
+        messages: Vec<Json>,
+        seq: i64,
+    }
+
+    impl TestClient {
+        fn new() -> Self {
+            let output = SharedOutput::default();
+            let writer = Arc::new(Mutex::new(MessageWriter::new(Box::new(output.clone()))));
+            Self {
+                server: Server::new(writer),
+                output,
+                messages: vec![],
+                seq: 0,
+            }
+        }
+
+        // Sends a request, returning the server's response
+        fn request(&mut self, command: &str, arguments: Json) -> Json {
+            self.seq += 1;
+            self.server.handle_request(&json!({
+                "seq": self.seq,
+                "type": "request",
+                "command": command,
+                "arguments": arguments,
+            }));
+
+            let seq = self.seq;
+            self.wait_for(|message| message["type"] == "response" && message["request_seq"] == seq)
+        }
+
+        fn wait_for_event(&mut self, event: &str) -> Json {
+            self.wait_for(|message| message["type"] == "event" && message["event"] == event)
+        }
+
+        // Waits for a message from the server that matches the predicate
+        //
+        // Events are sent from the script's thread, so messages are polled until one is found.
+        fn wait_for(&mut self, predicate: impl Fn(&Json) -> bool) -> Json {
+            let start = Instant::now();
+
+            loop {
+                let output = std::mem::take(&mut *self.output.0.lock().unwrap());
+                let mut output = output.as_slice();
+                while let Some(message) = read_message(&mut output).unwrap() {
+                    self.messages.push(message);
+                }
+
+                if let Some(index) = self.messages.iter().position(&predicate) {
+                    return self.messages.remove(index);
+                }
+
+                assert!(
+                    start.elapsed() < Duration::from_secs(5),
+                    "Timed out while waiting for a message, received: {:?}",
+                    self.messages
+                );
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    const SCRIPT: &str = "\
+f = |x|
+  y = x + 1
+  y
+z = f 41
+";
+
+    // Writes the script to a temporary directory, returning the directory and the script's path
+    fn write_script(script: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.koto");
+        fs::write(&path, script).unwrap();
+        let path = canonicalize(&path);
+        (dir, path)
+    }
+
+    #[test]
+    fn initialize() {
+        let mut client = TestClient::new();
+
+        let response = client.request("initialize", json!({ "adapterID": "koto" }));
+        assert_eq!(response["success"], true);
+        assert_eq!(response["body"]["supportsConfigurationDoneRequest"], true);
+
+        client.wait_for_event("initialized");
+    }
+
+    #[test]
+    fn set_breakpoints() {
+        let mut client = TestClient::new();
+
+        let response = client.request(
+            "setBreakpoints",
+            json!({
+                "source": { "path": "test.koto" },
+                "breakpoints": [{ "line": 2 }, { "line": 4 }],
+            }),
+        );
+        assert_eq!(response["success"], true);
+        assert_eq!(
+            response["body"]["breakpoints"],
+            json!([{ "verified": true, "line": 2 }, { "verified": true, "line": 4 }])
+        );
+
+        let response = client.request("setBreakpoints", json!({ "breakpoints": [] }));
+        assert_eq!(response["success"], false);
+    }
+
+    #[test]
+    fn requests_that_need_a_paused_script() {
+        let mut client = TestClient::new();
+
+        for command in &["stackTrace", "variables", "continue"] {
+            let response = client.request(command, json!({}));
+            assert_eq!(response["success"], false, "{} should fail", command);
+        }
+    }
+
+    #[test]
+    fn stack_trace_and_variables() {
+        let (_dir, path) = write_script(SCRIPT);
+        let mut client = TestClient::new();
+
+        client.request("initialize", json!({}));
+        client.request(
+            "setBreakpoints",
+            json!({ "source": { "path": path }, "breakpoints": [{ "line": 2 }] }),
+        );
+        client.request("launch", json!({ "program": path }));
+        client.request("configurationDone", json!({}));
+
+        let stopped = client.wait_for_event("stopped");
+        assert_eq!(stopped["body"]["reason"], "breakpoint");
+
+        let response = client.request("stackTrace", json!({ "threadId": THREAD_ID }));
+        let frames = response["body"]["stackFrames"].as_array().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0]["name"], "f");
+        assert_eq!(frames[0]["line"], 2);
+        assert_eq!(frames[0]["source"]["path"], json!(path));
+        assert_eq!(frames[1]["name"], "<module>");
+        assert_eq!(frames[1]["line"], 4);
+
+        let response = client.request("scopes", json!({ "frameId": 0 }));
+        let scopes = response["body"]["scopes"].as_array().unwrap();
+        assert_eq!(scopes[0]["name"], "Registers");
+
+        let response = client.request(
+            "variables",
+            json!({ "variablesReference": scopes[0]["variablesReference"] }),
+        );
+        let values = response["body"]["variables"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variable| variable["value"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert!(values.contains(&"41"), "Unexpected variables: {:?}", values);
+
+        let response = client.request("continue", json!({ "threadId": THREAD_ID }));
+        assert_eq!(response["success"], true);
+
+        let exited = client.wait_for_event("exited");
+        assert_eq!(exited["body"]["exitCode"], 0);
+        client.wait_for_event("terminated");
+    }
+}
@@ -0,0 +1,162 @@
+use {
+    koto::runtime::{type_as_string, DebugPause, Value},
+    serde_json::{json, Value as Json},
+};
+
+/// A value that can be expanded by the client to show its children
+enum Container {
+    Registers(usize), // frame index
+    Captures(usize),  // frame index
+    Value(Value),
+}
+
+/// Keeps track of the DAP variable references that are available while execution is paused
+///
+/// References are only valid until execution resumes, at which point the table is cleared.
+#[derive(Default)]
+pub struct Variables {
+    containers: Vec<Container>,
+}
+
+impl Variables {
+    pub fn clear(&mut self) {
+        self.containers.clear();
+    }
+
+    pub fn scopes(&mut self, frame_index: usize) -> Json {
+        json!([
+            {
+                "name": "Registers",
+                "presentationHint": "locals",
+                "variablesReference": self.add(Container::Registers(frame_index)),
+                "expensive": false,
+            },
+            {
+                "name": "Captures",
+                "variablesReference": self.add(Container::Captures(frame_index)),
+                "expensive": false,
+            },
+        ])
+    }
+
+    pub fn variables(&mut self, reference: usize, pause: &DebugPause) -> Json {
+        let children = match reference
+            .checked_sub(1)
+            .and_then(|index| self.containers.get(index))
+        {
+            Some(Container::Registers(frame)) => match pause.call_stack.get(*frame) {
+                Some(frame) => named_values("r", &frame.registers),
+                None => vec![],
+            },
+            Some(Container::Captures(frame)) => match pause.call_stack.get(*frame) {
+                Some(frame) => named_values("c", &frame.captures),
+                None => vec![],
+            },
+            Some(Container::Value(value)) => children(value),
+            None => vec![],
+        };
+
+        let variables = children
+            .into_iter()
+            .map(|(name, value)| self.variable(name, value))
+            .collect::<Vec<_>>();
+
+        Json::Array(variables)
+    }
+
+    fn variable(&mut self, name: String, value: Value) -> Json {
+        let value_string = match &value {
+            Value::Str(s) => format!("\"{}\"", s),
+            _ => value.to_string(),
+        };
+        let value_type = type_as_string(&value);
+        let reference = if is_expandable(&value) {
+            self.add(Container::Value(value))
+        } else {
+            0
+        };
+
+        json!({
+            "name": name,
+            "value": value_string,
+            "type": value_type,
+            "variablesReference": reference,
+        })
+    }
+
+    fn add(&mut self, container: Container) -> usize {
+        self.containers.push(container);
+        // References start at 1, 0 is used to show that a variable has no children
+        self.containers.len()
+    }
+}
+
+fn named_values(prefix: &str, values: &[Value]) -> Vec<(String, Value)> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| (format!("{}{}", prefix, i), value.clone()))
+        .collect()
+}
+
+fn is_expandable(value: &Value) -> bool {
+    match value {
+        Value::List(list) => !list.is_empty(),
+        Value::Tuple(tuple) => !tuple.data().is_empty(),
+        Value::Map(map) => !map.is_empty(),
+        _ => false,
+    }
+}
+
+fn children(value: &Value) -> Vec<(String, Value)> {
+    let indexed = |values: &[Value]| {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (format!("[{}]", i), value.clone()))
+            .collect()
+    };
+
+    match value {
+        Value::List(list) => indexed(&list.data()),
+        Value::Tuple(tuple) => indexed(tuple.data()),
+        Value::Map(map) => map
+            .cloned_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        koto::runtime::{ValueList, ValueMap},
+    };
+
+    #[test]
+    fn nested_containers() {
+        let mut map = ValueMap::new();
+        map.add_value("x", Value::Number(42.into()));
+        let list = Value::List(ValueList::from_slice(&[Value::Map(map), Value::Empty]));
+
+        let pause = DebugPause {
+            reason: koto::runtime::PauseReason::Step,
+            call_stack: vec![],
+        };
+
+        let mut variables = Variables::default();
+        let list_variable = variables.variable("l".to_string(), list);
+        assert_eq!(list_variable["variablesReference"], 1);
+
+        let list_children = variables.variables(1, &pause);
+        assert_eq!(list_children[0]["name"], "[0]");
+        assert_eq!(list_children[0]["variablesReference"], 2);
+        assert_eq!(list_children[1]["variablesReference"], 0);
+
+        let map_children = variables.variables(2, &pause);
+        assert_eq!(map_children[0]["name"], "x");
+        assert_eq!(map_children[0]["value"], "42");
+    }
+}
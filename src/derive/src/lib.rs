@@ -0,0 +1,544 @@
+//! Macros for generating Koto bindings for Rust types
+//!
+//! Annotating an `impl` block with `#[koto_impl]` generates:
+//!   - an `ExternalValue` impl for the type, along with a matching `Display` impl.
+//!   - a `make_value_map` function that wraps a value of the type in a `ValueMap`, with an
+//!     entry for each method that has been marked with `#[koto_method]`.
+//!
+//! Arguments are checked and converted to the method's argument types, and return values are
+//! converted back into Koto values. Calls with unexpected arguments result in errors like:
+//! `Counter.add: Expected (Number) as arguments, found (String)`.
+//!
+//! ```ignore
+//! use koto_derive::koto_impl;
+//!
+//! #[derive(Debug)]
+//! struct Counter(i64);
+//!
+//! #[koto_impl]
+//! impl Counter {
+//!     #[koto_method]
+//!     fn add(&mut self, amount: i64) -> i64 {
+//!         self.0 += amount;
+//!         self.0
+//!     }
+//! }
+//!
+//! let counter_map = Counter(0).make_value_map();
+//! ```
+//!
+//! Attribute arguments:
+//!   - `name = "..."`: the name used for the value's type, defaults to the Rust type's name.
+//!   - `runtime = "..."`: the path to the Koto runtime crate, defaults to `koto_runtime`.
+//!     Crates that only depend on `koto` can use `runtime = "koto::runtime"`.
+//!
+//! Supported argument types:
+//!   - `bool`, `String`, `&str`, `Num2`, `Num4`, `ValueList`, `ValueMap`, `ValueTuple`
+//!   - Numbers: `f32`, `f64`, `i32`, `i64`, `isize`, `u8`, `u32`, `usize`
+//!     - Integer arguments need to be integral and in range for the argument's type.
+//!   - `Value` and `&Value`, which accept any value.
+//!
+//! Supported return types are the same as for arguments (with the exception of `&Value`), along
+//! with `()`, `Self`, `Option<T>`, and `Result<T, E>` (including `Result<(), E>`), where `E`
+//! implements `Display`.
+//! `Self` is returned as a new value map, `None` is returned as `()`, and errors are thrown
+//! as runtime errors.
+
+extern crate proc_macro;
+
+use {
+    proc_macro::TokenStream,
+    proc_macro2::TokenStream as TokenStream2,
+    quote::{format_ident, quote},
+    syn::{
+        parse_macro_input, spanned::Spanned, AttributeArgs, Error, FnArg, GenericArgument,
+        ImplItem, ImplItemMethod, ItemImpl, Lit, Meta, NestedMeta, Path, PathArguments, Type,
+    },
+};
+
+/// Generates Koto bindings for the methods in an `impl` block
+///
+/// See the crate documentation for details.
+#[proc_macro_attribute]
+pub fn koto_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr_args = parse_macro_input!(attr as AttributeArgs);
+    let item_impl = parse_macro_input!(item as ItemImpl);
+
+    match generate_impl(attr_args, item_impl) {
+        Ok(output) => output.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Marks a method in a `#[koto_impl]` block as being available in Koto
+///
+/// The attribute is removed by `#[koto_impl]`, using it elsewhere results in an error.
+#[proc_macro_attribute]
+pub fn koto_method(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item = TokenStream2::from(item);
+    Error::new(
+        item.span(),
+        "#[koto_method] can only be used on methods in a #[koto_impl] block",
+    )
+    .to_compile_error()
+    .into()
+}
+
+struct Context {
+    runtime: Path,
+    // The name of the type as exposed to Koto
+    type_name: String,
+    // The name of the type in Rust, used to find methods that return the type by name
+    rust_type_name: String,
+}
+
+fn generate_impl(attr_args: AttributeArgs, mut item_impl: ItemImpl) -> Result<TokenStream2, Error> {
+    if !item_impl.generics.params.is_empty() {
+        return Err(Error::new(
+            item_impl.generics.span(),
+            "#[koto_impl] doesn't support generic types",
+        ));
+    }
+
+    if let Some((_, trait_path, _)) = &item_impl.trait_ {
+        return Err(Error::new(
+            trait_path.span(),
+            "#[koto_impl] should be used on an inherent impl block, not a trait impl",
+        ));
+    }
+
+    let self_type = item_impl.self_ty.clone();
+    let default_name = match self_type.as_ref() {
+        Type::Path(type_path) => match type_path.path.segments.last() {
+            Some(segment) => segment.ident.to_string(),
+            None => return Err(Error::new(self_type.span(), "Missing type name")),
+        },
+        _ => {
+            return Err(Error::new(
+                self_type.span(),
+                "#[koto_impl] expects a named type",
+            ))
+        }
+    };
+
+    let mut context = Context {
+        runtime: syn::parse_str("koto_runtime")?,
+        type_name: default_name.clone(),
+        rust_type_name: default_name,
+    };
+
+    for arg in attr_args.iter() {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(name_value)) => match &name_value.lit {
+                Lit::Str(value) if name_value.path.is_ident("name") => {
+                    context.type_name = value.value();
+                }
+                Lit::Str(value) if name_value.path.is_ident("runtime") => {
+                    context.runtime = value.parse()?;
+                }
+                _ => return Err(Error::new(arg.span(), "Unexpected #[koto_impl] argument")),
+            },
+            _ => return Err(Error::new(arg.span(), "Unexpected #[koto_impl] argument")),
+        }
+    }
+
+    let mut functions = Vec::new();
+
+    for item in item_impl.items.iter_mut() {
+        if let ImplItem::Method(method) = item {
+            let attribute_count = method.attrs.len();
+            method.attrs.retain(|attr| !is_koto_method_attribute(attr));
+            if method.attrs.len() != attribute_count {
+                functions.push(generate_function(&context, method)?);
+            }
+        }
+    }
+
+    let rt = &context.runtime;
+    let type_name = &context.type_name;
+
+    Ok(quote! {
+        #item_impl
+
+        impl #self_type {
+            /// Makes a `ValueMap` containing the value, along with its Koto methods
+            pub fn make_value_map(self) -> #rt::ValueMap {
+                let mut result = #rt::ValueMap::new();
+                #(#functions)*
+                result.insert(#rt::Value::ExternalDataId, #rt::make_external_value(self));
+                result
+            }
+        }
+
+        impl #rt::ExternalValue for #self_type {
+            fn value_type(&self) -> String {
+                #type_name.to_string()
+            }
+        }
+
+        impl ::std::fmt::Display for #self_type {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str(#type_name)
+            }
+        }
+    })
+}
+
+fn is_koto_method_attribute(attr: &syn::Attribute) -> bool {
+    match attr.path.segments.last() {
+        Some(segment) => segment.ident == "koto_method",
+        None => false,
+    }
+}
+
+enum Receiver {
+    None,
+    Ref,
+    Value,
+}
+
+fn generate_function(context: &Context, method: &ImplItemMethod) -> Result<TokenStream2, Error> {
+    let rt = &context.runtime;
+    let method_ident = &method.sig.ident;
+    let fn_name = method_ident.to_string();
+    let type_name = &context.type_name;
+
+    if !method.sig.generics.params.is_empty() {
+        return Err(Error::new(
+            method.sig.generics.span(),
+            "Generic methods aren't supported by #[koto_method]",
+        ));
+    }
+
+    let mut receiver = Receiver::None;
+    let mut arg_idents = Vec::new();
+    let mut arg_patterns = Vec::new();
+    let mut arg_conversions = Vec::new();
+    let mut checked_args = Vec::new();
+    let mut expected_types = Vec::new();
+
+    for input in method.sig.inputs.iter() {
+        match input {
+            FnArg::Receiver(self_arg) => {
+                receiver = if self_arg.reference.is_some() {
+                    Receiver::Ref
+                } else {
+                    Receiver::Value
+                };
+            }
+            FnArg::Typed(typed) => {
+                let value_ident = format_ident!("value_{}", arg_idents.len());
+                let arg = argument_conversion(rt, &typed.ty, &value_ident)?;
+                arg_patterns.push(arg.pattern);
+                arg_conversions.push(arg.conversion);
+                checked_args.push(arg.checked);
+                expected_types.push(arg.expected_type);
+                arg_idents.push(format_ident!("arg_{}", arg_idents.len()));
+            }
+        }
+    }
+
+    if let Receiver::Value = receiver {
+        return Err(Error::new(
+            method.sig.inputs.span(),
+            "#[koto_method] methods should take self by reference",
+        ));
+    }
+
+    let error_prefix = format!("{}.{}", type_name, fn_name);
+    let mismatch_error = if expected_types.is_empty() {
+        format!("{}: Expected no arguments, found ({{}})", error_prefix)
+    } else {
+        format!(
+            "{}: Expected ({}) as arguments, found ({{}})",
+            error_prefix,
+            expected_types.join(", ")
+        )
+    };
+
+    let call = match receiver {
+        Receiver::Ref => quote! { instance.#method_ident(#(#arg_idents),*) },
+        _ => quote! { Self::#method_ident(#(#arg_idents),*) },
+    };
+
+    let mismatch_result = quote! {
+        #rt::external_error!(
+            #mismatch_error,
+            args.iter()
+                .map(#rt::type_as_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+
+    // Checked conversions produce an Option, with failed conversions reported as mismatches
+    let arg_conversions = arg_conversions
+        .iter()
+        .zip(checked_args.iter())
+        .map(|(conversion, checked)| {
+            if *checked {
+                quote! {
+                    match #conversion {
+                        Some(arg) => arg,
+                        None => return #mismatch_result,
+                    }
+                }
+            } else {
+                conversion.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let body = match &method.sig.output {
+        syn::ReturnType::Type(_, return_type) if !is_unit_type(return_type) => {
+            let result_conversion =
+                return_conversion(context, return_type, quote! { result }, &error_prefix)?;
+            quote! {
+                #(let #arg_idents = #arg_conversions;)*
+                let result = #call;
+                Ok(#result_conversion)
+            }
+        }
+        _ => quote! {
+            #(let #arg_idents = #arg_conversions;)*
+            #call;
+            Ok(#rt::Value::Empty)
+        },
+    };
+
+    let unexpected_args = quote! {
+        _ => #mismatch_result
+    };
+
+    let output = match receiver {
+        Receiver::Ref => {
+            let instance_error = format!(
+                "{}: Expected {} instance as first argument",
+                error_prefix, type_name
+            );
+
+            quote! {
+                result.add_instance_fn(#fn_name, |vm, args| {
+                    match vm.get_args(args) {
+                        [#rt::Value::Map(instance), args @ ..] => {
+                            match args {
+                                [#(#arg_patterns),*] => {
+                                    #rt::visit_external_value(instance, |instance: &mut Self| {
+                                        #body
+                                    })
+                                }
+                                #unexpected_args
+                            }
+                        }
+                        _ => #rt::external_error!(#instance_error),
+                    }
+                });
+            }
+        }
+        _ => quote! {
+            result.add_fn(#fn_name, |vm, args| {
+                let args = vm.get_args(args);
+                match args {
+                    [#(#arg_patterns),*] => {
+                        #body
+                    }
+                    #unexpected_args
+                }
+            });
+        },
+    };
+
+    Ok(output)
+}
+
+struct ArgumentConversion {
+    pattern: TokenStream2,
+    conversion: TokenStream2,
+    // True if the conversion produces an Option, with None indicating an invalid argument
+    checked: bool,
+    expected_type: &'static str,
+}
+
+fn argument_conversion(
+    rt: &Path,
+    arg_type: &Type,
+    ident: &syn::Ident,
+) -> Result<ArgumentConversion, Error> {
+    let unsupported = || Error::new(arg_type.span(), "Unsupported argument type");
+
+    let (pattern, conversion, expected_type) = match arg_type {
+        Type::Reference(reference) if reference.mutability.is_none() => {
+            match type_name(&reference.elem).as_deref() {
+                Some("str") => (
+                    quote! { #rt::Value::Str(#ident) },
+                    quote! { #ident.as_str() },
+                    "String",
+                ),
+                Some("Value") => (quote! { #ident }, quote! { #ident }, "Any"),
+                _ => return Err(unsupported()),
+            }
+        }
+        Type::Path(_) => match type_name(arg_type).as_deref() {
+            Some(number) if is_number_type(number) => {
+                // Numbers are converted with FromValue, which checks that integers are in range
+                return Ok(ArgumentConversion {
+                    pattern: quote! { #ident @ #rt::Value::Number(_) },
+                    conversion: quote! { <#arg_type as #rt::FromValue>::from_value(#ident) },
+                    checked: true,
+                    expected_type: "Number",
+                });
+            }
+            Some("bool") => (
+                quote! { #rt::Value::Bool(#ident) },
+                quote! { *#ident },
+                "Bool",
+            ),
+            Some("String") => (
+                quote! { #rt::Value::Str(#ident) },
+                quote! { #ident.to_string() },
+                "String",
+            ),
+            Some("Num2") => (
+                quote! { #rt::Value::Num2(#ident) },
+                quote! { *#ident },
+                "Num2",
+            ),
+            Some("Num4") => (
+                quote! { #rt::Value::Num4(#ident) },
+                quote! { *#ident },
+                "Num4",
+            ),
+            Some("ValueList") => (
+                quote! { #rt::Value::List(#ident) },
+                quote! { #ident.clone() },
+                "List",
+            ),
+            Some("ValueMap") => (
+                quote! { #rt::Value::Map(#ident) },
+                quote! { #ident.clone() },
+                "Map",
+            ),
+            Some("ValueTuple") => (
+                quote! { #rt::Value::Tuple(#ident) },
+                quote! { #ident.clone() },
+                "Tuple",
+            ),
+            Some("Value") => (quote! { #ident }, quote! { #ident.clone() }, "Any"),
+            _ => return Err(unsupported()),
+        },
+        _ => return Err(unsupported()),
+    };
+
+    Ok(ArgumentConversion {
+        pattern,
+        conversion,
+        checked: false,
+        expected_type,
+    })
+}
+
+fn return_conversion(
+    context: &Context,
+    return_type: &Type,
+    value: TokenStream2,
+    error_prefix: &str,
+) -> Result<TokenStream2, Error> {
+    let rt = &context.runtime;
+    let unsupported = || Error::new(return_type.span(), "Unsupported return type");
+
+    let result = match return_type {
+        Type::Reference(reference) if reference.mutability.is_none() => {
+            match type_name(&reference.elem).as_deref() {
+                Some("str") => quote! { #rt::Value::Str(#value.into()) },
+                _ => return Err(unsupported()),
+            }
+        }
+        Type::Path(_) => match type_name(return_type).as_deref() {
+            Some(number) if is_number_type(number) => {
+                quote! { #rt::Value::Number(#value.into()) }
+            }
+            Some("bool") => quote! { #rt::Value::Bool(#value) },
+            Some("String") => quote! { #rt::Value::Str(#value.into()) },
+            Some("Num2") => quote! { #rt::Value::Num2(#value) },
+            Some("Num4") => quote! { #rt::Value::Num4(#value) },
+            Some("ValueList") => quote! { #rt::Value::List(#value) },
+            Some("ValueMap") => quote! { #rt::Value::Map(#value) },
+            Some("ValueTuple") => quote! { #rt::Value::Tuple(#value) },
+            Some("Value") => value,
+            Some("Self") => quote! { #rt::Value::Map(#value.make_value_map()) },
+            Some("Option") => {
+                let inner = return_conversion(
+                    context,
+                    generic_argument(return_type, 0).ok_or_else(unsupported)?,
+                    quote! { value },
+                    error_prefix,
+                )?;
+                quote! {
+                    match #value {
+                        Some(value) => #inner,
+                        None => #rt::Value::Empty,
+                    }
+                }
+            }
+            Some("Result") => {
+                let ok_type = generic_argument(return_type, 0).ok_or_else(unsupported)?;
+                let ok_arm = if is_unit_type(ok_type) {
+                    quote! { Ok(_) => #rt::Value::Empty }
+                } else {
+                    let inner =
+                        return_conversion(context, ok_type, quote! { value }, error_prefix)?;
+                    quote! { Ok(value) => #inner }
+                };
+                let error_message = format!("{}: {{}}", error_prefix);
+                quote! {
+                    match #value {
+                        #ok_arm,
+                        Err(error) => return #rt::external_error!(#error_message, error),
+                    }
+                }
+            }
+            Some(name) if name == context.rust_type_name => {
+                quote! { #rt::Value::Map(#value.make_value_map()) }
+            }
+            _ => return Err(unsupported()),
+        },
+        _ => return Err(unsupported()),
+    };
+
+    Ok(result)
+}
+
+fn is_unit_type(t: &Type) -> bool {
+    matches!(t, Type::Tuple(tuple) if tuple.elems.is_empty())
+}
+
+fn is_number_type(name: &str) -> bool {
+    matches!(
+        name,
+        "f32" | "f64" | "i32" | "i64" | "isize" | "u8" | "u32" | "usize"
+    )
+}
+
+fn type_name(t: &Type) -> Option<String> {
+    match t {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+fn generic_argument(t: &Type, index: usize) -> Option<&Type> {
+    match t {
+        Type::Path(type_path) => match &type_path.path.segments.last()?.arguments {
+            PathArguments::AngleBracketed(arguments) => match arguments.args.iter().nth(index)? {
+                GenericArgument::Type(argument) => Some(argument),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
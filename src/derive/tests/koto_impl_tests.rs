@@ -0,0 +1,288 @@
+use {
+    koto_derive::koto_impl,
+    koto_runtime::{num2, Loader, Value, Value::*, ValueList, Vm},
+};
+
+#[derive(Debug, Default)]
+struct Counter {
+    count: i64,
+    label: String,
+}
+
+#[koto_impl]
+impl Counter {
+    #[koto_method]
+    fn new(label: &str) -> Self {
+        Self {
+            count: 0,
+            label: label.to_string(),
+        }
+    }
+
+    #[koto_method]
+    fn add(&mut self, amount: i64) -> i64 {
+        self.count += amount;
+        self.count
+    }
+
+    #[koto_method]
+    fn add_small(&mut self, amount: u8) -> i64 {
+        self.count += amount as i64;
+        self.count
+    }
+
+    #[koto_method]
+    fn count(&self) -> f64 {
+        self.count as f64
+    }
+
+    #[koto_method]
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    #[koto_method]
+    fn set_label(&mut self, label: String) {
+        self.label = label;
+    }
+
+    #[koto_method]
+    fn is_even(&self) -> bool {
+        self.count % 2 == 0
+    }
+
+    #[koto_method]
+    fn scaled(&self, x: num2::Num2) -> num2::Num2 {
+        x * self.count as f64
+    }
+
+    #[koto_method]
+    fn push_to(&self, list: ValueList, value: &Value) -> usize {
+        list.data_mut().push(value.clone());
+        list.len()
+    }
+
+    #[koto_method]
+    fn checked_sub(&mut self, amount: i64) -> Result<i64, String> {
+        if amount > self.count {
+            Err(format!("Unable to subtract {} from {}", amount, self.count))
+        } else {
+            self.count -= amount;
+            Ok(self.count)
+        }
+    }
+
+    #[koto_method]
+    fn non_zero(&self) -> Option<i64> {
+        if self.count != 0 {
+            Some(self.count)
+        } else {
+            None
+        }
+    }
+
+    #[koto_method]
+    fn split(&self) -> Counter {
+        Self {
+            count: self.count,
+            label: format!("{}_split", self.label),
+        }
+    }
+
+    // Not exported to Koto
+    fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+#[derive(Debug)]
+struct Named;
+
+#[koto_impl(name = "Thing", runtime = "koto_runtime")]
+impl Named {
+    #[koto_method]
+    fn ping(&self) -> &str {
+        "pong"
+    }
+}
+
+fn run_script(script: &str) -> Result<Value, String> {
+    let mut vm = Vm::default();
+    let mut prelude = vm.context_mut().prelude.clone();
+
+    let mut counter = Counter::default();
+    counter.reset();
+    prelude.add_map("counter", counter.make_value_map());
+    prelude.add_map("named", Named.make_value_map());
+    prelude.add_value("items", List(ValueList::default()));
+
+    let script = format!("import counter, items, named\n{}", script);
+    let mut loader = Loader::default();
+    let chunk = loader
+        .compile_script(&script, &None)
+        .map_err(|error| error.to_string())?;
+    vm.run(chunk).map_err(|error| error.to_string())
+}
+
+fn check_script_output(script: &str, expected_output: Value) {
+    match run_script(script) {
+        Ok(result) => assert_eq!(result, expected_output),
+        Err(error) => panic!("Error while running script: {}", error),
+    }
+}
+
+fn check_script_fails(script: &str, expected_error: &str) {
+    match run_script(script) {
+        Ok(_) => panic!("Expected the script to fail"),
+        Err(error) => assert!(
+            error.contains(expected_error),
+            "Expected error containing '{}', found '{}'",
+            expected_error,
+            error
+        ),
+    }
+}
+
+mod koto_impl {
+    use super::*;
+
+    #[test]
+    fn instance_methods() {
+        let script = "
+counter.add 3
+counter.add 4
+counter.count()";
+        check_script_output(script, Number(7.0.into()));
+    }
+
+    #[test]
+    fn string_arguments_and_return_values() {
+        let script = "
+counter.set_label \"hello\"
+counter.label()";
+        check_script_output(script, Str("hello".into()));
+    }
+
+    #[test]
+    fn bool_return_value() {
+        let script = "
+counter.add 1
+counter.is_even()";
+        check_script_output(script, Bool(false));
+    }
+
+    #[test]
+    fn num2_argument() {
+        let script = "
+counter.add 2
+counter.scaled (num2 1 2)";
+        check_script_output(script, Num2(num2::Num2(2.0, 4.0)));
+    }
+
+    #[test]
+    fn list_and_value_arguments() {
+        let script = "
+counter.push_to items 42
+counter.push_to items \"x\"";
+        check_script_output(script, Number(2.into()));
+    }
+
+    #[test]
+    fn result_return_value() {
+        let script = "
+counter.add 10
+counter.checked_sub 3";
+        check_script_output(script, Number(7.into()));
+    }
+
+    #[test]
+    fn result_error() {
+        check_script_fails(
+            "counter.checked_sub 1",
+            "Counter.checked_sub: Unable to subtract 1 from 0",
+        );
+    }
+
+    #[test]
+    fn option_return_value() {
+        check_script_output("counter.non_zero()", Empty);
+        check_script_output(
+            "
+counter.add 5
+counter.non_zero()",
+            Number(5.into()),
+        );
+    }
+
+    #[test]
+    fn self_return_value() {
+        let script = "
+counter.set_label \"foo\"
+x = counter.split()
+x.set_label (x.label() + \"!\")
+x.label() + counter.label()";
+        check_script_output(script, Str("foo_split!foo".into()));
+    }
+
+    #[test]
+    fn static_function() {
+        let script = "
+x = counter.new \"abc\"
+x.add 2
+x.label()";
+        check_script_output(script, Str("abc".into()));
+    }
+
+    #[test]
+    fn unexported_method() {
+        check_script_fails("counter.reset()", "reset");
+    }
+
+    #[test]
+    fn custom_name() {
+        check_script_output("named.ping()", Str("pong".into()));
+        check_script_fails(
+            "named.ping 1",
+            "Thing.ping: Expected no arguments, found (Number)",
+        );
+    }
+
+    #[test]
+    fn checked_integer_arguments() {
+        check_script_output("counter.add_small 255", Number(255.into()));
+        check_script_output("counter.add_small 2.0", Number(2.into()));
+
+        for invalid in &["300", "-1", "1.5"] {
+            check_script_fails(
+                &format!("counter.add_small {}", invalid),
+                "Counter.add_small: Expected (Number) as arguments, found (Number)",
+            );
+        }
+    }
+
+    #[test]
+    fn unexpected_arguments() {
+        check_script_fails(
+            "counter.add \"x\"",
+            "Counter.add: Expected (Number) as arguments, found (String)",
+        );
+        check_script_fails(
+            "counter.push_to items",
+            "Counter.push_to: Expected (List, Any) as arguments, found (List)",
+        );
+    }
+
+    #[test]
+    fn external_value() {
+        let map = Counter::default().make_value_map();
+        assert!(koto_runtime::is_external_instance::<Counter>(&map));
+        match map.data().get(&ExternalDataId) {
+            Some(ExternalValue(value)) => {
+                let value = value.read().unwrap();
+                assert_eq!(value.value_type(), "Counter");
+                assert_eq!(value.to_string(), "Counter");
+            }
+            _ => panic!("Missing external value"),
+        };
+    }
+}
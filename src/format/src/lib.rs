@@ -0,0 +1,792 @@
+//! A source code formatter for the Koto programming language
+//!
+//! Scripts are reprinted from the lexer's tokens, with comments retained, using consistent
+//! indentation, spacing, and blank lines. Lines that are longer than the configured line width
+//! are wrapped. The script is parsed before and after formatting to make sure that the formatted
+//! script is equivalent to the original.
+//!
+//! ## Example
+//!
+//! ```
+//! let formatted = koto_format::format("x  =  [1 ,2]\nif x.size()  >  1\n      x\n").unwrap();
+//! assert_eq!(formatted, "x = [1, 2]\nif x.size() > 1\n  x\n");
+//! ```
+
+use {
+    koto_lexer::{Lexer, Token},
+    koto_parser::{AstNode, Node, Parser, ParserError},
+    std::{error, fmt},
+};
+
+/// The string used for each level of indentation in formatted scripts
+pub const INDENT: &str = "  ";
+
+/// Settings for the formatter
+#[derive(Clone, Debug)]
+pub struct FormatSettings {
+    /// The maximum width of a formatted line, including its indentation
+    ///
+    /// Defaults to 100.
+    pub line_width: usize,
+}
+
+impl Default for FormatSettings {
+    fn default() -> Self {
+        Self { line_width: 100 }
+    }
+}
+
+/// Errors that can occur while formatting a script
+#[derive(Clone, Debug)]
+pub enum FormatError {
+    /// The script failed to parse, so it couldn't be formatted
+    ParserError(ParserError),
+    /// The formatted script wasn't equivalent to the original script
+    ///
+    /// This can happen when a script's indentation is ambiguous, e.g. when lines in a block have
+    /// inconsistent indentation.
+    ProgramChanged,
+}
+
+impl From<ParserError> for FormatError {
+    fn from(e: ParserError) -> Self {
+        Self::ParserError(e)
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParserError(e) => {
+                write!(f, "{} - {}:{}", e, e.span.start.line, e.span.start.column)
+            }
+            Self::ProgramChanged => {
+                f.write_str("Formatting would change the meaning of the script")
+            }
+        }
+    }
+}
+
+impl error::Error for FormatError {}
+
+/// Formats a Koto script with the default [FormatSettings]
+///
+/// See [format_with_settings].
+pub fn format(source: &str) -> Result<String, FormatError> {
+    format_with_settings(source, &FormatSettings::default())
+}
+
+/// Formats a Koto script
+///
+/// Lines are indented by [INDENT] for each level of nesting, runs of blank lines are reduced to
+/// a single blank line, and spacing between tokens is made consistent.
+///
+/// Lines that are longer than the settings' line width are wrapped by breaking call arguments,
+/// lists, and maps across indented lines, with one element per line. If the wrapped script
+/// wouldn't be equivalent to the original then the script is formatted without wrapping.
+pub fn format_with_settings(
+    source: &str,
+    settings: &FormatSettings,
+) -> Result<String, FormatError> {
+    let (ast, constants) = Parser::parse(source)?;
+
+    let lines = split_lines(source);
+    let levels = indentation_levels(&lines);
+
+    let is_equivalent = |formatted: &str| match Parser::parse(formatted) {
+        Ok((formatted_ast, formatted_constants)) => {
+            formatted_constants == constants
+                && formatted_ast
+                    .nodes()
+                    .iter()
+                    .map(normalized_node)
+                    .eq(ast.nodes().iter().map(normalized_node))
+        }
+        Err(_) => false,
+    };
+
+    // Check that the formatted script produces the same program as the original
+    let wrapped = print_lines(&lines, &levels, Some(settings.line_width));
+    if is_equivalent(&wrapped) {
+        return Ok(wrapped);
+    }
+    let unwrapped = print_lines(&lines, &levels, None);
+    if is_equivalent(&unwrapped) {
+        Ok(unwrapped)
+    } else {
+        Err(FormatError::ProgramChanged)
+    }
+}
+
+fn print_lines(lines: &[Line], levels: &[usize], line_width: Option<usize>) -> String {
+    let mut result = String::new();
+    for (i, (line, level)) in lines.iter().zip(levels.iter()).enumerate() {
+        if i > 0 && line.blank_lines_before > 0 {
+            result.push('\n');
+        }
+        // Lines that start an indented block aren't wrapped,
+        // the parser expects the block's header to be on a single line.
+        let starts_block = matches!(levels.get(i + 1), Some(next) if next > level);
+        let line_width = if starts_block { None } else { line_width };
+
+        let spaces = token_spacing(&line.tokens);
+        print_tokens(&line.tokens, &spaces, *level, line_width, &mut result);
+    }
+    result
+}
+
+// Prepares a node for comparison with the nodes of another AST
+fn normalized_node(node: &AstNode) -> Node {
+    match &node.node {
+        Node::Function(function) => {
+            // The parser collects a function's non-locals in a set,
+            // so their order isn't consistent between parses.
+            let mut function = function.clone();
+            function.accessed_non_locals.sort_unstable();
+            Node::Function(function)
+        }
+        other => other.clone(),
+    }
+}
+
+/// Returns true if the script is already formatted with the default [FormatSettings]
+pub fn is_formatted(source: &str) -> Result<bool, FormatError> {
+    is_formatted_with_settings(source, &FormatSettings::default())
+}
+
+/// Returns true if the script is already formatted
+pub fn is_formatted_with_settings(
+    source: &str,
+    settings: &FormatSettings,
+) -> Result<bool, FormatError> {
+    format_with_settings(source, settings).map(|formatted| formatted == source)
+}
+
+struct FormatToken<'a> {
+    token: Token,
+    slice: &'a str,
+    space_before: bool,
+}
+
+// A line of source containing at least one token
+//
+// Multi-line tokens like strings and comments are included in the line where they start.
+struct Line<'a> {
+    indent: usize,
+    blank_lines_before: usize,
+    tokens: Vec<FormatToken<'a>>,
+}
+
+impl<'a> Line<'a> {
+    fn new(indent: usize) -> Self {
+        Self {
+            indent,
+            blank_lines_before: 0,
+            tokens: vec![],
+        }
+    }
+
+    fn is_comment(&self) -> bool {
+        self.tokens
+            .iter()
+            .all(|t| matches!(t.token, Token::CommentSingle | Token::CommentMulti))
+    }
+}
+
+fn split_lines(source: &str) -> Vec<Line<'_>> {
+    let mut lexer = Lexer::new(source);
+    let mut lines = vec![];
+    let mut line = Line::new(0);
+    let mut space_before = false;
+    let mut blank_lines = 0;
+
+    while let Some(token) = lexer.next() {
+        match token {
+            Token::Whitespace => {
+                if line.tokens.is_empty() {
+                    // Whitespace at the start of the script
+                    line.indent = lexer.slice().len();
+                } else {
+                    space_before = true;
+                }
+            }
+            Token::NewLine | Token::NewLineIndented => {
+                if line.tokens.is_empty() {
+                    blank_lines += 1;
+                } else {
+                    lines.push(line);
+                }
+                // The newline token includes the following line's indentation
+                line = Line::new(lexer.slice().len() - 1);
+                space_before = false;
+            }
+            _ => {
+                if line.tokens.is_empty() {
+                    line.blank_lines_before = blank_lines;
+                    blank_lines = 0;
+                }
+                line.tokens.push(FormatToken {
+                    token,
+                    slice: lexer.slice(),
+                    space_before,
+                });
+                space_before = false;
+            }
+        }
+    }
+
+    if !line.tokens.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Determines the indentation level of each line
+//
+// Levels are determined by comparing each line's indentation with the lines before it.
+// Lines that only contain comments don't affect the indentation of the lines that follow them,
+// and comments with indentation that doesn't match an enclosing block are indented to match the
+// next line.
+fn indentation_levels(lines: &[Line]) -> Vec<usize> {
+    let mut indent_stack: Vec<usize> = vec![];
+    let mut levels = Vec::with_capacity(lines.len());
+
+    for line in lines.iter() {
+        let level = if line.is_comment() {
+            indent_stack
+                .iter()
+                .position(|indent| *indent == line.indent)
+        } else {
+            while matches!(indent_stack.last(), Some(indent) if line.indent < *indent) {
+                indent_stack.pop();
+            }
+            if indent_stack.last() != Some(&line.indent) {
+                indent_stack.push(line.indent);
+            }
+            Some(indent_stack.len() - 1)
+        };
+        levels.push(level);
+    }
+
+    let mut next_level = 0;
+    levels
+        .into_iter()
+        .rev()
+        .map(|level| {
+            if let Some(level) = level {
+                next_level = level;
+            }
+            next_level
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect()
+}
+
+// Prints a line of tokens, along with its indentation and a trailing newline
+//
+// If a line width is provided and the line is too long, then the line is wrapped by breaking the
+// largest group of elements in the line (call arguments, a list, or a map) across lines.
+// The lines that are produced are wrapped in turn if they're still too long.
+fn print_tokens(
+    tokens: &[FormatToken],
+    spaces: &[bool],
+    level: usize,
+    line_width: Option<usize>,
+    output: &mut String,
+) {
+    let line = render_tokens(tokens, spaces);
+
+    let group = match line_width {
+        Some(line_width) if level * INDENT.len() + line.chars().count() > line_width => {
+            wrappable_group(tokens, spaces)
+        }
+        _ => None,
+    };
+
+    match group {
+        Some((open, close)) => {
+            print_tokens(&tokens[..=open], &spaces[..=open], level, None, output);
+
+            let elements = split_elements(&tokens[open + 1..close]);
+            let mut start = open + 1;
+            for element in elements {
+                let end = start + element.len();
+                print_tokens(
+                    &tokens[start..end],
+                    &spaces[start..end],
+                    level + 1,
+                    line_width,
+                    output,
+                );
+                // Each element is followed by a comma, including the last element unless it
+                // contains a function, in which case the comma would be included in the
+                // function's body.
+                let needs_comma = match element.last() {
+                    Some(last) => last.token != Token::Comma && !contains_function(element),
+                    None => false,
+                };
+                if needs_comma {
+                    output.pop();
+                    output.push_str(",\n");
+                }
+                start = end;
+            }
+
+            print_tokens(
+                &tokens[close..],
+                &spaces[close..],
+                level,
+                line_width,
+                output,
+            );
+        }
+        None => {
+            for _ in 0..level {
+                output.push_str(INDENT);
+            }
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+}
+
+fn render_tokens(tokens: &[FormatToken], spaces: &[bool]) -> String {
+    let mut result = String::new();
+    for (i, (token, space)) in tokens.iter().zip(spaces.iter()).enumerate() {
+        if i > 0 && *space {
+            result.push(' ');
+        }
+        match token.token {
+            Token::CommentSingle => result.push_str(token.slice.trim_end()),
+            _ => result.push_str(token.slice),
+        }
+    }
+    result
+}
+
+// Finds the group of elements in the line that should be broken across lines when wrapping
+//
+// Groups are call arguments, lists, and maps that are opened and closed within the line, and
+// that aren't nested in another group. The parser doesn't allow parenthesized expressions or
+// indexing brackets to be broken across lines, or for an expression to continue after a closing
+// bracket on a new line, so only groups that are followed by closing brackets, commas,
+// or comments are considered. Functions are only allowed in the group's last element, otherwise
+// the function's body would include the comma at the end of its line. The group containing the most tokens is chosen, and its
+// open and close positions are returned.
+//
+// Lines containing multi-line tokens aren't wrapped.
+fn wrappable_group(tokens: &[FormatToken], spaces: &[bool]) -> Option<(usize, usize)> {
+    use Token::*;
+
+    if tokens.iter().any(|token| token.slice.contains('\n')) {
+        return None;
+    }
+
+    let mut result: Option<(usize, usize)> = None;
+    let mut depth = 0;
+    let mut open = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token.token {
+            ParenOpen | ListStart | MapStart => {
+                if depth == 0 {
+                    open = i;
+                }
+                depth += 1;
+            }
+            ParenClose | ListEnd | MapEnd => {
+                if depth == 0 {
+                    continue;
+                }
+                depth -= 1;
+                if depth > 0 || i == open + 1 {
+                    continue;
+                }
+                let follows_value =
+                    open > 0 && !spaces[open] && is_value_end(tokens[open - 1].token);
+                let is_group = match tokens[open].token {
+                    ParenOpen => follows_value,
+                    ListStart => !follows_value,
+                    _ => true,
+                };
+                let is_largest = match result {
+                    Some((largest_open, largest_close)) => i - open > largest_close - largest_open,
+                    None => true,
+                };
+                let ends_line = tokens[i + 1..].iter().all(|token| {
+                    matches!(
+                        token.token,
+                        ParenClose | ListEnd | MapEnd | Comma | CommentSingle | CommentMulti
+                    )
+                });
+                let has_function_before_end = || {
+                    let elements = split_elements(&tokens[open + 1..i]);
+                    elements[..elements.len() - 1]
+                        .iter()
+                        .any(|element| contains_function(element))
+                };
+                if is_group && ends_line && is_largest && !has_function_before_end() {
+                    result = Some((open, i));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
+// Splits the contents of a group into elements, with each element including its trailing comma
+fn split_elements<'a, 'b>(tokens: &'a [FormatToken<'b>]) -> Vec<&'a [FormatToken<'b>]> {
+    use Token::*;
+
+    let mut result = vec![];
+    let mut depth = 0;
+    let mut function_args_open = false;
+    let mut start = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token.token {
+            ParenOpen | ListStart | MapStart => depth += 1,
+            ParenClose | ListEnd | MapEnd => depth -= 1,
+            Function => function_args_open = !function_args_open,
+            Comma if depth == 0 && !function_args_open => {
+                result.push(&tokens[start..=i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if start < tokens.len() {
+        result.push(&tokens[start..]);
+    }
+
+    result
+}
+
+fn contains_function(tokens: &[FormatToken]) -> bool {
+    tokens.iter().any(|token| token.token == Token::Function)
+}
+
+// Determines whether or not each token in a line is preceded by a space
+fn token_spacing(tokens: &[FormatToken]) -> Vec<bool> {
+    use Token::*;
+
+    // A '-' is a unary negation when it's directly followed by its operand,
+    // and when it isn't preceded by a value that could be the lhs of a subtraction.
+    let is_negation = |i: usize| {
+        tokens[i].token == Subtract
+            && tokens.get(i + 1).map(|next| next.space_before) == Some(false)
+            && (i == 0 || tokens[i].space_before || !is_value_end(tokens[i - 1].token))
+    };
+
+    // Function args are contained between pairs of '|' tokens
+    let mut function_args_open = false;
+    let mut result = Vec::with_capacity(tokens.len());
+
+    for (i, token) in tokens.iter().enumerate() {
+        if i == 0 {
+            result.push(false);
+        } else {
+            let previous = &tokens[i - 1];
+
+            let space = match (previous.token, token.token) {
+                (CommentMulti, _) | (_, CommentSingle) | (_, CommentMulti) => true,
+                (_, Comma) => false,
+                (ParenOpen, _) | (ListStart, _) | (MapStart, _) => false,
+                (_, ParenClose) | (_, ListEnd) | (_, MapEnd) => false,
+                (Comma, _) => true,
+                (_, Colon) => false,
+                (Colon, _) => true,
+                (a, b) if is_tight_symbol(a) || is_tight_symbol(b) => token.space_before,
+                (_, Function) if function_args_open => false,
+                (Function, _) if function_args_open => false,
+                (Function, _) => true,
+                (_, Subtract) if is_negation(i) => token.space_before,
+                (Subtract, _) if is_negation(i - 1) => false,
+                (a, b) if is_spaced(a) || is_spaced(b) => true,
+                _ => token.space_before,
+            };
+
+            result.push(space);
+        }
+
+        if token.token == Function {
+            function_args_open = !function_args_open;
+        }
+    }
+
+    result
+}
+
+// Tokens that are written without changing the spacing around them
+fn is_tight_symbol(token: Token) -> bool {
+    use Token::*;
+    matches!(token, Dot | Range | RangeInclusive | Ellipsis)
+}
+
+// Operators and keywords that are separated from neighbouring tokens with a single space
+fn is_spaced(token: Token) -> bool {
+    use Token::*;
+
+    matches!(
+        token,
+        Add | Subtract
+            | Multiply
+            | Divide
+            | Modulo
+            | Assign
+            | AssignAdd
+            | AssignSubtract
+            | AssignMultiply
+            | AssignDivide
+            | AssignModulo
+            | Equal
+            | NotEqual
+            | Greater
+            | GreaterOrEqual
+            | Less
+            | LessOrEqual
+            | And
+            | Catch
+            | Copy
+            | Debug
+            | Else
+            | ElseIf
+            | Export
+            | Finally
+            | For
+            | From
+            | If
+            | Import
+            | In
+            | Loop
+            | Match
+            | Not
+            | Or
+            | Return
+            | Then
+            | Throw
+            | Try
+            | Until
+            | While
+            | Yield
+    )
+}
+
+// Tokens that can end a value, and which could be followed by a binary operator
+fn is_value_end(token: Token) -> bool {
+    use Token::*;
+
+    matches!(
+        token,
+        Number
+            | String
+            | Id
+            | ParenClose
+            | ListEnd
+            | MapEnd
+            | Wildcard
+            | True
+            | False
+            | Break
+            | Continue
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_format(source: &str, expected: &str) {
+        let formatted = match format(source) {
+            Ok(formatted) => formatted,
+            Err(error) => panic!("Failed to format script: {}", error),
+        };
+        assert_eq!(formatted, expected);
+        // Formatting should be idempotent
+        assert_eq!(format(&formatted).unwrap(), expected);
+    }
+
+    #[test]
+    fn spacing() {
+        check_format(
+            "x  =  [ 1 ,2,3 ]\ny = {a:1,  b: x}\nz = x.size()   *   -2\n",
+            "x = [1, 2, 3]\ny = {a: 1, b: x}\nz = x.size() * -2\n",
+        );
+    }
+
+    #[test]
+    fn negation_and_calls() {
+        check_format(
+            "a = f -x\nb = a-1\nc = a - -x\nd = f(x,y)\ne = f [1]\n",
+            "a = f -x\nb = a-1\nc = a - -x\nd = f(x, y)\ne = f [1]\n",
+        );
+    }
+
+    #[test]
+    fn functions() {
+        check_format(
+            "f = | x,y |   x  +  y\ng = ||   42\nh = |args...| args\n",
+            "f = |x, y| x + y\ng = || 42\nh = |args...| args\n",
+        );
+    }
+
+    #[test]
+    fn indentation() {
+        check_format(
+            "\
+f = |x|
+    if x  >  0
+            x
+    else
+            -x
+y = [
+      1,
+]
+",
+            "\
+f = |x|
+  if x > 0
+    x
+  else
+    -x
+y = [
+  1,
+]
+",
+        );
+    }
+
+    #[test]
+    fn comments_are_retained() {
+        check_format(
+            "\
+# A comment
+f = ||   # Trailing comment
+        x = 1
+    # Comment with unmatched indentation
+        #- Multi-line
+   comment -#
+        x
+",
+            "\
+# A comment
+f = || # Trailing comment
+  x = 1
+  # Comment with unmatched indentation
+  #- Multi-line
+   comment -#
+  x
+",
+        );
+    }
+
+    #[test]
+    fn blank_lines() {
+        check_format(
+            "\n\nx = 1\n\n\n\ny = 2\n   \nz = 3\n\n\n",
+            "x = 1\n\ny = 2\n\nz = 3\n",
+        );
+    }
+
+    #[test]
+    fn multi_line_strings_are_unchanged() {
+        check_format("x = \"foo\n    bar\"\n", "x = \"foo\n    bar\"\n");
+    }
+
+    fn check_format_with_width(source: &str, line_width: usize, expected: &str) {
+        let settings = FormatSettings { line_width };
+        let formatted = match format_with_settings(source, &settings) {
+            Ok(formatted) => formatted,
+            Err(error) => panic!("Failed to format script: {}", error),
+        };
+        assert_eq!(formatted, expected);
+        assert_eq!(
+            format_with_settings(&formatted, &settings).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn wrapped_call_arguments() {
+        check_format_with_width(
+            "x = foo.bar(first_argument, second_argument) # Comment\n",
+            30,
+            "\
+x = foo.bar(
+  first_argument,
+  second_argument,
+) # Comment
+",
+        );
+    }
+
+    #[test]
+    fn wrapped_lists_and_maps() {
+        check_format_with_width(
+            "\
+f = ||
+  x = {foo: [1, 2, 3], bar: \"a long string\", baz: [\"another long string\", 42]}
+",
+            30,
+            "\
+f = ||
+  x = {
+    foo: [1, 2, 3],
+    bar: \"a long string\",
+    baz: [
+      \"another long string\",
+      42,
+    ],
+  }
+",
+        );
+    }
+
+    #[test]
+    fn wrapped_function_arguments() {
+        check_format_with_width(
+            "x = foo.fold([1, 2, 3], |x, y| x + y)\n",
+            20,
+            "\
+x = foo.fold(
+  [1, 2, 3],
+  |x, y| x + y
+)
+",
+        );
+    }
+
+    #[test]
+    fn unwrappable_lines() {
+        // Indexing, parenthesized expressions, and lines that start blocks aren't wrapped
+        let source = "\
+x = foo[first_index..second_index]
+x = foo.fold(|x, y| x + y, initial_value)
+y = (first_value + second_value)
+z = [first_value, second_value] + x
+for x in [first_value, second_value]
+  x
+";
+        check_format_with_width(source, 20, source);
+    }
+
+    #[test]
+    fn parser_errors() {
+        assert!(matches!(
+            format("x = [1, 2"),
+            Err(FormatError::ParserError(_))
+        ));
+    }
+
+    #[test]
+    fn is_formatted_check() {
+        assert!(is_formatted("x = 1\n").unwrap());
+        assert!(!is_formatted("x=1\n").unwrap());
+    }
+}
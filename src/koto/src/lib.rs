@@ -32,13 +32,15 @@ use {
     koto_bytecode::{
         chunk_to_string, chunk_to_string_annotated, Chunk, CompilerError, LoaderError,
     },
-    koto_parser::{ParserError, Position},
+    koto_parser::{format_error_with_excerpt, ParserError, Position},
     koto_runtime::{
-        type_as_string, Error, Loader, RuntimeFunction, Value, ValueList, ValueVec, Vm, VmContext,
+        type_as_string, BacktraceFrame, Capabilities, Coroutine, Debugger, Error, InterruptHandle,
+        Loader, MemoryLimits, RuntimeFunction, Value, ValueList, ValueVec, Vm, VmContext,
     },
     std::{
-        path::PathBuf,
-        sync::{Arc, RwLockReadGuard, RwLockWriteGuard},
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex, RwLockReadGuard, RwLockWriteGuard},
+        time::Instant,
     },
 };
 
@@ -74,6 +76,16 @@ impl Koto {
         result
     }
 
+    /// Makes a runtime where scripts only have access to the given capabilities
+    ///
+    /// See [VmContext::with_capabilities].
+    pub fn with_capabilities(capabilities: Capabilities) -> Self {
+        Self {
+            runtime: Vm::with_capabilities(capabilities),
+            ..Self::default()
+        }
+    }
+
     pub fn compile(&mut self, script: &str) -> Result<Arc<Chunk>, LoaderError> {
         let compile_result = if self.settings.repl_mode {
             self.loader.compile_repl(script)
@@ -101,6 +113,15 @@ impl Koto {
         }
     }
 
+    /// Loads a chunk that was compiled ahead of time with [Chunk::serialize]
+    ///
+    /// The loaded chunk is then ready to be run with [Koto::run].
+    pub fn load_compiled(&mut self, data: &[u8]) -> Result<Arc<Chunk>, LoaderError> {
+        let chunk = self.loader.load_compiled(data, &self.script_path)?;
+        self.chunk = Some(chunk.clone());
+        Ok(chunk)
+    }
+
     pub fn run_with_args(&mut self, args: &[String]) -> Result<Value, String> {
         self.set_args(args);
         self.run()
@@ -155,6 +176,68 @@ impl Koto {
         self.runtime.context_mut()
     }
 
+    /// Attaches a step debugger to the runtime, see [Debugger]
+    pub fn set_debugger(&mut self, debugger: Option<Debugger>) {
+        self.runtime.set_debugger(debugger);
+    }
+
+    /// Returns the runtime's debugger, allowing breakpoints to be modified while it's running
+    pub fn debugger(&self) -> Option<Arc<Mutex<Debugger>>> {
+        self.runtime.debugger()
+    }
+
+    /// Sets the number of instructions that the runtime is allowed to execute
+    ///
+    /// See [Vm::set_instruction_limit].
+    pub fn set_instruction_limit(&mut self, limit: Option<u64>) {
+        self.runtime.set_instruction_limit(limit);
+    }
+
+    /// Sets a deadline for the runtime's execution
+    ///
+    /// See [Vm::set_deadline].
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.runtime.set_deadline(deadline);
+    }
+
+    /// Returns a handle that allows the runtime to be interrupted from another thread
+    pub fn interrupt_handle(&mut self) -> InterruptHandle {
+        self.runtime.interrupt_handle()
+    }
+
+    /// Sets limits on the memory that the runtime can use
+    ///
+    /// See [Vm::set_memory_limits].
+    pub fn set_memory_limits(&mut self, limits: MemoryLimits) {
+        self.runtime.set_memory_limits(limits);
+    }
+
+    /// Sets the directories that are searched for modules by `import` expressions
+    ///
+    /// See [VmContext::set_module_paths].
+    pub fn set_module_paths(&mut self, paths: Vec<PathBuf>) {
+        self.runtime.context_mut().set_module_paths(paths);
+    }
+
+    /// Removes a module from the runtime's module cache
+    ///
+    /// See [Vm::invalidate_module].
+    pub fn invalidate_module(&mut self, path: &Path) {
+        self.runtime.invalidate_module(path);
+    }
+
+    /// Reloads the imported modules that have been modified since they were loaded
+    ///
+    /// See [Vm::reload_modified_modules].
+    pub fn reload_modified_modules(
+        &mut self,
+        preserved_globals: &[&str],
+    ) -> Result<Vec<PathBuf>, String> {
+        self.runtime
+            .reload_modified_modules(preserved_globals)
+            .map_err(|e| self.format_error(e))
+    }
+
     pub fn set_args(&mut self, args: &[String]) {
         use Value::{Map, Str};
 
@@ -243,6 +326,36 @@ impl Koto {
             .map_err(|e| self.format_error(e))
     }
 
+    /// Makes a coroutine that runs the given function when it's resumed
+    ///
+    /// See [Vm::make_coroutine].
+    pub fn make_coroutine(
+        &mut self,
+        function: &Value,
+        args: &[Value],
+    ) -> Result<Coroutine, String> {
+        self.runtime
+            .make_coroutine(function, args)
+            .map_err(|e| self.format_error(e))
+    }
+
+    /// Resumes a coroutine, returning its next yielded value, or `None` if it has finished
+    ///
+    /// See [Coroutine::resume].
+    pub fn resume_coroutine(
+        &mut self,
+        coroutine: &Coroutine,
+        value: Value,
+    ) -> Result<Option<Value>, String> {
+        coroutine.resume(value).map_err(|e| self.format_error(e))
+    }
+
+    pub fn value_to_string(&mut self, value: &Value) -> Result<String, String> {
+        self.runtime
+            .value_to_string(value)
+            .map_err(|e| self.format_error(e))
+    }
+
     pub fn format_error(&self, error: Error) -> String {
         use Error::*;
         match error {
@@ -251,8 +364,9 @@ impl Koto {
                 chunk,
                 instruction,
                 extra_error,
+                backtrace,
             } => {
-                if let Some(extra_error) = extra_error {
+                let error = if let Some(extra_error) = extra_error {
                     self.format_vm_error(
                         &format!("{}: {}", message, extra_error),
                         chunk,
@@ -260,7 +374,8 @@ impl Koto {
                     )
                 } else {
                     self.format_vm_error(&message, chunk, instruction)
-                }
+                };
+                self.add_backtrace(error, &backtrace)
             }
             TestError { message, error } => {
                 format!("{}: {}", message, self.format_error(error.as_ref().clone()))
@@ -269,6 +384,24 @@ impl Koto {
                 self.format_loader_error(error, &self.runtime.chunk().debug_info.source)
             }
             ErrorWithoutLocation { message } => format!("Error: {}\n", message,),
+            ThrownValue {
+                value,
+                chunk,
+                instruction,
+                backtrace,
+            } => {
+                let error = self.format_vm_error(&value.to_string(), chunk, instruction);
+                self.add_backtrace(error, &backtrace)
+            }
+            Interrupted {
+                reason,
+                chunk,
+                instruction,
+            } => self.format_vm_error(
+                &format!("Execution stopped: {}", reason),
+                chunk,
+                instruction,
+            ),
         }
     }
 
@@ -306,10 +439,26 @@ impl Koto {
                     span.start,
                     span.end,
                 ),
+            LoaderError::DeserializationError(error) => error.to_string(),
             LoaderError::IoError(message) => message,
         }
     }
 
+    // Appends the error's backtrace when the error occurred in a nested function call
+    fn add_backtrace(&self, error: String, backtrace: &[BacktraceFrame]) -> String {
+        if self.settings.repl_mode || backtrace.len() < 2 {
+            return error;
+        }
+
+        let mut result = error.trim_end().to_string();
+        result += "\nBacktrace:";
+        for frame in backtrace {
+            result += &format!("\n  {}", frame);
+        }
+        result.push('\n');
+        result
+    }
+
     fn format_error_with_excerpt(
         &self,
         message: &str,
@@ -323,73 +472,6 @@ impl Koto {
             return message.to_string();
         }
 
-        let (excerpt, padding) = {
-            let excerpt_lines = source
-                .lines()
-                .skip((start_pos.line - 1) as usize)
-                .take((end_pos.line - start_pos.line + 1) as usize)
-                .collect::<Vec<_>>();
-
-            let line_numbers = (start_pos.line..=end_pos.line)
-                .map(|n| n.to_string())
-                .collect::<Vec<_>>();
-
-            let number_width = line_numbers.iter().max_by_key(|n| n.len()).unwrap().len();
-
-            let padding = " ".repeat(number_width + 2);
-
-            if start_pos.line == end_pos.line {
-                let mut excerpt = format!(
-                    " {:>width$} | {}\n",
-                    line_numbers.first().unwrap(),
-                    excerpt_lines.first().unwrap(),
-                    width = number_width
-                );
-
-                excerpt += &format!(
-                    "{}|{}",
-                    padding,
-                    format!(
-                        "{}{}",
-                        " ".repeat(start_pos.column as usize),
-                        "^".repeat((end_pos.column - start_pos.column) as usize)
-                    ),
-                );
-
-                (excerpt, padding)
-            } else {
-                let mut excerpt = String::new();
-
-                for (excerpt_line, line_number) in excerpt_lines.iter().zip(line_numbers.iter()) {
-                    excerpt += &format!(
-                        " {:>width$} | {}\n",
-                        line_number,
-                        excerpt_line,
-                        width = number_width
-                    );
-                }
-
-                (excerpt, padding)
-            }
-        };
-
-        let position_info = if let Some(path) = source_path {
-            format!(
-                "{} - {}:{}",
-                path.display(),
-                start_pos.line,
-                start_pos.column
-            )
-        } else {
-            format!("{}:{}", start_pos.line, start_pos.column)
-        };
-
-        format!(
-            "{message}\n --> {}\n{padding}|\n{excerpt}",
-            position_info,
-            padding = padding,
-            excerpt = excerpt,
-            message = message
-        )
+        format_error_with_excerpt(message, source_path.as_deref(), source, start_pos, end_pos)
     }
 }
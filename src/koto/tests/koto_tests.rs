@@ -91,10 +91,20 @@ assert_near 1 2 0.1
         run_script(script, None, true);
     }
 
+    #[test]
+    fn check_missing_operator_overload() {
+        let script = "
+x = {\"@+\": |self, other| 42}
+x * 2
+";
+        run_script(script, None, true);
+    }
+
     koto_test!(arithmetic);
     koto_test!(assignment);
     koto_test!(comments);
     koto_test!(control_flow);
+    koto_test!(coroutines);
     koto_test!(error_handling);
     koto_test!(function_closures);
     koto_test!(functions);
@@ -112,6 +122,7 @@ assert_near 1 2 0.1
     koto_test!(maps_and_lists);
     koto_test!(numbers);
     koto_test!(num2_4);
+    koto_test!(operator_overloading);
     koto_test!(primes);
     koto_test!(ranges);
     koto_test!(strings);
@@ -79,6 +79,7 @@ pub enum Token {
     Or,
     Return,
     Then,
+    Throw,
     True,
     Try,
     Until,
@@ -230,32 +231,14 @@ impl<'a> TokenLexer<'a> {
 
         let mut string_bytes = 1;
         let mut position = self.position;
+        position.column += 1;
 
-        while let Some(c) = chars.next() {
-            string_bytes += c.len_utf8();
-            position.column += c.width().unwrap_or(0) as u32;
-
-            match c {
-                '\\' => {
-                    if chars.peek() == Some(&'"') {
-                        chars.next();
-                        string_bytes += 1;
-                        position.column += 1;
-                    }
-                }
-                '\n' => {
-                    position.line += 1;
-                    position.column = 1;
-                }
-                '"' => {
-                    self.advance_to_position(string_bytes, position);
-                    return Some(String);
-                }
-                _ => {}
-            }
+        if consume_string_contents(&mut chars, &mut string_bytes, &mut position) {
+            self.advance_to_position(string_bytes, position);
+            Some(String)
+        } else {
+            Some(Error)
         }
-
-        Some(Error)
     }
 
     fn consume_number_or_subtract(&mut self, mut chars: Peekable<Chars>) -> Option<Token> {
@@ -371,6 +354,7 @@ impl<'a> TokenLexer<'a> {
         check_keyword!("or", Or);
         check_keyword!("return", Return);
         check_keyword!("then", Then);
+        check_keyword!("throw", Throw);
         check_keyword!("true", True);
         check_keyword!("try", Try);
         check_keyword!("until", Until);
@@ -479,6 +463,211 @@ fn is_whitespace(c: char) -> bool {
     matches!(c, ' ' | '\t')
 }
 
+// Counts a character that's been consumed while lexing a string
+fn count_string_char(c: char, string_bytes: &mut usize, position: &mut Position) {
+    *string_bytes += c.len_utf8();
+
+    if c == '\n' {
+        position.line += 1;
+        position.column = 1;
+    } else {
+        position.column += c.width().unwrap_or(0) as u32;
+    }
+}
+
+// Consumes the contents of a string, up to and including the closing '"'
+//
+// Returns false if the string isn't terminated.
+fn consume_string_contents(
+    chars: &mut Peekable<Chars>,
+    string_bytes: &mut usize,
+    position: &mut Position,
+) -> bool {
+    while let Some(c) = chars.next() {
+        count_string_char(c, string_bytes, position);
+
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    count_string_char(escaped, string_bytes, position);
+                }
+            }
+            '{' => match chars.peek().copied() {
+                // '{{' and '{}' are left in the string, e.g. for use with string.format
+                Some('{') | Some('}') => {
+                    let c = chars.next().unwrap();
+                    count_string_char(c, string_bytes, position);
+                }
+                _ if is_format_placeholder(chars) => {
+                    consume_format_placeholder(chars, string_bytes, position);
+                }
+                _ => {
+                    if !consume_string_expression(chars, string_bytes, position) {
+                        return false;
+                    }
+                }
+            },
+            '"' => return true,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+// Returns true if the characters following a '{' in a string are a string.format placeholder
+//
+// Placeholders like '{0}', '{:.2}', and '{1:.2}' are left in the string rather than being
+// treated as embedded expressions. '{}' is handled separately along with '{{'.
+//
+// A ':' can't follow an index in an expression, so placeholders with format specs are left in
+// the string even when they're unterminated, with string.format reporting the error.
+fn is_format_placeholder(chars: &Peekable<Chars>) -> bool {
+    let mut has_index = false;
+
+    for c in chars.clone() {
+        match c {
+            '0'..='9' => has_index = true,
+            '}' => return has_index,
+            ':' => return true,
+            _ => return false,
+        }
+    }
+
+    false
+}
+
+// Returns true if the character can appear in a string.format placeholder
+fn is_format_placeholder_char(c: char) -> bool {
+    !matches!(c, '"' | '{' | '\\' | '\n' | '\r')
+}
+
+// Consumes a string.format placeholder, up to and including the closing '}'
+//
+// Consumption stops early at the end of the string, or at a character that can't be part of
+// the placeholder, leaving the character to be handled by the caller.
+fn consume_format_placeholder(
+    chars: &mut Peekable<Chars>,
+    string_bytes: &mut usize,
+    position: &mut Position,
+) {
+    while let Some(c) = chars.next_if(|c| is_format_placeholder_char(*c)) {
+        count_string_char(c, string_bytes, position);
+        if c == '}' {
+            break;
+        }
+    }
+}
+
+// Consumes an expression that's embedded in a string, up to and including the closing '}'
+//
+// Returns false if the expression isn't terminated.
+fn consume_string_expression(
+    chars: &mut Peekable<Chars>,
+    string_bytes: &mut usize,
+    position: &mut Position,
+) -> bool {
+    let mut nest_count = 1;
+
+    while let Some(c) = chars.next() {
+        count_string_char(c, string_bytes, position);
+
+        match c {
+            '"' if !consume_string_contents(chars, string_bytes, position) => return false,
+            '{' => nest_count += 1,
+            '}' => {
+                nest_count -= 1;
+                if nest_count == 0 {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// A section of a string literal, see [string_sections]
+#[derive(Clone, Debug, PartialEq)]
+pub enum StringSection<'a> {
+    /// Text from the string, with its escape sequences left in place
+    Literal(&'a str),
+    /// An expression embedded in the string between `{` and `}`
+    Expression {
+        /// The byte offset of the expression's start in the string's slice
+        start: usize,
+        /// The byte offset of the expression's end in the string's slice
+        end: usize,
+        /// The expression's position in the source
+        position: Position,
+    },
+}
+
+/// Splits a string literal into sections of literal text and embedded expressions
+///
+/// `string` is the slice of a [Token::String], including its quotes,
+/// and `position` is the position of the string's opening quote.
+pub fn string_sections(string: &str, mut position: Position) -> Vec<StringSection<'_>> {
+    let mut result = Vec::new();
+
+    // Skip the opening quote
+    let mut offset = 1;
+    let mut literal_start = offset;
+    position.column += 1;
+
+    let contents = &string[..string.len() - 1];
+    let mut chars = contents[offset..].chars().peekable();
+
+    while let Some(c) = chars.next() {
+        count_string_char(c, &mut offset, &mut position);
+
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    count_string_char(escaped, &mut offset, &mut position);
+                }
+            }
+            '{' => match chars.peek().copied() {
+                Some('{') | Some('}') => {
+                    let c = chars.next().unwrap();
+                    count_string_char(c, &mut offset, &mut position);
+                }
+                _ if is_format_placeholder(&chars) => {
+                    consume_format_placeholder(&mut chars, &mut offset, &mut position);
+                }
+                _ => {
+                    let expression_start = offset;
+                    let expression_position = position;
+
+                    if literal_start < expression_start - 1 {
+                        result.push(StringSection::Literal(
+                            &contents[literal_start..expression_start - 1],
+                        ));
+                    }
+
+                    consume_string_expression(&mut chars, &mut offset, &mut position);
+
+                    result.push(StringSection::Expression {
+                        start: expression_start,
+                        end: offset - 1,
+                        position: expression_position,
+                    });
+
+                    literal_start = offset;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    if literal_start < contents.len() {
+        result.push(StringSection::Literal(&contents[literal_start..]));
+    }
+
+    result
+}
+
 pub fn is_id_start(c: char) -> bool {
     UnicodeXID::is_xid_start(c)
 }
@@ -545,6 +734,27 @@ impl<'a> KotoLexer<'a> {
         }
     }
 
+    /// Makes a lexer that starts at the given byte offset in the source
+    ///
+    /// This is used to lex expressions that are embedded in strings,
+    /// with `position` being the position of the offset in the source.
+    pub fn with_start(source: &'a str, offset: usize, position: Position) -> Self {
+        let mut lexer = TokenLexer::new(source);
+        lexer.previous = offset;
+        lexer.current = offset;
+        lexer.position = position;
+        lexer.span = Span {
+            start: position,
+            end: position,
+        };
+
+        Self {
+            lexer,
+            peeked_tokens: Vec::new(),
+            current_peek_index: 0,
+        }
+    }
+
     pub fn peek(&mut self) -> Option<Token> {
         if self.peeked_tokens.is_empty() {
             self.peek_n(0)
@@ -813,6 +1023,114 @@ true"#;
         );
     }
 
+    #[test]
+    fn strings_with_embedded_expressions() {
+        let input = r#"
+"hello {name}!"
+"{x.get "}"} {{}} \{"
+true"#;
+        check_lexer_output(
+            input,
+            &[
+                (NewLine, None, 2),
+                (String, Some(r#""hello {name}!""#), 2),
+                (NewLine, None, 3),
+                (String, Some(r#""{x.get "}"} {{}} \{""#), 3),
+                (NewLine, None, 4),
+                (True, None, 4),
+            ],
+        );
+    }
+
+    #[test]
+    fn string_sections_with_expressions() {
+        use StringSection::*;
+
+        let string = r#""a {x} {{}} {f "{y}"}""#;
+        assert_eq!(
+            string_sections(string, Position { line: 2, column: 3 }),
+            vec![
+                Literal("a "),
+                Expression {
+                    start: 4,
+                    end: 5,
+                    position: Position { line: 2, column: 7 },
+                },
+                Literal(" {{}} "),
+                Expression {
+                    start: 13,
+                    end: 20,
+                    position: Position {
+                        line: 2,
+                        column: 16
+                    },
+                },
+            ]
+        );
+
+        assert_eq!(
+            string_sections(r#""{}""#, Position::default()),
+            vec![Literal("{}")]
+        );
+
+        // string.format placeholders are left in the string
+        assert_eq!(
+            string_sections(r#""{0} {12:.2} {:>4} {x}""#, Position::default()),
+            vec![
+                Literal("{0} {12:.2} {:>4} "),
+                Expression {
+                    start: 20,
+                    end: 21,
+                    position: Position {
+                        line: 1,
+                        column: 21
+                    },
+                },
+            ]
+        );
+        assert!(string_sections(r#""""#, Position::default()).is_empty());
+
+        // Unterminated placeholders end at the string's closing quote
+        assert_eq!(
+            string_sections(r#""{1: {x}""#, Position::default()),
+            vec![
+                Literal("{1: "),
+                Expression {
+                    start: 6,
+                    end: 7,
+                    position: Position { line: 1, column: 7 },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn strings_with_unterminated_format_placeholders() {
+        let input = r#"
+a = "{1:"
+"{:"
+x = {y: 1}"#;
+        check_lexer_output(
+            input,
+            &[
+                (NewLine, None, 2),
+                (Id, Some("a"), 2),
+                (Assign, None, 2),
+                (String, Some(r#""{1:""#), 2),
+                (NewLine, None, 3),
+                (String, Some(r#""{:""#), 3),
+                (NewLine, None, 4),
+                (Id, Some("x"), 4),
+                (Assign, None, 4),
+                (MapStart, None, 4),
+                (Id, Some("y"), 4),
+                (Colon, None, 4),
+                (Number, Some("1"), 4),
+                (MapEnd, None, 4),
+            ],
+        );
+    }
+
     #[test]
     fn numbers() {
         let input = "\
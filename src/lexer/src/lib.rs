@@ -3,5 +3,7 @@
 mod lexer;
 mod span;
 
-pub use lexer::{is_id_continue, is_id_start, KotoLexer as Lexer, Token};
+pub use lexer::{
+    is_id_continue, is_id_start, string_sections, KotoLexer as Lexer, StringSection, Token,
+};
 pub use span::{Position, Span};
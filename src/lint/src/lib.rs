@@ -0,0 +1,367 @@
+//! A static linter for Koto scripts
+//!
+//! The script is parsed with `koto_parser`, and then the AST is checked for common mistakes,
+//! like values that are assigned but never used, or code that can never be reached.
+//!
+//! ```
+//! use koto_lint::{lint, LintSettings, Rule};
+//!
+//! let script = "
+//! f = |x, y|
+//!   return x
+//!   x + 1
+//! f 1, 2
+//! ";
+//!
+//! let warnings = lint(script, &LintSettings::default()).unwrap();
+//! let rules: Vec<Rule> = warnings.iter().map(|warning| warning.rule).collect();
+//! assert_eq!(rules, vec![Rule::UnusedArgument, Rule::UnreachableCode]);
+//!
+//! let mut settings = LintSettings::default();
+//! settings.disabled_rules.insert(Rule::UnreachableCode);
+//! assert_eq!(lint(script, &settings).unwrap().len(), 1);
+//! ```
+
+mod linter;
+
+use {
+    koto_parser::{format_error_with_excerpt, Parser, ParserError, Span},
+    std::{collections::HashSet, fmt, path::Path},
+};
+
+/// The checks that are performed by the linter
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// A local value is assigned but never used
+    UnusedLocal,
+    /// A function argument is never used
+    UnusedArgument,
+    /// A value is assigned with the same name as a core library module, e.g. `list`
+    ShadowedCoreModule,
+    /// Expressions that follow a `return`, `break`, or `continue`
+    UnreachableCode,
+    /// A match arm that follows an arm that matches any value
+    UnreachableMatchArm,
+    /// A comparison against an empty value, e.g. `x == ()`
+    ComparisonWithEmpty,
+    /// A global value is exported from inside a function without being exported by the script
+    UndeclaredGlobal,
+}
+
+impl Rule {
+    /// All of the linter's rules
+    pub const ALL: &'static [Rule] = &[
+        Rule::UnusedLocal,
+        Rule::UnusedArgument,
+        Rule::ShadowedCoreModule,
+        Rule::UnreachableCode,
+        Rule::UnreachableMatchArm,
+        Rule::ComparisonWithEmpty,
+        Rule::UndeclaredGlobal,
+    ];
+
+    /// The rule's name, as shown in warnings
+    pub fn name(&self) -> &'static str {
+        use Rule::*;
+        match self {
+            UnusedLocal => "unused-local",
+            UnusedArgument => "unused-argument",
+            ShadowedCoreModule => "shadowed-core-module",
+            UnreachableCode => "unreachable-code",
+            UnreachableMatchArm => "unreachable-match-arm",
+            ComparisonWithEmpty => "comparison-with-empty",
+            UndeclaredGlobal => "undeclared-global",
+        }
+    }
+
+    /// Returns the rule with the given name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|rule| rule.name() == name).cloned()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Settings for the linter
+#[derive(Clone, Debug, Default)]
+pub struct LintSettings {
+    /// Rules that won't be checked
+    pub disabled_rules: HashSet<Rule>,
+}
+
+impl LintSettings {
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        !self.disabled_rules.contains(&rule)
+    }
+}
+
+/// A problem that was found by the linter
+#[derive(Clone, Debug)]
+pub struct Warning {
+    pub rule: Rule,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Warning {
+    /// Formats the warning along with an excerpt of the script that caused it
+    pub fn format_with_excerpt(&self, source: &str, source_path: Option<&Path>) -> String {
+        format_error_with_excerpt(
+            &format!("Warning: {} ({})", self.message, self.rule),
+            source_path,
+            source,
+            self.span.start,
+            self.span.end,
+        )
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) - {}:{}",
+            self.message, self.rule, self.span.start.line, self.span.start.column
+        )
+    }
+}
+
+/// Checks the script for problems, returning warnings ordered by their position in the script
+pub fn lint(source: &str, settings: &LintSettings) -> Result<Vec<Warning>, ParserError> {
+    let (ast, constants) = Parser::parse(source)?;
+    Ok(linter::Linter::new(&ast, &constants, source, settings).lint())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_lint(source: &str, expected: &[(Rule, u32)]) {
+        let warnings = match lint(source, &LintSettings::default()) {
+            Ok(warnings) => warnings,
+            Err(error) => panic!("Failed to parse script: {}", error),
+        };
+        let result: Vec<(Rule, u32)> = warnings
+            .iter()
+            .map(|warning| (warning.rule, warning.span.start.line))
+            .collect();
+        assert_eq!(result, expected, "{:#?}", warnings);
+    }
+
+    #[test]
+    fn unused_locals() {
+        let source = "
+x = 1
+y = 2
+f = |a|
+  z = a
+  y
+f 42
+";
+        check_lint(source, &[(Rule::UnusedLocal, 2), (Rule::UnusedLocal, 5)]);
+    }
+
+    #[test]
+    fn values_captured_by_nested_functions_are_used() {
+        let source = "
+f = || g()
+g = || x
+x = 1
+f()
+";
+        check_lint(source, &[]);
+    }
+
+    #[test]
+    fn reassigned_captures_are_used() {
+        let source = "
+x = 1
+f = ||
+  x = x + 1
+  x
+f()
+";
+        check_lint(source, &[]);
+    }
+
+    #[test]
+    fn recursive_functions_are_used() {
+        let source = "
+export fib = |n|
+  if n < 2
+    n
+  else
+    (fib n - 1) + (fib n - 2)
+";
+        check_lint(source, &[]);
+    }
+
+    #[test]
+    fn unused_arguments() {
+        let source = "
+f = |self, a, b, _| a
+f()
+for i, x in [1, 2].enumerate()
+  i
+";
+        check_lint(source, &[(Rule::UnusedArgument, 2), (Rule::UnusedLocal, 4)]);
+    }
+
+    #[test]
+    fn map_shorthand_uses_local() {
+        let source = "
+x = 1
+{x}
+";
+        check_lint(source, &[]);
+    }
+
+    #[test]
+    fn shadowed_core_modules() {
+        let source = "
+list = [1, 2, 3]
+f = |string| string
+f list
+";
+        check_lint(
+            source,
+            &[(Rule::ShadowedCoreModule, 2), (Rule::ShadowedCoreModule, 3)],
+        );
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let source = "
+f = ||
+  return 1
+  x = 2
+  x
+for x in 0..10
+  if x > 5
+    break
+    x
+f()
+";
+        check_lint(
+            source,
+            &[(Rule::UnreachableCode, 4), (Rule::UnreachableCode, 9)],
+        );
+    }
+
+    #[test]
+    fn unreachable_code_after_throw() {
+        let source = "
+f = |x|
+  throw {message: x}
+  x
+f 42
+";
+        check_lint(source, &[(Rule::UnreachableCode, 4)]);
+    }
+
+    #[test]
+    fn unreachable_match_arms() {
+        let source = "
+f = |x|
+  match x
+    0 then 1
+    _ then 2
+    1 then 3
+    2 then 4
+g = |x|
+  match x
+    y if y > 10 then 1
+    z then z
+    2 then 3
+f g
+";
+        check_lint(
+            source,
+            &[
+                (Rule::UnreachableMatchArm, 6),
+                (Rule::UnreachableMatchArm, 12),
+            ],
+        );
+    }
+
+    #[test]
+    fn comparisons_with_empty() {
+        let source = "
+f = |x| x == ()
+g = |x| () != x
+h = |x| x == 0
+f g h
+";
+        check_lint(
+            source,
+            &[
+                (Rule::ComparisonWithEmpty, 2),
+                (Rule::ComparisonWithEmpty, 3),
+            ],
+        );
+    }
+
+    #[test]
+    fn undeclared_globals() {
+        let source = "
+f = ||
+  export x = 1
+  export y = 2
+f()
+export y = 0
+";
+        check_lint(source, &[(Rule::UndeclaredGlobal, 3)]);
+    }
+
+    #[test]
+    fn disabled_rules() {
+        let source = "
+f = |list|
+  x = 1
+  return 0
+  2
+f()
+";
+        let mut settings = LintSettings {
+            disabled_rules: Rule::ALL.iter().cloned().collect(),
+        };
+        assert!(lint(source, &settings).unwrap().is_empty());
+
+        settings.disabled_rules.remove(&Rule::UnusedLocal);
+        let warnings = lint(source, &settings).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].rule, Rule::UnusedLocal);
+    }
+
+    #[test]
+    fn warning_spans() {
+        let source = "f = |a, b| a\nf()\n";
+        let warnings = lint(source, &LintSettings::default()).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].span.start.column, 9);
+        assert_eq!(warnings[0].span.end.column, 10);
+
+        let formatted = warnings[0].format_with_excerpt(source, None);
+        assert_eq!(
+            formatted,
+            "\
+Warning: Unused argument 'b' (unused-argument)
+ --> 1:9
+   |
+ 1 | f = |a, b| a
+   |         ^"
+        );
+    }
+
+    #[test]
+    fn rule_names() {
+        for rule in Rule::ALL {
+            assert_eq!(Rule::from_name(rule.name()), Some(*rule));
+        }
+        assert_eq!(Rule::from_name("not-a-rule"), None);
+    }
+}
@@ -0,0 +1,628 @@
+use {
+    crate::{LintSettings, Rule, Warning},
+    koto_parser::{
+        AssignOp, AssignTarget, Ast, AstFor, AstIf, AstIndex, AstOp, AstTry, ConstantIndex,
+        ConstantPool, Function, LookupNode, MatchArm, Node, Position, Scope, Span, StringNode,
+    },
+    std::collections::HashSet,
+};
+
+// The names of the modules in Koto's core library
+const CORE_LIB_MODULES: &[&str] = &[
+    "coroutine",
+    "io",
+    "iterator",
+    "koto",
+    "list",
+    "map",
+    "num2",
+    "num4",
+    "number",
+    "range",
+    "string",
+    "test",
+    "thread",
+    "tuple",
+];
+
+struct Definition<'a> {
+    name: &'a str,
+    span: Span,
+    is_argument: bool,
+    used: bool,
+}
+
+// The values that are defined and accessed in a function, or in the script's main block
+#[derive(Default)]
+struct FunctionScope<'a> {
+    definitions: Vec<Definition<'a>>,
+    // IDs that were accessed before being defined in the scope,
+    // which are either captured from a parent scope or are globals
+    non_local_accesses: HashSet<&'a str>,
+    // Non-local IDs that were accessed by nested functions
+    nested_accesses: HashSet<&'a str>,
+}
+
+impl<'a> FunctionScope<'a> {
+    fn definition_mut(&mut self, name: &str) -> Option<&mut Definition<'a>> {
+        self.definitions
+            .iter_mut()
+            .find(|definition| definition.name == name)
+    }
+}
+
+pub(crate) struct Linter<'a> {
+    ast: &'a Ast,
+    constants: &'a ConstantPool,
+    source_lines: Vec<Vec<char>>,
+    settings: &'a LintSettings,
+    scopes: Vec<FunctionScope<'a>>,
+    // Values exported from the script's main block
+    exported_names: HashSet<&'a str>,
+    // Values exported from inside functions, checked against exported_names once the whole
+    // script has been visited.
+    exported_from_functions: Vec<(&'a str, Span)>,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Linter<'a> {
+    pub fn new(
+        ast: &'a Ast,
+        constants: &'a ConstantPool,
+        source: &str,
+        settings: &'a LintSettings,
+    ) -> Self {
+        Self {
+            ast,
+            constants,
+            source_lines: source.lines().map(|line| line.chars().collect()).collect(),
+            settings,
+            scopes: vec![],
+            exported_names: HashSet::new(),
+            exported_from_functions: vec![],
+            warnings: vec![],
+        }
+    }
+
+    pub fn lint(mut self) -> Vec<Warning> {
+        let ast = self.ast;
+        if let Some(Node::MainBlock { body, .. }) = ast.entry_point().map(|entry| &entry.node) {
+            self.scopes.push(FunctionScope::default());
+            self.visit_block(body);
+            self.pop_scope();
+        }
+
+        for (name, span) in std::mem::take(&mut self.exported_from_functions) {
+            if !self.exported_names.contains(name) {
+                self.warn(
+                    Rule::UndeclaredGlobal,
+                    format!(
+                        "'{}' is exported from a function without being exported by the script",
+                        name
+                    ),
+                    span,
+                );
+            }
+        }
+
+        self.warnings
+            .sort_by_key(|warning| (warning.span.start.line, warning.span.start.column));
+        self.warnings
+    }
+
+    fn visit(&mut self, index: AstIndex) {
+        let node = self.ast.node(index);
+
+        match &node.node {
+            Node::Empty
+            | Node::BoolTrue
+            | Node::BoolFalse
+            | Node::Number0
+            | Node::Number1
+            | Node::Float(_)
+            | Node::Int(_)
+            | Node::Str(_)
+            | Node::RangeFull
+            | Node::Wildcard
+            | Node::Ellipsis(_)
+            | Node::Break
+            | Node::Continue
+            | Node::Return => {}
+            Node::Id(id) => self.access(*id),
+            Node::Lookup((lookup_node, next)) => {
+                match lookup_node {
+                    LookupNode::Root(root) => self.visit(*root),
+                    LookupNode::Id(_) => {}
+                    LookupNode::Index(index) => self.visit(*index),
+                    LookupNode::Call(args) => self.visit_nodes(args),
+                }
+                if let Some(next) = next {
+                    self.visit(*next);
+                }
+            }
+            Node::Num2(elements)
+            | Node::Num4(elements)
+            | Node::List(elements)
+            | Node::Tuple(elements)
+            | Node::TempTuple(elements) => self.visit_nodes(elements),
+            Node::Range { start, end, .. } => {
+                self.visit(*start);
+                self.visit(*end);
+            }
+            Node::RangeFrom { start } => self.visit(*start),
+            Node::RangeTo { end, .. } => self.visit(*end),
+            Node::InterpolatedString(nodes) => {
+                for node in nodes.iter() {
+                    if let StringNode::Expression(expression) = node {
+                        self.visit(*expression);
+                    }
+                }
+            }
+            Node::Map(entries) => {
+                for (key, value) in entries.iter() {
+                    match value {
+                        Some(value) => self.visit(*value),
+                        // e.g. `{x}` is shorthand for `{x: x}`
+                        None => self.access(*key),
+                    }
+                }
+            }
+            Node::MainBlock { body, .. } | Node::Block(body) => self.visit_block(body),
+            Node::Function(function) => self.visit_function(function, node.span),
+            Node::Call { function, args } => {
+                self.visit(*function);
+                self.visit_nodes(args);
+            }
+            Node::Import { from, items } => self.visit_import(from, items, node.span, false),
+            Node::Assign {
+                target,
+                op,
+                expression,
+            } => {
+                let expression_node = self.ast.node(*expression);
+                if let Node::Import { from, items } = &expression_node.node {
+                    self.visit_import(from, items, expression_node.span, true);
+                    self.visit_assign_target(target, *op);
+                } else if matches!(expression_node.node, Node::Function(_)) {
+                    // Assign before visiting the function so that recursive calls are counted
+                    self.visit_assign_target(target, *op);
+                    self.visit(*expression);
+                } else {
+                    self.visit(*expression);
+                    self.visit_assign_target(target, *op);
+                }
+            }
+            Node::MultiAssign {
+                targets,
+                expressions,
+            } => {
+                let expressions_node = self.ast.node(*expressions);
+                if let Node::Import { from, items } = &expressions_node.node {
+                    self.visit_import(from, items, expressions_node.span, true);
+                } else {
+                    self.visit(*expressions);
+                }
+                for target in targets.iter() {
+                    self.visit_assign_target(target, AssignOp::Equal);
+                }
+            }
+            Node::BinaryOp { op, lhs, rhs } => {
+                use AstOp::*;
+
+                if matches!(
+                    op,
+                    Equal | NotEqual | Less | LessOrEqual | Greater | GreaterOrEqual
+                ) && (self.is_empty(*lhs) || self.is_empty(*rhs))
+                {
+                    self.warn(
+                        Rule::ComparisonWithEmpty,
+                        "Comparison with '()'".to_string(),
+                        *self.ast.span(node.span),
+                    );
+                }
+
+                self.visit(*lhs);
+                self.visit(*rhs);
+            }
+            Node::If(AstIf {
+                condition,
+                then_node,
+                else_if_blocks,
+                else_node,
+            }) => {
+                self.visit(*condition);
+                self.visit(*then_node);
+                for (else_if_condition, else_if_block) in else_if_blocks.iter() {
+                    self.visit(*else_if_condition);
+                    self.visit(*else_if_block);
+                }
+                if let Some(else_node) = else_node {
+                    self.visit(*else_node);
+                }
+            }
+            Node::Match { expression, arms } => {
+                self.visit(*expression);
+                self.visit_match_arms(arms);
+            }
+            Node::For(AstFor { args, range, body }) => {
+                self.visit(*range);
+                let for_span = *self.ast.span(node.span);
+                for arg in args.iter().flatten() {
+                    let name = self.constants.get_str(*arg);
+                    let span = self.find_id(name, for_span.start).unwrap_or(for_span);
+                    self.define(name, span, false);
+                }
+                self.visit(*body);
+            }
+            Node::Loop { body } => self.visit(*body),
+            Node::While { condition, body } | Node::Until { condition, body } => {
+                self.visit(*condition);
+                self.visit(*body);
+            }
+            Node::ReturnExpression(expression)
+            | Node::CopyExpression(expression)
+            | Node::Negate(expression)
+            | Node::Throw(expression)
+            | Node::Yield(expression)
+            | Node::Debug { expression, .. } => self.visit(*expression),
+            Node::Try(AstTry {
+                try_block,
+                catch_arg,
+                catch_block,
+                finally_block,
+            }) => {
+                self.visit(*try_block);
+                if let Some(catch_arg) = catch_arg {
+                    let name = self.constants.get_str(*catch_arg);
+                    let try_span = *self.ast.span(node.span);
+                    let span = self
+                        .find_id("catch", try_span.start)
+                        .and_then(|catch_span| self.find_id(name, catch_span.end))
+                        .unwrap_or(try_span);
+                    self.define(name, span, false);
+                }
+                self.visit(*catch_block);
+                if let Some(finally_block) = finally_block {
+                    self.visit(*finally_block);
+                }
+            }
+        }
+    }
+
+    fn visit_nodes(&mut self, nodes: &[AstIndex]) {
+        for node in nodes.iter() {
+            self.visit(*node);
+        }
+    }
+
+    fn visit_block(&mut self, body: &[AstIndex]) {
+        let exit_position = body.iter().position(|expression| {
+            matches!(
+                self.ast.node(*expression).node,
+                Node::Return
+                    | Node::ReturnExpression(_)
+                    | Node::Throw(_)
+                    | Node::Break
+                    | Node::Continue
+            )
+        });
+
+        if let Some(exit_position) = exit_position {
+            if let (Some(first), Some(last)) = (body.get(exit_position + 1), body.last()) {
+                let span = Span {
+                    start: self.node_span(*first).start,
+                    end: self.node_span(*last).end,
+                };
+                self.warn(Rule::UnreachableCode, "Unreachable code".to_string(), span);
+            }
+        }
+
+        // Unreachable expressions are still visited so that their accesses are counted
+        self.visit_nodes(body);
+    }
+
+    fn visit_function(&mut self, function: &Function, span_index: AstIndex) {
+        let function_span = *self.ast.span(span_index);
+
+        self.scopes.push(FunctionScope::default());
+
+        for arg in function.args.iter().flatten() {
+            let name = self.constants.get_str(*arg);
+            if name == "self" {
+                continue;
+            }
+            let span = self
+                .find_id(name, function_span.start)
+                .unwrap_or(function_span);
+            self.define(name, span, true);
+        }
+
+        self.visit(function.body);
+        self.pop_scope();
+    }
+
+    // Imported items are assigned to locals
+    //
+    // When the import is used as an expression, e.g. `x = import foo`, then the imported items
+    // are considered to be used.
+    fn visit_import(
+        &mut self,
+        from: &[ConstantIndex],
+        items: &[Vec<ConstantIndex>],
+        span_index: AstIndex,
+        is_expression: bool,
+    ) {
+        let import_span = *self.ast.span(span_index);
+        let search_start = self
+            .find_id("import", import_span.start)
+            .map_or(import_span.start, |span| span.end);
+
+        for item in items.iter() {
+            if let Some(id) = item.last() {
+                let name = self.constants.get_str(*id);
+                let span = self.find_id(name, search_start).unwrap_or(import_span);
+
+                if from.is_empty() && item.len() == 1 {
+                    // Importing a core library module doesn't shadow it, e.g. `import io`
+                    self.add_definition(name, span, false);
+                } else {
+                    self.define(name, span, false);
+                }
+
+                if is_expression {
+                    self.mark_used(name);
+                }
+            }
+        }
+    }
+
+    fn visit_assign_target(&mut self, target: &AssignTarget, op: AssignOp) {
+        let target_node = self.ast.node(target.target_index);
+
+        match &target_node.node {
+            Node::Id(id) => {
+                let name = self.constants.get_str(*id);
+                let span = *self.ast.span(target_node.span);
+
+                match target.scope {
+                    Scope::Local => {
+                        if op == AssignOp::Equal {
+                            self.define(name, span, false);
+                        } else {
+                            // Compound assignments access the value before assigning to it,
+                            // which could be a capture of a value from a parent scope.
+                            self.access(*id);
+                            self.define(name, span, false);
+                            self.mark_used(name);
+                        }
+                    }
+                    Scope::Global => {
+                        self.check_core_module_shadowing(name, span);
+                        if self.scopes.len() > 1 {
+                            self.exported_from_functions.push((name, span));
+                        } else {
+                            self.exported_names.insert(name);
+                        }
+                    }
+                }
+            }
+            // Assigning to a lookup accesses the lookup's root
+            _ => self.visit(target.target_index),
+        }
+    }
+
+    fn visit_match_arms(&mut self, arms: &[MatchArm]) {
+        let mut unreachable_arm_reported = false;
+        let mut previous_arm_matches_anything = false;
+
+        for arm in arms.iter() {
+            if previous_arm_matches_anything && !unreachable_arm_reported {
+                if let Some(first_pattern) = arm.patterns.first() {
+                    self.warn(
+                        Rule::UnreachableMatchArm,
+                        "Unreachable match arm, a previous arm matches any value".to_string(),
+                        self.node_span(*first_pattern),
+                    );
+                    unreachable_arm_reported = true;
+                }
+            }
+
+            for pattern in arm.patterns.iter() {
+                self.visit_match_pattern(*pattern);
+            }
+            if let Some(condition) = arm.condition {
+                self.visit(condition);
+            }
+            self.visit(arm.expression);
+
+            if arm.condition.is_none()
+                && arm
+                    .patterns
+                    .iter()
+                    .any(|pattern| self.pattern_matches_anything(*pattern))
+            {
+                previous_arm_matches_anything = true;
+            }
+        }
+    }
+
+    fn visit_match_pattern(&mut self, pattern: AstIndex) {
+        let pattern_node = self.ast.node(pattern);
+
+        match &pattern_node.node {
+            Node::Id(id) | Node::Ellipsis(Some(id)) => {
+                let name = self.constants.get_str(*id);
+                let span = *self.ast.span(pattern_node.span);
+                self.define(name, span, false);
+            }
+            Node::List(patterns) | Node::Tuple(patterns) | Node::TempTuple(patterns) => {
+                for nested_pattern in patterns.iter() {
+                    self.visit_match_pattern(*nested_pattern);
+                }
+            }
+            _ => self.visit(pattern),
+        }
+    }
+
+    fn pattern_matches_anything(&self, pattern: AstIndex) -> bool {
+        match &self.ast.node(pattern).node {
+            Node::Wildcard | Node::Id(_) => true,
+            Node::TempTuple(patterns) => patterns
+                .iter()
+                .all(|nested_pattern| self.pattern_matches_anything(*nested_pattern)),
+            _ => false,
+        }
+    }
+
+    fn define(&mut self, name: &'a str, span: Span, is_argument: bool) {
+        if self.add_definition(name, span, is_argument) {
+            self.check_core_module_shadowing(name, span);
+        }
+    }
+
+    // Adds a definition to the current scope, returning false if the name was already defined
+    fn add_definition(&mut self, name: &'a str, span: Span, is_argument: bool) -> bool {
+        let scope = self.scopes.last_mut().expect("Missing scope");
+
+        if scope.definition_mut(name).is_none() {
+            scope.definitions.push(Definition {
+                name,
+                span,
+                is_argument,
+                used: false,
+            });
+            true
+        } else {
+            false
+        }
+    }
+
+    fn mark_used(&mut self, name: &str) {
+        let scope = self.scopes.last_mut().expect("Missing scope");
+        if let Some(definition) = scope.definition_mut(name) {
+            definition.used = true;
+        }
+    }
+
+    fn access(&mut self, id: ConstantIndex) {
+        let name = self.constants.get_str(id);
+        let scope = self.scopes.last_mut().expect("Missing scope");
+
+        match scope.definition_mut(name) {
+            Some(definition) => definition.used = true,
+            None => {
+                scope.non_local_accesses.insert(name);
+            }
+        }
+    }
+
+    fn pop_scope(&mut self) {
+        let mut scope = self.scopes.pop().expect("Missing scope");
+
+        // Nested functions can access values that are defined later in the scope,
+        // e.g. a function that calls a function that's defined after it.
+        let mut unresolved = std::mem::take(&mut scope.non_local_accesses);
+        for name in std::mem::take(&mut scope.nested_accesses) {
+            match scope.definition_mut(name) {
+                Some(definition) => definition.used = true,
+                None => {
+                    unresolved.insert(name);
+                }
+            }
+        }
+
+        for definition in scope.definitions.iter() {
+            if !definition.used {
+                let (rule, description) = if definition.is_argument {
+                    (Rule::UnusedArgument, "argument")
+                } else {
+                    (Rule::UnusedLocal, "local value")
+                };
+                self.warn(
+                    rule,
+                    format!("Unused {} '{}'", description, definition.name),
+                    definition.span,
+                );
+            }
+        }
+
+        if let Some(parent) = self.scopes.last_mut() {
+            parent.nested_accesses.extend(unresolved);
+        }
+    }
+
+    fn check_core_module_shadowing(&mut self, name: &str, span: Span) {
+        if CORE_LIB_MODULES.contains(&name) {
+            self.warn(
+                Rule::ShadowedCoreModule,
+                format!(
+                    "'{}' shadows the core library module with the same name",
+                    name
+                ),
+                span,
+            );
+        }
+    }
+
+    fn warn(&mut self, rule: Rule, message: String, span: Span) {
+        if self.settings.is_enabled(rule) {
+            self.warnings.push(Warning {
+                rule,
+                message,
+                span,
+            });
+        }
+    }
+
+    fn is_empty(&self, index: AstIndex) -> bool {
+        matches!(self.ast.node(index).node, Node::Empty)
+    }
+
+    fn node_span(&self, index: AstIndex) -> Span {
+        *self.ast.span(self.ast.node(index).span)
+    }
+
+    // Finds the first occurrence of the ID in the source, starting from the given position
+    //
+    // Spans aren't available in the AST for IDs like function args, so they're found in the source.
+    fn find_id(&self, id: &str, start: Position) -> Option<Span> {
+        let id: Vec<char> = id.chars().collect();
+        let is_id_char = |c: char| c.is_alphanumeric() || c == '_';
+
+        for (line_index, line) in self
+            .source_lines
+            .iter()
+            .enumerate()
+            .skip(start.line.saturating_sub(1) as usize)
+        {
+            let mut column = if line_index + 1 == start.line as usize {
+                start.column.saturating_sub(1) as usize
+            } else {
+                0
+            };
+
+            while column + id.len() <= line.len() {
+                let end = column + id.len();
+                if line[column..end] == id[..]
+                    && (column == 0 || !is_id_char(line[column - 1]))
+                    && !matches!(line.get(end), Some(c) if is_id_char(*c))
+                {
+                    let line_number = line_index as u32 + 1;
+                    return Some(Span {
+                        start: Position {
+                            line: line_number,
+                            column: column as u32 + 1,
+                        },
+                        end: Position {
+                            line: line_number,
+                            column: end as u32 + 1,
+                        },
+                    });
+                }
+                column += 1;
+            }
+        }
+
+        None
+    }
+}
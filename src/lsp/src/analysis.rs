@@ -0,0 +1,604 @@
+use koto_parser::{
+    AssignTarget, Ast, AstIndex, AstNode, ConstantIndex, ConstantPool, LookupNode, Node, Position,
+    Scope, Span, StringNode,
+};
+
+/// The kind of value that's introduced by a [Definition]
+#[derive(Clone, Debug, PartialEq)]
+pub enum DefinitionKind {
+    Variable,
+    Function,
+    Argument,
+    /// An item brought into scope with `import`, along with the full path of the imported item
+    Import(Vec<String>),
+}
+
+/// A place in the script where an ID is assigned a value
+#[derive(Clone, Debug)]
+pub struct Definition {
+    pub name: String,
+    pub span: Span,
+    pub kind: DefinitionKind,
+    scope: usize,
+}
+
+/// A top-level assignment or exported value in the script
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    /// The span of the symbol's ID
+    pub span: Span,
+    /// The span of the symbol's assignment
+    pub full_span: Span,
+    pub is_function: bool,
+    pub is_exported: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Reference {
+    name: String,
+    span: Span,
+    scope: usize,
+}
+
+/// The definitions, references, and symbols found in a script
+///
+/// Function bodies introduce new scopes, with the script's main block at the root. Positions that
+/// aren't available in the AST (e.g. the spans of function arguments) are found in the source.
+#[derive(Debug, Default)]
+pub struct Analysis {
+    definitions: Vec<Definition>,
+    references: Vec<Reference>,
+    scope_parents: Vec<Option<usize>>,
+    symbols: Vec<Symbol>,
+}
+
+impl Analysis {
+    pub fn new(ast: &Ast, constants: &ConstantPool, source: &str) -> Self {
+        let mut analyzer = Analyzer {
+            ast,
+            constants,
+            source_lines: source.lines().map(|line| line.chars().collect()).collect(),
+            analysis: Analysis {
+                scope_parents: vec![None],
+                ..Default::default()
+            },
+        };
+
+        if let Some(entry_point) = ast.entry_point() {
+            analyzer.visit_node(entry_point, 0, false);
+        }
+
+        analyzer.analysis
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn definitions(&self) -> &[Definition] {
+        &self.definitions
+    }
+
+    /// Finds the definition of the ID at the given position
+    ///
+    /// If the position is on a definition then the definition itself is returned.
+    pub fn definition_at(&self, position: Position) -> Option<&Definition> {
+        if let Some(definition) = self
+            .definitions
+            .iter()
+            .find(|definition| span_contains(&definition.span, position))
+        {
+            return Some(definition);
+        }
+
+        self.references
+            .iter()
+            .find(|reference| span_contains(&reference.span, position))
+            .and_then(|reference| {
+                self.resolve(&reference.name, reference.span.start, reference.scope)
+            })
+    }
+
+    /// Finds the definition that a name at the given position refers to
+    ///
+    /// Names that aren't referenced in the AST, e.g. partially typed lookups, are resolved
+    /// against the definitions in the main block.
+    pub fn resolve_name(&self, name: &str, position: Position) -> Option<&Definition> {
+        let scope = self
+            .references
+            .iter()
+            .find(|reference| span_contains(&reference.span, position))
+            .map_or(0, |reference| reference.scope);
+        self.resolve(name, position, scope)
+    }
+
+    fn resolve(&self, name: &str, position: Position, scope: usize) -> Option<&Definition> {
+        let mut fallback = None;
+        let mut scope = Some(scope);
+
+        while let Some(current_scope) = scope {
+            let mut candidates = self
+                .definitions
+                .iter()
+                .filter(|definition| definition.scope == current_scope && definition.name == name)
+                .peekable();
+
+            if fallback.is_none() {
+                fallback = candidates.peek().cloned();
+            }
+
+            // The closest preceding definition in the scope is the one that's being referred to
+            if let Some(definition) =
+                candidates.rfind(|definition| position_le(definition.span.start, position))
+            {
+                return Some(definition);
+            }
+
+            scope = self.scope_parents.get(current_scope).cloned().flatten();
+        }
+
+        fallback
+    }
+}
+
+struct Analyzer<'a> {
+    ast: &'a Ast,
+    constants: &'a ConstantPool,
+    source_lines: Vec<Vec<char>>,
+    analysis: Analysis,
+}
+
+impl<'a> Analyzer<'a> {
+    fn visit(&mut self, index: AstIndex, scope: usize) {
+        self.visit_node(self.ast.node(index), scope, false);
+    }
+
+    fn visit_nodes(&mut self, nodes: &[AstIndex], scope: usize) {
+        for node in nodes.iter() {
+            self.visit(*node, scope);
+        }
+    }
+
+    fn visit_node(&mut self, node: &AstNode, scope: usize, is_top_level: bool) {
+        let span = *self.ast.span(node.span);
+
+        match &node.node {
+            Node::Id(constant) => {
+                let name = self.string(*constant);
+                self.analysis
+                    .references
+                    .push(Reference { name, span, scope });
+            }
+            Node::Lookup((lookup_node, next)) => {
+                match lookup_node {
+                    LookupNode::Root(root) | LookupNode::Index(root) => self.visit(*root, scope),
+                    LookupNode::Call(args) => self.visit_nodes(args, scope),
+                    LookupNode::Id(_) => {}
+                }
+                if let Some(next) = next {
+                    self.visit(*next, scope);
+                }
+            }
+            Node::Num2(nodes)
+            | Node::Num4(nodes)
+            | Node::List(nodes)
+            | Node::Tuple(nodes)
+            | Node::TempTuple(nodes)
+            | Node::Block(nodes) => self.visit_nodes(nodes, scope),
+            Node::MainBlock { body, .. } => {
+                for node in body.iter() {
+                    self.visit_node(self.ast.node(*node), scope, true);
+                }
+            }
+            Node::Range { start, end, .. } => self.visit_nodes(&[*start, *end], scope),
+            Node::RangeFrom { start: node }
+            | Node::RangeTo { end: node, .. }
+            | Node::ReturnExpression(node)
+            | Node::CopyExpression(node)
+            | Node::Negate(node)
+            | Node::Throw(node)
+            | Node::Yield(node)
+            | Node::Loop { body: node }
+            | Node::Debug {
+                expression: node, ..
+            } => self.visit(*node, scope),
+            Node::InterpolatedString(string_nodes) => {
+                for string_node in string_nodes.iter() {
+                    if let StringNode::Expression(expression) = string_node {
+                        self.visit(*expression, scope);
+                    }
+                }
+            }
+            Node::Map(entries) => {
+                for (_, value) in entries.iter() {
+                    if let Some(value) = value {
+                        self.visit(*value, scope);
+                    }
+                }
+            }
+            Node::Function(function) => {
+                let function_scope = self.analysis.scope_parents.len();
+                self.analysis.scope_parents.push(Some(scope));
+
+                let mut search_start = span.start;
+                for arg in function.args.iter().flatten() {
+                    let name = self.string(*arg);
+                    if let Some(arg_span) = self.find_word(&name, search_start, |_| true) {
+                        search_start = arg_span.end;
+                        self.add_definition(
+                            name,
+                            arg_span,
+                            DefinitionKind::Argument,
+                            function_scope,
+                        );
+                    }
+                }
+
+                self.visit(function.body, function_scope);
+            }
+            Node::Call { function, args } => {
+                self.visit(*function, scope);
+                self.visit_nodes(args, scope);
+            }
+            Node::Import { from, items } => self.visit_import(from, items, span, scope),
+            Node::Assign {
+                target, expression, ..
+            } => {
+                self.visit(*expression, scope);
+
+                // Symbols are added for top-level and exported assignments
+                let exported = target.scope == Scope::Global;
+                let symbol = if is_top_level || exported {
+                    Some(exported)
+                } else {
+                    None
+                };
+                self.visit_target(target, *expression, symbol, scope);
+            }
+            Node::MultiAssign {
+                targets,
+                expressions,
+            } => {
+                self.visit(*expressions, scope);
+                for target in targets.iter() {
+                    self.visit_target(target, *expressions, None, scope);
+                }
+            }
+            Node::BinaryOp { lhs, rhs, .. } => self.visit_nodes(&[*lhs, *rhs], scope),
+            Node::If(ast_if) => {
+                self.visit_nodes(&[ast_if.condition, ast_if.then_node], scope);
+                for (condition, block) in ast_if.else_if_blocks.iter() {
+                    self.visit_nodes(&[*condition, *block], scope);
+                }
+                if let Some(else_node) = ast_if.else_node {
+                    self.visit(else_node, scope);
+                }
+            }
+            Node::Match { expression, arms } => {
+                self.visit(*expression, scope);
+                for arm in arms.iter() {
+                    for pattern in arm.patterns.iter() {
+                        self.visit_pattern(*pattern, scope);
+                    }
+                    if let Some(condition) = arm.condition {
+                        self.visit(condition, scope);
+                    }
+                    self.visit(arm.expression, scope);
+                }
+            }
+            Node::For(ast_for) => {
+                self.visit(ast_for.range, scope);
+
+                let mut search_start = span.start;
+                for arg in ast_for.args.iter().flatten() {
+                    let name = self.string(*arg);
+                    if let Some(arg_span) = self.find_word(&name, search_start, |_| true) {
+                        search_start = arg_span.end;
+                        self.add_definition(name, arg_span, DefinitionKind::Variable, scope);
+                    }
+                }
+
+                self.visit(ast_for.body, scope);
+            }
+            Node::While { condition, body } | Node::Until { condition, body } => {
+                self.visit_nodes(&[*condition, *body], scope)
+            }
+            Node::Try(ast_try) => {
+                self.visit(ast_try.try_block, scope);
+
+                if let Some(catch_arg) = ast_try.catch_arg {
+                    let name = self.string(catch_arg);
+                    let try_end = self.node_span(ast_try.try_block).end;
+                    if let Some(arg_span) = self.find_word(&name, try_end, |_| true) {
+                        self.add_definition(name, arg_span, DefinitionKind::Variable, scope);
+                    }
+                }
+
+                self.visit(ast_try.catch_block, scope);
+                if let Some(finally_block) = ast_try.finally_block {
+                    self.visit(finally_block, scope);
+                }
+            }
+            Node::Empty
+            | Node::BoolTrue
+            | Node::BoolFalse
+            | Node::Number0
+            | Node::Number1
+            | Node::Float(_)
+            | Node::Int(_)
+            | Node::Str(_)
+            | Node::RangeFull
+            | Node::Wildcard
+            | Node::Ellipsis(_)
+            | Node::Break
+            | Node::Continue
+            | Node::Return => {}
+        }
+    }
+
+    // Adds a definition for an assignment target,
+    // and a symbol if the assignment is at the top level or is exported.
+    fn visit_target(
+        &mut self,
+        target: &AssignTarget,
+        expression: AstIndex,
+        symbol_is_exported: Option<bool>,
+        scope: usize,
+    ) {
+        let target_node = self.ast.node(target.target_index);
+
+        match &target_node.node {
+            Node::Id(constant) => {
+                let name = self.string(*constant);
+                let span = *self.ast.span(target_node.span);
+                let is_function = matches!(self.ast.node(expression).node, Node::Function(_));
+                let kind = if is_function {
+                    DefinitionKind::Function
+                } else {
+                    DefinitionKind::Variable
+                };
+
+                // Exported values are added to the root scope
+                let definition_scope = match target.scope {
+                    Scope::Global => 0,
+                    Scope::Local => scope,
+                };
+                self.add_definition(name.clone(), span, kind, definition_scope);
+
+                if let Some(is_exported) = symbol_is_exported {
+                    let expression_end = self.node_span(expression).end;
+                    self.analysis.symbols.push(Symbol {
+                        name,
+                        span,
+                        full_span: Span {
+                            start: span.start,
+                            end: if position_le(span.end, expression_end) {
+                                expression_end
+                            } else {
+                                span.end
+                            },
+                        },
+                        is_function,
+                        is_exported,
+                    });
+                }
+            }
+            _ => self.visit(target.target_index, scope),
+        }
+    }
+
+    // IDs in match patterns are bound to the matched values
+    fn visit_pattern(&mut self, pattern: AstIndex, scope: usize) {
+        let pattern_node = self.ast.node(pattern);
+        match &pattern_node.node {
+            Node::Id(constant) => {
+                let name = self.string(*constant);
+                let span = *self.ast.span(pattern_node.span);
+                self.add_definition(name, span, DefinitionKind::Variable, scope);
+            }
+            Node::List(nodes) | Node::Tuple(nodes) | Node::TempTuple(nodes) => {
+                for node in nodes.iter() {
+                    self.visit_pattern(*node, scope);
+                }
+            }
+            _ => self.visit(pattern, scope),
+        }
+    }
+
+    fn visit_import(
+        &mut self,
+        from: &[ConstantIndex],
+        items: &[Vec<ConstantIndex>],
+        span: Span,
+        scope: usize,
+    ) {
+        let from: Vec<String> = from.iter().map(|id| self.string(*id)).collect();
+
+        // Items are listed after the import keyword, which follows the 'from' path if present
+        let mut search_start = if from.is_empty() {
+            span.start
+        } else {
+            match self.find_word("import", span.start, |_| true) {
+                Some(import_span) => import_span.end,
+                None => span.start,
+            }
+        };
+
+        for item in items.iter() {
+            let item: Vec<String> = item.iter().map(|id| self.string(*id)).collect();
+            let name = match item.last() {
+                Some(name) => name.clone(),
+                None => continue,
+            };
+
+            // The imported ID is the last part of the item's path, so it isn't followed by a '.'
+            if let Some(item_span) = self.find_word(&name, search_start, |next| next != Some('.')) {
+                search_start = item_span.end;
+                let path = from.iter().chain(item.iter()).cloned().collect();
+                self.add_definition(name, item_span, DefinitionKind::Import(path), scope);
+            }
+        }
+    }
+
+    fn add_definition(&mut self, name: String, span: Span, kind: DefinitionKind, scope: usize) {
+        self.analysis.definitions.push(Definition {
+            name,
+            span,
+            kind,
+            scope,
+        });
+    }
+
+    fn node_span(&self, index: AstIndex) -> Span {
+        *self.ast.span(self.ast.node(index).span)
+    }
+
+    fn string(&self, constant: ConstantIndex) -> String {
+        self.constants.get_str(constant).to_string()
+    }
+
+    // Finds the first occurrence of a word in the source, starting from the given position
+    //
+    // The provided predicate receives the character following the word, and returns true if the
+    // occurrence should be accepted.
+    fn find_word(
+        &self,
+        word: &str,
+        start: Position,
+        accept: impl Fn(Option<char>) -> bool,
+    ) -> Option<Span> {
+        let word: Vec<char> = word.chars().collect();
+
+        for (line_index, line) in self
+            .source_lines
+            .iter()
+            .enumerate()
+            .skip(start.line.saturating_sub(1) as usize)
+        {
+            let first_column = if line_index + 1 == start.line as usize {
+                start.column.saturating_sub(1) as usize
+            } else {
+                0
+            };
+
+            let mut column = first_column;
+            while column + word.len() <= line.len() {
+                let end = column + word.len();
+                if line[column..end] == word[..]
+                    && (column == 0 || !is_id_char(line[column - 1]))
+                    && !matches!(line.get(end), Some(c) if is_id_char(*c))
+                    && accept(line.get(end).cloned())
+                {
+                    let line_number = line_index as u32 + 1;
+                    return Some(Span {
+                        start: Position {
+                            line: line_number,
+                            column: column as u32 + 1,
+                        },
+                        end: Position {
+                            line: line_number,
+                            column: end as u32 + 1,
+                        },
+                    });
+                }
+                column += 1;
+            }
+        }
+
+        None
+    }
+}
+
+/// Returns true if the character can be part of an ID
+pub fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns true if the span contains the position, including the position at the end of the span
+pub fn span_contains(span: &Span, position: Position) -> bool {
+    position_le(span.start, position) && position_le(position, span.end)
+}
+
+fn position_le(a: Position, b: Position) -> bool {
+    (a.line, a.column) <= (b.line, b.column)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, koto_parser::Parser};
+
+    fn analyze(source: &str) -> Analysis {
+        let (ast, constants) = Parser::parse(source).unwrap();
+        Analysis::new(&ast, &constants, source)
+    }
+
+    fn position(line: u32, column: u32) -> Position {
+        Position { line, column }
+    }
+
+    #[test]
+    fn top_level_symbols() {
+        let source = "\
+f = |x| x
+y = 42
+export z = ||
+  w = 1
+";
+        let analysis = analyze(source);
+        let symbols = analysis.symbols();
+
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(symbols[0].name, "f");
+        assert!(symbols[0].is_function);
+        assert_eq!(symbols[1].name, "y");
+        assert!(!symbols[1].is_function);
+        assert_eq!(symbols[2].name, "z");
+        assert!(symbols[2].is_exported);
+    }
+
+    #[test]
+    fn definition_of_local() {
+        let source = "\
+x = 1
+f = |x|
+  x + 1
+x = 2
+f x
+";
+        let analysis = analyze(source);
+
+        // The x in the function body refers to the function's argument
+        let definition = analysis.definition_at(position(3, 3)).unwrap();
+        assert_eq!(definition.kind, DefinitionKind::Argument);
+        assert_eq!(definition.span.start, position(2, 6));
+
+        // The x on the last line refers to the second assignment
+        let definition = analysis.definition_at(position(5, 3)).unwrap();
+        assert_eq!(definition.span.start, position(4, 1));
+    }
+
+    #[test]
+    fn definition_of_import() {
+        let source = "\
+import string.print, foo
+from bar import baz
+print baz
+";
+        let analysis = analyze(source);
+
+        let definition = analysis.definition_at(position(3, 1)).unwrap();
+        assert_eq!(
+            definition.kind,
+            DefinitionKind::Import(vec!["string".to_string(), "print".to_string()])
+        );
+        assert_eq!(definition.span.start, position(1, 15));
+
+        let definition = analysis.definition_at(position(3, 8)).unwrap();
+        assert_eq!(
+            definition.kind,
+            DefinitionKind::Import(vec!["bar".to_string(), "baz".to_string()])
+        );
+        assert_eq!(definition.span.start, position(2, 17));
+    }
+}
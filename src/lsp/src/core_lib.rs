@@ -0,0 +1,92 @@
+use koto_runtime::{core::CoreLib, Value, ValueMap};
+
+/// The modules of Koto's core library, used for hover information and completions
+pub struct CoreLibModules {
+    modules: Vec<(&'static str, ValueMap)>,
+}
+
+impl Default for CoreLibModules {
+    fn default() -> Self {
+        let core_lib = CoreLib::default();
+
+        Self {
+            modules: vec![
+                ("coroutine", core_lib.coroutine),
+                ("io", core_lib.io),
+                ("iterator", core_lib.iterator),
+                ("koto", core_lib.koto),
+                ("list", core_lib.list),
+                ("map", core_lib.map),
+                ("num2", core_lib.num2),
+                ("num4", core_lib.num4),
+                ("number", core_lib.number),
+                ("range", core_lib.range),
+                ("string", core_lib.string),
+                ("test", core_lib.test),
+                ("thread", core_lib.thread),
+                ("tuple", core_lib.tuple),
+            ],
+        }
+    }
+}
+
+impl CoreLibModules {
+    pub fn module_names(&self) -> impl Iterator<Item = &str> {
+        self.modules.iter().map(|(name, _)| *name)
+    }
+
+    pub fn contains_module(&self, name: &str) -> bool {
+        self.module(name).is_some()
+    }
+
+    /// Returns the members of a module, sorted by name
+    pub fn members(&self, module_name: &str) -> Vec<(String, Value)> {
+        let mut result = match self.module(module_name) {
+            Some(module) => module
+                .cloned_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+            None => vec![],
+        };
+        result.sort_by(|a, b| a.0.cmp(&b.0));
+        result
+    }
+
+    pub fn member(&self, module_name: &str, member_name: &str) -> Option<Value> {
+        self.module(module_name)
+            .and_then(|module| module.data().get_with_string(member_name).cloned())
+    }
+
+    fn module(&self, name: &str) -> Option<&ValueMap> {
+        self.modules
+            .iter()
+            .find(|(module_name, _)| *module_name == name)
+            .map(|(_, module)| module)
+    }
+}
+
+/// Returns true if the value is a function that can be called from a script
+pub fn is_function(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Function(_) | Value::Generator(_) | Value::ExternalFunction(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_members() {
+        let modules = CoreLibModules::default();
+
+        assert!(modules.contains_module("string"));
+        assert!(!modules.contains_module("foo"));
+
+        let members = modules.members("string");
+        assert!(members.iter().any(|(name, _)| name == "print"));
+        assert!(is_function(&modules.member("string", "print").unwrap()));
+        assert!(modules.member("string", "foo").is_none());
+    }
+}
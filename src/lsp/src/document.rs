@@ -0,0 +1,165 @@
+use {
+    crate::analysis::{is_id_char, Analysis},
+    koto_bytecode::{Compiler, CompilerSettings},
+    koto_parser::{Parser, Position, Span},
+};
+
+/// An error that was found while compiling a document
+#[derive(Clone, Debug)]
+pub struct DocumentError {
+    pub message: String,
+    pub span: Span,
+}
+
+/// A Koto script that's open in the client
+pub struct Document {
+    lines: Vec<Vec<char>>,
+    analysis: Analysis,
+    error: Option<DocumentError>,
+}
+
+impl Document {
+    pub fn new(source: &str) -> Self {
+        let mut result = Self {
+            lines: vec![],
+            analysis: Analysis::default(),
+            error: None,
+        };
+        result.update(source);
+        result
+    }
+
+    /// Updates the document's source, and then compiles it to check for errors
+    ///
+    /// If the new source fails to parse then the analysis of the previous source is kept,
+    /// which allows symbols and completions to remain available while the script is being edited.
+    pub fn update(&mut self, source: &str) {
+        self.lines = source.lines().map(|line| line.chars().collect()).collect();
+
+        match Parser::parse(source) {
+            Ok((ast, constants)) => {
+                self.analysis = Analysis::new(&ast, &constants, source);
+                self.error = Compiler::compile(&ast, CompilerSettings::default())
+                    .err()
+                    .map(|error| DocumentError {
+                        message: error.message,
+                        span: error.span,
+                    });
+            }
+            Err(error) => {
+                self.error = Some(DocumentError {
+                    message: error.to_string(),
+                    span: error.span,
+                });
+            }
+        }
+    }
+
+    pub fn analysis(&self) -> &Analysis {
+        &self.analysis
+    }
+
+    pub fn error(&self) -> Option<&DocumentError> {
+        self.error.as_ref()
+    }
+
+    /// Returns the dotted path ending with the ID at the given position, along with the path's
+    /// starting position
+    ///
+    /// e.g. In `foo.bar.baz`, a position in `bar` produces `["foo", "bar"]`.
+    pub fn path_at(&self, position: Position) -> Option<(Vec<String>, Position)> {
+        let line = self.lines.get(position.line.checked_sub(1)? as usize)?;
+        let column = (position.column.saturating_sub(1) as usize).min(line.len());
+
+        let mut end = column;
+        while end < line.len() && is_id_char(line[end]) {
+            end += 1;
+        }
+
+        match self.path_before(line, end, position.line) {
+            Some((path, start)) if path.last().map(String::is_empty) == Some(false) => {
+                Some((path, start))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the dotted path that's being typed before the given position, along with the
+    /// path's starting position
+    ///
+    /// The last ID in the path is the partially typed ID, which may be empty, e.g. `foo.` produces
+    /// `["foo", ""]`.
+    pub fn path_before_position(&self, position: Position) -> Option<(Vec<String>, Position)> {
+        let line = self.lines.get(position.line.checked_sub(1)? as usize)?;
+        let column = (position.column.saturating_sub(1) as usize).min(line.len());
+        self.path_before(line, column, position.line)
+    }
+
+    fn path_before(
+        &self,
+        line: &[char],
+        end: usize,
+        line_number: u32,
+    ) -> Option<(Vec<String>, Position)> {
+        let mut start = end;
+        while start > 0 && (is_id_char(line[start - 1]) || line[start - 1] == '.') {
+            start -= 1;
+        }
+
+        let path_string: String = line[start..end].iter().collect();
+        let path: Vec<String> = path_string.split('.').map(String::from).collect();
+
+        // The path should start with an ID, e.g. numbers like 1.5 aren't paths
+        match path.first().and_then(|id| id.chars().next()) {
+            Some(c) if !c.is_numeric() => Some((
+                path,
+                Position {
+                    line: line_number,
+                    column: start as u32 + 1,
+                },
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(line: u32, column: u32) -> Position {
+        Position { line, column }
+    }
+
+    fn path(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn errors() {
+        let document = Document::new("x = [1, 2\n");
+        assert!(document.error().is_some());
+
+        let document = Document::new("x = [1, 2]\n");
+        assert!(document.error().is_none());
+    }
+
+    #[test]
+    fn paths() {
+        let document = Document::new("x = string.print 1.5\ny = foo.\n");
+
+        assert_eq!(
+            document.path_at(position(1, 13)),
+            Some((path(&["string", "print"]), position(1, 5)))
+        );
+        assert_eq!(
+            document.path_at(position(1, 6)),
+            Some((path(&["string"]), position(1, 5)))
+        );
+        assert_eq!(document.path_at(position(1, 18)), None);
+        assert_eq!(
+            document.path_before_position(position(2, 9)),
+            Some((path(&["foo", ""]), position(2, 5)))
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! A Language Server Protocol server for Koto scripts
+//!
+//! The server communicates with the client over stdin and stdout, and provides diagnostics,
+//! document symbols, go-to-definition, hover information, and completions.
+//!
+//! Imported modules are searched for in the directories listed in the `KOTO_PATH` environment
+//! variable, after the importing script's directory.
+
+mod analysis;
+mod core_lib;
+mod document;
+mod protocol;
+mod server;
+
+use {
+    protocol::{read_message, MessageWriter},
+    server::Server,
+    std::{env, io, path::PathBuf},
+};
+
+// The directories listed in KOTO_PATH, which are searched for imported modules
+fn module_paths_from_env() -> Vec<PathBuf> {
+    match env::var_os("KOTO_PATH") {
+        Some(paths) => env::split_paths(&paths)
+            .filter(|path| !path.as_os_str().is_empty())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn main() {
+    let mut server = Server::new(MessageWriter::new(Box::new(io::stdout())));
+    server.set_module_paths(module_paths_from_env());
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+
+    loop {
+        match read_message(&mut input) {
+            Ok(Some(message)) => {
+                if !server.handle_message(&message) {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(error) => {
+                eprintln!("Error while reading message: {}", error);
+                break;
+            }
+        }
+    }
+}
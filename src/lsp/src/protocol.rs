@@ -0,0 +1,53 @@
+pub use koto_protocol::read_message;
+
+use {
+    koto_protocol::write_message,
+    serde_json::{json, Value as Json},
+    std::io::Write,
+};
+
+/// The JSON-RPC error code used when a method isn't supported by the server
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Writes JSON-RPC messages to the output
+pub struct MessageWriter {
+    output: Box<dyn Write>,
+}
+
+impl MessageWriter {
+    pub fn new(output: Box<dyn Write>) -> Self {
+        Self { output }
+    }
+
+    pub fn send_response(&mut self, id: &Json, result: Json) {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": result,
+        }));
+    }
+
+    pub fn send_error_response(&mut self, id: &Json, code: i64, message: &str) {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {
+                "code": code,
+                "message": message,
+            },
+        }));
+    }
+
+    pub fn send_notification(&mut self, method: &str, params: Json) {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }));
+    }
+
+    fn send(&mut self, message: Json) {
+        // Errors are ignored here, if the client has gone away then the input will be closed
+        let _ = write_message(&mut self.output, &message);
+    }
+}
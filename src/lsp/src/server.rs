@@ -0,0 +1,654 @@
+use {
+    crate::{
+        analysis::{span_contains, DefinitionKind},
+        core_lib::{is_function, CoreLibModules},
+        document::Document,
+        protocol::{MessageWriter, METHOD_NOT_FOUND},
+    },
+    koto_bytecode::{Loader, COMPILED_EXTENSION, SOURCE_EXTENSION},
+    koto_parser::{Position, Span},
+    koto_runtime::type_as_string,
+    serde_json::{json, Value as Json},
+    std::{
+        collections::HashMap,
+        ffi::OsStr,
+        path::{Path, PathBuf},
+    },
+};
+
+// See the LSP specification for the meanings of the following values
+const TEXT_DOCUMENT_SYNC_FULL: u32 = 1;
+const SEVERITY_ERROR: u32 = 1;
+const SYMBOL_KIND_FUNCTION: u32 = 12;
+const SYMBOL_KIND_VARIABLE: u32 = 13;
+const COMPLETION_KIND_FUNCTION: u32 = 3;
+const COMPLETION_KIND_VARIABLE: u32 = 6;
+const COMPLETION_KIND_MODULE: u32 = 9;
+
+/// A Language Server Protocol server for Koto scripts
+///
+/// Documents are synchronized in full with each change, and diagnostics are published whenever
+/// a document is opened or changed.
+pub struct Server {
+    writer: MessageWriter,
+    documents: HashMap<String, Document>,
+    core_lib: CoreLibModules,
+    loader: Loader,
+}
+
+impl Server {
+    pub fn new(writer: MessageWriter) -> Self {
+        Self {
+            writer,
+            documents: HashMap::new(),
+            core_lib: CoreLibModules::default(),
+            loader: Loader::default(),
+        }
+    }
+
+    /// Sets the directories that are searched for imported modules
+    ///
+    /// The search paths are checked in order after the importing script's directory,
+    /// see [Loader::set_module_paths].
+    pub fn set_module_paths(&mut self, paths: Vec<PathBuf>) {
+        self.loader.set_module_paths(paths);
+    }
+
+    /// Handles a message from the client, returning false when the server should exit
+    pub fn handle_message(&mut self, message: &Json) -> bool {
+        let method = match message["method"].as_str() {
+            Some(method) => method,
+            // Responses from the client aren't expected, so they can be ignored
+            None => return true,
+        };
+        let params = &message["params"];
+
+        match message.get("id") {
+            Some(id) => match self.handle_request(method, params) {
+                Ok(result) => self.writer.send_response(id, result),
+                Err(error) => self
+                    .writer
+                    .send_error_response(id, METHOD_NOT_FOUND, &error),
+            },
+            None if method == "exit" => return false,
+            None => self.handle_notification(method, params),
+        }
+
+        true
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Json) -> Result<Json, String> {
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": TEXT_DOCUMENT_SYNC_FULL,
+                    "documentSymbolProvider": true,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                },
+                "serverInfo": {
+                    "name": "koto_lsp",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            }),
+            "shutdown" => Json::Null,
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            "textDocument/definition" => self.definition(params).unwrap_or(Json::Null),
+            "textDocument/hover" => self.hover(params).unwrap_or(Json::Null),
+            "textDocument/completion" => self.completion(params),
+            _ => return Err(format!("Unsupported method '{}'", method)),
+        };
+
+        Ok(result)
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Json) {
+        let uri = match params["textDocument"]["uri"].as_str() {
+            Some(uri) => uri.to_string(),
+            None => return,
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                let source = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), Document::new(source));
+                self.publish_diagnostics(&uri);
+            }
+            "textDocument/didChange" => {
+                // The server uses full synchronization, so the last change contains the full text
+                let source = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+
+                if let (Some(document), Some(source)) = (self.documents.get_mut(&uri), source) {
+                    document.update(source);
+                    self.publish_diagnostics(&uri);
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.writer.send_notification(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str) {
+        let diagnostics = match self
+            .documents
+            .get(uri)
+            .and_then(|document| document.error())
+        {
+            Some(error) => json!([{
+                "range": lsp_range(&error.span),
+                "severity": SEVERITY_ERROR,
+                "source": "koto",
+                "message": error.message,
+            }]),
+            None => json!([]),
+        };
+
+        self.writer.send_notification(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        );
+    }
+
+    fn document_symbols(&self, params: &Json) -> Json {
+        let document = match self.document(params) {
+            Some((_, document)) => document,
+            None => return json!([]),
+        };
+
+        let symbols = document
+            .analysis()
+            .symbols()
+            .iter()
+            .map(|symbol| {
+                json!({
+                    "name": symbol.name,
+                    "detail": if symbol.is_exported { "export" } else { "" },
+                    "kind": if symbol.is_function {
+                        SYMBOL_KIND_FUNCTION
+                    } else {
+                        SYMBOL_KIND_VARIABLE
+                    },
+                    "range": lsp_range(&symbol.full_span),
+                    "selectionRange": lsp_range(&symbol.span),
+                })
+            })
+            .collect();
+
+        Json::Array(symbols)
+    }
+
+    fn definition(&self, params: &Json) -> Option<Json> {
+        let (uri, document) = self.document(params)?;
+        let position = koto_position(&params["position"]);
+        let definition = document.analysis().definition_at(position)?;
+
+        // Requesting the definition of an import item goes to the imported module's file
+        if let DefinitionKind::Import(path) = &definition.kind {
+            if span_contains(&definition.span, position) {
+                if let Some(module_path) = uri_to_path(uri)
+                    .and_then(|script_path| self.find_module_source(script_path, path))
+                {
+                    return Some(json!({
+                        "uri": path_to_uri(&module_path),
+                        "range": lsp_range(&Span::default()),
+                    }));
+                }
+            }
+        }
+
+        Some(json!({
+            "uri": uri,
+            "range": lsp_range(&definition.span),
+        }))
+    }
+
+    // Finds the source file of an imported module, using the loader's search rules
+    //
+    // Nested modules in the import path are followed as far as they can be found, with the
+    // remaining items in the path being values exported by the module. Compiled modules are
+    // replaced with their source files.
+    fn find_module_source(&self, script_path: PathBuf, import_path: &[String]) -> Option<PathBuf> {
+        let (name, nested_names) = import_path.split_first()?;
+        let mut module_path = self.loader.find_module(name, Some(script_path)).ok()?;
+
+        for nested_name in nested_names {
+            match self.loader.find_nested_module(nested_name, &module_path) {
+                Ok(nested_path) => module_path = nested_path,
+                Err(_) => break,
+            }
+        }
+
+        if module_path.extension() == Some(OsStr::new(COMPILED_EXTENSION)) {
+            module_path.set_extension(SOURCE_EXTENSION);
+        }
+
+        if module_path.is_file() {
+            Some(module_path)
+        } else {
+            None
+        }
+    }
+
+    fn hover(&self, params: &Json) -> Option<Json> {
+        let (_, document) = self.document(params)?;
+        let (path, start) = document.path_at(koto_position(&params["position"]))?;
+
+        let contents = match self.core_lib_path(document, &path, start)?.as_slice() {
+            [module] => format!("```koto\n{}\n```\nCore library module", module),
+            [module, member] => {
+                let value = self.core_lib.member(module, member)?;
+                let description = if is_function(&value) {
+                    "Core library function".to_string()
+                } else {
+                    format!("Core library value ({})", type_as_string(&value))
+                };
+                format!("```koto\n{}.{}\n```\n{}", module, member, description)
+            }
+            _ => return None,
+        };
+
+        Some(json!({
+            "contents": {
+                "kind": "markdown",
+                "value": contents,
+            }
+        }))
+    }
+
+    fn completion(&self, params: &Json) -> Json {
+        let (document, position) = match self.document(params) {
+            Some((_, document)) => (document, koto_position(&params["position"])),
+            None => return json!([]),
+        };
+
+        let (path, start) = document
+            .path_before_position(position)
+            .unwrap_or_else(|| (vec![String::new()], position));
+
+        let items: Vec<Json> = if path.len() > 1 {
+            // Complete the members of a core library module
+            match self.core_lib_path(document, &path[..path.len() - 1], start) {
+                Some(module_path) if module_path.len() == 1 => self
+                    .core_lib
+                    .members(&module_path[0])
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let kind = if is_function(&value) {
+                            COMPLETION_KIND_FUNCTION
+                        } else {
+                            COMPLETION_KIND_VARIABLE
+                        };
+                        completion_item(&name, kind)
+                    })
+                    .collect(),
+                _ => vec![],
+            }
+        } else {
+            // Complete core library module names and the IDs that are defined in the document
+            let mut names = Vec::new();
+            let definitions = document
+                .analysis()
+                .definitions()
+                .iter()
+                .filter(|definition| {
+                    if names.contains(&definition.name) {
+                        false
+                    } else {
+                        names.push(definition.name.clone());
+                        true
+                    }
+                })
+                .map(|definition| {
+                    let kind = match definition.kind {
+                        DefinitionKind::Function => COMPLETION_KIND_FUNCTION,
+                        _ => COMPLETION_KIND_VARIABLE,
+                    };
+                    completion_item(&definition.name, kind)
+                })
+                .collect::<Vec<_>>();
+
+            self.core_lib
+                .module_names()
+                .filter(|name| !names.iter().any(|defined| defined == name))
+                .map(|name| completion_item(name, COMPLETION_KIND_MODULE))
+                .chain(definitions)
+                .collect()
+        };
+
+        Json::Array(items)
+    }
+
+    // Resolves a path in the document to a path in the core library
+    //
+    // The path's root can be the name of a core library module, or an ID that was imported from
+    // the core library, e.g. with `import string.print`, `print` resolves to `string.print`.
+    fn core_lib_path(
+        &self,
+        document: &Document,
+        path: &[String],
+        start: Position,
+    ) -> Option<Vec<String>> {
+        let (root, rest) = path.split_first()?;
+
+        let mut result = match document.analysis().resolve_name(root, start) {
+            Some(definition) => match &definition.kind {
+                DefinitionKind::Import(import_path) => import_path.clone(),
+                _ => return None,
+            },
+            None => vec![root.clone()],
+        };
+
+        if self.core_lib.contains_module(&result[0]) {
+            result.extend(rest.iter().cloned());
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    fn document<'a>(&'a self, params: &'a Json) -> Option<(&'a str, &'a Document)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        self.documents.get(uri).map(|document| (uri, document))
+    }
+}
+
+fn completion_item(label: &str, kind: u32) -> Json {
+    json!({ "label": label, "kind": kind })
+}
+
+// LSP positions start from 0, while Koto positions start from 1
+//
+// Koto's columns are based on display width, which matches the LSP's character offsets for most
+// scripts.
+fn koto_position(position: &Json) -> Position {
+    Position {
+        line: position["line"].as_u64().unwrap_or(0) as u32 + 1,
+        column: position["character"].as_u64().unwrap_or(0) as u32 + 1,
+    }
+}
+
+fn lsp_position(position: Position) -> Json {
+    json!({
+        "line": position.line.saturating_sub(1),
+        "character": position.column.saturating_sub(1),
+    })
+}
+
+fn lsp_range(span: &Span) -> Json {
+    json!({
+        "start": lsp_position(span.start),
+        "end": lsp_position(span.end),
+    })
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let bytes = path.as_bytes();
+
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = if bytes[i] == b'%' {
+            path.get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    Some(PathBuf::from(String::from_utf8_lossy(&decoded).as_ref()))
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let mut result = "file://".to_string();
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            result.push(byte as char);
+        } else {
+            result += &format!("%{:02X}", byte);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::protocol::read_message,
+        std::{
+            fs,
+            io::{self, Write},
+            sync::{Arc, Mutex},
+        },
+    };
+
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct TestClient {
+        server: Server,
+        output: SharedOutput,
+        id: i64,
+    }
+
+    impl TestClient {
+        fn new() -> Self {
+            let output = SharedOutput::default();
+            Self {
+                server: Server::new(MessageWriter::new(Box::new(output.clone()))),
+                output,
+                id: 0,
+            }
+        }
+
+        fn open(&mut self, uri: &str, text: &str) -> Json {
+            self.server.handle_message(&json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didOpen",
+                "params": { "textDocument": { "uri": uri, "text": text } },
+            }));
+            self.take_message()
+        }
+
+        fn change(&mut self, uri: &str, text: &str) -> Json {
+            self.server.handle_message(&json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didChange",
+                "params": {
+                    "textDocument": { "uri": uri },
+                    "contentChanges": [{ "text": text }],
+                },
+            }));
+            self.take_message()
+        }
+
+        fn request(&mut self, method: &str, params: Json) -> Json {
+            self.id += 1;
+            self.server.handle_message(&json!({
+                "jsonrpc": "2.0",
+                "id": self.id,
+                "method": method,
+                "params": params,
+            }));
+            self.take_message()
+        }
+
+        fn take_message(&mut self) -> Json {
+            let output = std::mem::take(&mut *self.output.0.lock().unwrap());
+            read_message(&mut output.as_slice()).unwrap().unwrap()
+        }
+    }
+
+    fn position_params(uri: &str, line: u32, character: u32) -> Json {
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character },
+        })
+    }
+
+    const URI: &str = "file:///test.koto";
+
+    #[test]
+    fn diagnostics() {
+        let mut client = TestClient::new();
+
+        let notification = client.open(URI, "x = [1, 2\n");
+        assert_eq!(notification["method"], "textDocument/publishDiagnostics");
+        let diagnostics = notification["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], 0);
+
+        let notification = client.open(URI, "x = [1, 2]\n");
+        assert!(notification["params"]["diagnostics"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn definition_and_hover() {
+        let mut client = TestClient::new();
+        client.open(
+            URI,
+            "\
+import string.print
+f = |x| print x
+f 42
+",
+        );
+
+        let response = client.request("textDocument/definition", position_params(URI, 2, 0));
+        assert_eq!(response["result"]["uri"], URI);
+        assert_eq!(response["result"]["range"]["start"]["line"], 1);
+
+        let response = client.request("textDocument/hover", position_params(URI, 1, 9));
+        let contents = response["result"]["contents"]["value"].as_str().unwrap();
+        assert!(contents.contains("string.print"));
+        assert!(contents.contains("Core library function"));
+
+        let response = client.request("textDocument/hover", position_params(URI, 2, 0));
+        assert!(response["result"].is_null());
+    }
+
+    #[test]
+    fn completion() {
+        let mut client = TestClient::new();
+        client.open(URI, "x = 1\n");
+        // The incomplete lookup fails to parse, so the analysis of the previous text is used
+        client.change(URI, "x = 1\nstring.\n");
+
+        let response = client.request("textDocument/completion", position_params(URI, 1, 7));
+        let items = response["result"].as_array().unwrap();
+        assert!(items.iter().any(|item| item["label"] == "print"));
+        assert!(items.iter().all(|item| item["label"] != "string"));
+
+        let response = client.request("textDocument/completion", position_params(URI, 1, 0));
+        let items = response["result"].as_array().unwrap();
+        assert!(items.iter().any(|item| item["label"] == "string"));
+        assert!(items.iter().any(|item| item["label"] == "x"));
+    }
+
+    #[test]
+    fn definition_of_imported_modules() {
+        // A 'project' directory with a script and local modules,
+        // and a 'lib' directory that's added to the module search paths
+        let dir = std::env::temp_dir().join("koto_lsp_imported_modules");
+        let project_dir = dir.join("project");
+        let lib_dir = dir.join("lib");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::create_dir_all(lib_dir.join("utils")).unwrap();
+        fs::write(project_dir.join("main.koto"), "").unwrap();
+        fs::write(project_dir.join("local.koto"), "export x = 1").unwrap();
+        fs::write(project_dir.join("compiled.koto"), "export x = 2").unwrap();
+        fs::write(project_dir.join("compiled.kotoc"), "").unwrap();
+        fs::write(lib_dir.join("shared.koto"), "export x = 3").unwrap();
+        fs::write(lib_dir.join("utils").join("strings.koto"), "export x = 4").unwrap();
+
+        let mut client = TestClient::new();
+        client.server.set_module_paths(vec![lib_dir.clone()]);
+
+        let uri = path_to_uri(&project_dir.join("main.koto"));
+        client.open(
+            &uri,
+            "\
+import shared
+import local.x
+import utils.strings
+import compiled
+",
+        );
+
+        let mut definition_uri = |line, character| {
+            let response = client.request(
+                "textDocument/definition",
+                position_params(&uri, line, character),
+            );
+            response["result"]["uri"].as_str().unwrap().to_string()
+        };
+        let expected_uri = |path: PathBuf| path_to_uri(&path.canonicalize().unwrap());
+
+        assert_eq!(
+            definition_uri(0, 7),
+            expected_uri(lib_dir.join("shared.koto"))
+        );
+        assert_eq!(
+            definition_uri(1, 13),
+            expected_uri(project_dir.join("local.koto"))
+        );
+        assert_eq!(
+            definition_uri(2, 13),
+            expected_uri(lib_dir.join("utils").join("strings.koto"))
+        );
+        assert_eq!(
+            definition_uri(3, 7),
+            expected_uri(project_dir.join("compiled.koto"))
+        );
+    }
+
+    #[test]
+    fn unsupported_method() {
+        let mut client = TestClient::new();
+        let response = client.request("textDocument/foo", json!({}));
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn uri_conversion() {
+        let path = PathBuf::from("/some dir/script.koto");
+        let uri = path_to_uri(&path);
+        assert_eq!(uri, "file:///some%20dir/script.koto");
+        assert_eq!(uri_to_path(&uri), Some(path));
+    }
+}
@@ -74,6 +74,8 @@ pub enum SyntaxError {
     ExpectedMatchExpression,
     ExpectedMatchPattern,
     ExpectedNegatableExpression,
+    ExpectedStringExpression,
+    ExpectedStringExpressionEnd,
     ExpectedThenExpression,
     ExpectedUntilCondition,
     ExpectedWhileCondition,
@@ -84,6 +86,7 @@ pub enum SyntaxError {
     TooManyNum2Terms,
     TooManyNum4Terms,
     UnexpectedEscapeInString,
+    UnexpectedStringInterpolation,
     UnexpectedToken,
     UnexpectedTokenAfterExportId,
     UnexpectedTokenInImportExpression,
@@ -241,6 +244,10 @@ impl fmt::Display for SyntaxError {
             ExpectedMatchExpression => f.write_str("Expected expression after match"),
             ExpectedMatchPattern => f.write_str("Expected pattern for match arm"),
             ExpectedNegatableExpression => f.write_str("Expected negatable expression"),
+            ExpectedStringExpression => f.write_str("Expected expression between '{' and '}'"),
+            ExpectedStringExpressionEnd => {
+                f.write_str("Unexpected token in string expression, expected '}'")
+            }
             ExpectedThenExpression => f.write_str("Expected 'then' expression."),
             ExpectedUntilCondition => f.write_str("Expected condition in until loop"),
             ExpectedWhileCondition => f.write_str("Expected condition in while loop"),
@@ -255,6 +262,9 @@ impl fmt::Display for SyntaxError {
             TooManyNum2Terms => f.write_str("num2 only supports up to 2 terms"),
             TooManyNum4Terms => f.write_str("num4 only supports up to 4 terms"),
             UnexpectedEscapeInString => f.write_str("Unexpected escape pattern in string"),
+            UnexpectedStringInterpolation => {
+                f.write_str("Strings with embedded expressions can't be used here")
+            }
             UnexpectedToken => f.write_str("Unexpected token"),
             UnexpectedTokenAfterExportId => f.write_str("Unexpected token after export ID"),
             UnexpectedTokenInImportExpression => {
@@ -0,0 +1,88 @@
+use {koto_lexer::Position, std::path::Path};
+
+/// Formats an error message along with an excerpt of the source that caused the error
+///
+/// The excerpt is made up of the source lines between `start_pos` and `end_pos`, with the
+/// error's columns highlighted when the error is on a single line.
+pub fn format_error_with_excerpt(
+    message: &str,
+    source_path: Option<&Path>,
+    source: &str,
+    start_pos: Position,
+    end_pos: Position,
+) -> String {
+    let position_info = if let Some(path) = source_path {
+        format!(
+            "{} - {}:{}",
+            path.display(),
+            start_pos.line,
+            start_pos.column
+        )
+    } else {
+        format!("{}:{}", start_pos.line, start_pos.column)
+    };
+
+    if source
+        .lines()
+        .nth(start_pos.line.saturating_sub(1) as usize)
+        .is_none()
+    {
+        // The source isn't available, e.g. when running a chunk that was compiled without it
+        return format!("{}\n --> {}", message, position_info);
+    }
+
+    let (excerpt, padding) = {
+        let excerpt_lines = source
+            .lines()
+            .skip((start_pos.line - 1) as usize)
+            .take((end_pos.line - start_pos.line + 1) as usize)
+            .collect::<Vec<_>>();
+
+        let line_numbers = (start_pos.line..=end_pos.line)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>();
+
+        let number_width = line_numbers.iter().max_by_key(|n| n.len()).unwrap().len();
+
+        let padding = " ".repeat(number_width + 2);
+
+        if start_pos.line == end_pos.line {
+            let mut excerpt = format!(
+                " {:>width$} | {}\n",
+                line_numbers.first().unwrap(),
+                excerpt_lines.first().unwrap(),
+                width = number_width
+            );
+
+            excerpt += &format!(
+                "{}|{}{}",
+                padding,
+                " ".repeat(start_pos.column as usize),
+                "^".repeat((end_pos.column - start_pos.column) as usize)
+            );
+
+            (excerpt, padding)
+        } else {
+            let mut excerpt = String::new();
+
+            for (excerpt_line, line_number) in excerpt_lines.iter().zip(line_numbers.iter()) {
+                excerpt += &format!(
+                    " {:>width$} | {}\n",
+                    line_number,
+                    excerpt_line,
+                    width = number_width
+                );
+            }
+
+            (excerpt, padding)
+        }
+    };
+
+    format!(
+        "{message}\n --> {}\n{padding}|\n{excerpt}",
+        position_info,
+        padding = padding,
+        excerpt = excerpt,
+        message = message
+    )
+}
@@ -3,13 +3,15 @@
 mod ast;
 mod constant_pool;
 mod error;
+mod excerpt;
 mod node;
 mod parser;
 
 pub use {
     ast::*,
-    constant_pool::{Constant, ConstantPool},
+    constant_pool::{Constant, ConstantPool, ConstantPoolBuilder},
     error::{is_indentation_error, ParserError},
+    excerpt::format_error_with_excerpt,
     koto_lexer::{Position, Span},
     node::*,
     parser::Parser,
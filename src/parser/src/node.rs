@@ -12,8 +12,10 @@ pub enum Node {
     BoolFalse,
     Number0,
     Number1,
-    Number(ConstantIndex),
+    Float(ConstantIndex),
+    Int(ConstantIndex),
     Str(ConstantIndex),
+    InterpolatedString(Vec<StringNode>),
     Num2(Vec<AstIndex>),
     Num4(Vec<AstIndex>),
     List(Vec<AstIndex>),
@@ -87,6 +89,7 @@ pub enum Node {
     CopyExpression(AstIndex),
     Negate(AstIndex),
     Try(AstTry),
+    Throw(AstIndex),
     Yield(AstIndex),
     Debug {
         expression_string: ConstantIndex,
@@ -109,10 +112,12 @@ impl fmt::Display for Node {
             Lookup(_) => write!(f, "Lookup"),
             BoolTrue => write!(f, "BoolTrue"),
             BoolFalse => write!(f, "BoolFalse"),
-            Number(_) => write!(f, "Number"),
             Number0 => write!(f, "Number0"),
             Number1 => write!(f, "Number1"),
+            Float(_) => write!(f, "Float"),
+            Int(_) => write!(f, "Int"),
             Str(_) => write!(f, "Str"),
+            InterpolatedString(_) => write!(f, "InterpolatedString"),
             Num2(_) => write!(f, "Num2"),
             Num4(_) => write!(f, "Num4"),
             List(_) => write!(f, "List"),
@@ -146,12 +151,19 @@ impl fmt::Display for Node {
             Return => write!(f, "Return"),
             ReturnExpression(_) => write!(f, "ReturnExpression"),
             Try { .. } => write!(f, "Try"),
+            Throw(_) => write!(f, "Throw"),
             Yield { .. } => write!(f, "Yield"),
             Debug { .. } => write!(f, "Debug"),
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StringNode {
+    Literal(ConstantIndex),
+    Expression(AstIndex),
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Function {
     pub args: Vec<Option<ConstantIndex>>,
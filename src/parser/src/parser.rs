@@ -2,7 +2,7 @@
 
 use {
     crate::{constant_pool::ConstantPoolBuilder, error::*, *},
-    koto_lexer::{Lexer, Span, Token},
+    koto_lexer::{string_sections, Lexer, Position, Span, StringSection, Token},
     std::{
         collections::{HashMap, HashSet},
         iter::FromIterator,
@@ -52,10 +52,6 @@ macro_rules! syntax_error {
     }};
 }
 
-fn f64_eq(a: f64, b: f64) -> bool {
-    (a - b).abs() < std::f64::EPSILON
-}
-
 enum ConstantIndexOrWildcard {
     Index(ConstantIndex),
     Wildcard,
@@ -726,7 +722,7 @@ impl<'source> Parser<'source> {
             }
             Some(Token::String) => {
                 self.consume_next_token_on_same_line();
-                let s = self.parse_string(self.lexer.slice())?;
+                let s = self.parse_string()?;
                 Some(self.constants.add_string(&s) as ConstantIndex)
             }
             _ => None,
@@ -1174,19 +1170,26 @@ impl<'source> Parser<'source> {
                 Token::ParenOpen => self.parse_nested_expressions(context)?,
                 Token::Number => {
                     self.consume_next_token(context);
-                    let number_node = match f64::from_str(self.lexer.slice()) {
-                        Ok(n) => {
-                            if f64_eq(n, 0.0) {
-                                self.push_node(Number0)?
-                            } else if f64_eq(n, 1.0) {
-                                self.push_node(Number1)?
-                            } else {
-                                let constant_index = self.constants.add_number(n) as u32;
-                                self.push_node(Number(constant_index))?
+                    let slice = self.lexer.slice();
+                    let number_node = if let Ok(n) = i64::from_str(slice) {
+                        match n {
+                            0 => self.push_node(Number0)?,
+                            1 => self.push_node(Number1)?,
+                            _ => {
+                                let constant_index = self.constants.add_i64(n) as u32;
+                                self.push_node(Int(constant_index))?
                             }
                         }
-                        Err(_) => {
-                            return internal_error!(NumberParseFailure, self);
+                    } else {
+                        // Floats, and integer literals that are too large to fit in an i64
+                        match f64::from_str(slice) {
+                            Ok(n) => {
+                                let constant_index = self.constants.add_f64(n) as u32;
+                                self.push_node(Float(constant_index))?
+                            }
+                            Err(_) => {
+                                return internal_error!(NumberParseFailure, self);
+                            }
                         }
                     };
                     if self.next_token_is_lookup_start(context) {
@@ -1197,9 +1200,7 @@ impl<'source> Parser<'source> {
                 }
                 Token::String => {
                     self.consume_next_token(context);
-                    let s = self.parse_string(self.lexer.slice())?;
-                    let constant_index = self.constants.add_string(&s) as u32;
-                    let string_node = self.push_node(Str(constant_index))?;
+                    let string_node = self.parse_string_literal()?;
                     if self.next_token_is_lookup_start(context) {
                         Some(self.parse_lookup(string_node, context)?)
                     } else {
@@ -1327,6 +1328,17 @@ impl<'source> Parser<'source> {
                     };
                     Some(result)
                 }
+                Token::Throw => {
+                    self.consume_next_token(context);
+                    let start_span = self.lexer.span();
+                    if let Some(expression) =
+                        self.parse_expressions(&mut context.start_new_expression(), false)?
+                    {
+                        Some(self.push_node_with_start_span(Node::Throw(expression), start_span)?)
+                    } else {
+                        return syntax_error!(ExpectedExpression, self);
+                    }
+                }
                 Token::From | Token::Import => self.parse_import_expression(context)?,
                 Token::Try if context.allow_space_separated_call => {
                     self.parse_try_expression(context)?
@@ -2168,11 +2180,104 @@ impl<'source> Parser<'source> {
         }
     }
 
-    fn parse_string(&self, s: &str) -> Result<String, ParserError> {
-        let without_quotes = &s[1..s.len() - 1];
+    // Parses the string that was just consumed, producing a Str node,
+    // or an InterpolatedString node if the string contains embedded expressions
+    fn parse_string_literal(&mut self) -> Result<AstIndex, ParserError> {
+        let string = self.lexer.slice();
+        let string_span = self.lexer.span();
+        let string_start = self.lexer.source_position() - string.len();
+
+        let sections = string_sections(string, string_span.start);
+
+        let is_interpolated = sections
+            .iter()
+            .any(|section| matches!(section, StringSection::Expression { .. }));
+        if !is_interpolated {
+            let s = self.parse_string()?;
+            let constant_index = self.constants.add_string(&s) as ConstantIndex;
+            return self.push_node(Node::Str(constant_index));
+        }
+
+        let mut nodes = Vec::with_capacity(sections.len());
+
+        for section in sections {
+            match section {
+                StringSection::Literal(literal) => {
+                    let s = self.process_string_escapes(literal)?;
+                    nodes.push(StringNode::Literal(
+                        self.constants.add_string(&s) as ConstantIndex
+                    ));
+                }
+                StringSection::Expression {
+                    start,
+                    end,
+                    position,
+                } => {
+                    let expression = self.parse_string_expression(
+                        string_start + start,
+                        string_start + end,
+                        position,
+                    )?;
+                    nodes.push(StringNode::Expression(expression));
+                }
+            }
+        }
+
+        self.push_node_with_span(Node::InterpolatedString(nodes), string_span)
+    }
+
+    // Parses an expression that's embedded in a string, between the given source offsets
+    fn parse_string_expression(
+        &mut self,
+        start: usize,
+        end: usize,
+        position: Position,
+    ) -> Result<AstIndex, ParserError> {
+        let expression_lexer = Lexer::with_start(&self.lexer.source()[..end], start, position);
+        let string_lexer = std::mem::replace(&mut self.lexer, expression_lexer);
+
+        let result = self.parse_string_expression_tokens();
+
+        self.lexer = string_lexer;
+        result
+    }
+
+    fn parse_string_expression_tokens(&mut self) -> Result<AstIndex, ParserError> {
+        let mut context = ExpressionContext::permissive();
+
+        self.consume_until_next_token(&mut context);
+
+        let expression = match self.parse_expressions(&mut context, false)? {
+            Some(expression) => expression,
+            None => return syntax_error!(ExpectedStringExpression, self),
+        };
+
+        if self.peek_next_token(&context).is_some() {
+            self.consume_until_next_token(&mut context);
+            self.lexer.next();
+            return syntax_error!(ExpectedStringExpressionEnd, self);
+        }
+
+        Ok(expression)
+    }
+
+    // Parses the string that was just consumed, without support for embedded expressions
+    fn parse_string(&self) -> Result<String, ParserError> {
+        let string = self.lexer.slice();
+
+        let is_interpolated = string_sections(string, self.lexer.span().start)
+            .iter()
+            .any(|section| matches!(section, StringSection::Expression { .. }));
+        if is_interpolated {
+            return syntax_error!(UnexpectedStringInterpolation, self);
+        }
+
+        self.process_string_escapes(&string[1..string.len() - 1])
+    }
 
-        let mut result = String::with_capacity(without_quotes.len());
-        let mut chars = without_quotes.chars().peekable();
+    fn process_string_escapes(&self, s: &str) -> Result<String, ParserError> {
+        let mut result = String::with_capacity(s.len());
+        let mut chars = s.chars().peekable();
 
         while let Some(c) = chars.next() {
             match c {
@@ -2183,6 +2288,8 @@ impl<'source> Parser<'source> {
                     Some('n') => result.push('\n'),
                     Some('r') => result.push('\r'),
                     Some('t') => result.push('\t'),
+                    Some('{') => result.push('{'),
+                    Some('}') => result.push('}'),
                     Some('\n') | Some('\r') => {
                         while let Some(c) = chars.peek() {
                             if c.is_whitespace() {
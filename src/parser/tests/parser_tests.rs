@@ -53,7 +53,7 @@ a
                     BoolTrue,
                     BoolFalse,
                     Number1,
-                    Number(0),
+                    Float(0),
                     Str(1),
                     Id(2),
                     Empty,
@@ -63,13 +63,45 @@ a
                     },
                 ],
                 Some(&[
-                    Constant::Number(1.5),
+                    Constant::F64(1.5),
                     Constant::Str("hello"),
                     Constant::Str("a"),
                 ]),
             )
         }
 
+        #[test]
+        fn number_literals() {
+            let source = "
+0
+1
+42
+4.2
+-1e3
+99999999999999999999";
+            check_ast(
+                source,
+                &[
+                    Number0,
+                    Number1,
+                    Int(0),
+                    Float(1),
+                    Float(2),
+                    Float(3),
+                    MainBlock {
+                        body: vec![0, 1, 2, 3, 4, 5],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[
+                    Constant::I64(42),
+                    Constant::F64(4.2),
+                    Constant::F64(-1e3),
+                    Constant::F64(1e20),
+                ]),
+            )
+        }
+
         #[test]
         fn multiline_strings() {
             let source = r#"
@@ -97,6 +129,78 @@ a
             )
         }
 
+        #[test]
+        fn interpolated_strings() {
+            let source = r#"
+"hello {name}!"
+"{a + 1}{{}}\{"
+"#;
+            check_ast(
+                source,
+                &[
+                    Id(1),
+                    InterpolatedString(vec![
+                        StringNode::Literal(0),
+                        StringNode::Expression(0),
+                        StringNode::Literal(2),
+                    ]),
+                    Id(3),
+                    Number1,
+                    BinaryOp {
+                        op: AstOp::Add,
+                        lhs: 2,
+                        rhs: 3,
+                    },
+                    InterpolatedString(vec![StringNode::Expression(4), StringNode::Literal(4)]),
+                    MainBlock {
+                        body: vec![1, 5],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[
+                    Constant::Str("hello "),
+                    Constant::Str("name"),
+                    Constant::Str("!"),
+                    Constant::Str("a"),
+                    Constant::Str("{{}}{"),
+                ]),
+            )
+        }
+
+        #[test]
+        fn interpolated_string_with_nested_string() {
+            let source = r#""{x.get "{y}"}""#;
+            check_ast(
+                source,
+                &[
+                    Id(0),
+                    Id(2),
+                    InterpolatedString(vec![StringNode::Expression(1)]),
+                    Lookup((LookupNode::Call(vec![2]), None)),
+                    Lookup((LookupNode::Id(1), Some(3))),
+                    Lookup((LookupNode::Root(0), Some(4))),
+                    InterpolatedString(vec![StringNode::Expression(5)]),
+                    MainBlock {
+                        body: vec![6],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[Constant::Str("x"), Constant::Str("get"), Constant::Str("y")]),
+            )
+        }
+
+        #[test]
+        fn interpolated_string_expression_span() {
+            let source = "x = 1\n\"abc {x +} def\"";
+            match Parser::parse(source) {
+                Ok(_) => panic!("Expected a parsing failure"),
+                Err(error) => {
+                    assert_eq!(error.span.start.line, 2);
+                    assert_eq!(error.span.start.column, 9);
+                }
+            }
+        }
+
         #[test]
         fn negatives() {
             let source = "\
@@ -107,7 +211,7 @@ a
             check_ast(
                 source,
                 &[
-                    Number(0),
+                    Float(0),
                     Id(1),
                     Negate(1),
                     Id(2),
@@ -128,11 +232,7 @@ a
                         local_count: 0,
                     },
                 ],
-                Some(&[
-                    Constant::Number(-12.0),
-                    Constant::Str("a"),
-                    Constant::Str("x"),
-                ]),
+                Some(&[Constant::F64(-12.0), Constant::Str("a"), Constant::Str("x")]),
             )
         }
 
@@ -149,7 +249,7 @@ a
                     Id(0),
                     Str(1),
                     Id(0),
-                    Number(2),
+                    Int(2),
                     List(vec![0, 1, 2, 3, 4]),
                     List(vec![]),
                     MainBlock {
@@ -157,11 +257,7 @@ a
                         local_count: 0,
                     },
                 ],
-                Some(&[
-                    Constant::Str("n"),
-                    Constant::Str("test"),
-                    Constant::Number(-1.0),
-                ]),
+                Some(&[Constant::Str("n"), Constant::Str("test"), Constant::I64(-1)]),
             )
         }
 
@@ -209,7 +305,7 @@ x = [
                 source,
                 &[
                     Map(vec![]),
-                    Number(1),
+                    Int(1),
                     Str(4),
                     // map entries are constant/ast index pairs
                     Map(vec![(0, Some(1)), (2, None), (3, Some(2))]),
@@ -220,7 +316,7 @@ x = [
                 ],
                 Some(&[
                     Constant::Str("foo"),
-                    Constant::Number(42.0),
+                    Constant::I64(42),
                     Constant::Str("bar"),
                     Constant::Str("baz"),
                     Constant::Str("hello"),
@@ -240,7 +336,7 @@ x = [
             check_ast(
                 source,
                 &[
-                    Number(1),
+                    Int(1),
                     Str(4),
                     // map entries are constant/ast index pairs
                     Map(vec![(0, Some(0)), (2, None), (3, Some(1))]),
@@ -251,7 +347,7 @@ x = [
                 ],
                 Some(&[
                     Constant::Str("foo"),
-                    Constant::Number(42.0),
+                    Constant::I64(42),
                     Constant::Str("bar"),
                     Constant::Str("baz"),
                     Constant::Str("hello"),
@@ -271,8 +367,8 @@ x"#;
             check_ast(
                 source,
                 &[
-                    Id(0),     // x
-                    Number(2), // 42
+                    Id(0),  // x
+                    Int(2), // 42
                     Number0,
                     // map entries are constant/ast pairs
                     Map(vec![(1, Some(2))]), // baz, nested map
@@ -294,7 +390,7 @@ x"#;
                 Some(&[
                     Constant::Str("x"),
                     Constant::Str("foo"),
-                    Constant::Number(42.0),
+                    Constant::I64(42),
                     Constant::Str("bar"),
                     Constant::Str("baz"),
                 ]),
@@ -387,7 +483,7 @@ min..max
                         expression: 1,
                     },
                     Id(1),
-                    Number(2),
+                    Int(2),
                     Assign {
                         target: AssignTarget {
                             target_index: 3,
@@ -411,7 +507,7 @@ min..max
                 Some(&[
                     Constant::Str("min"),
                     Constant::Str("max"),
-                    Constant::Number(10.0),
+                    Constant::I64(10),
                 ]),
             )
         }
@@ -463,13 +559,13 @@ min..max
                     },
                     List(vec![2]),
                     Number0,
-                    Number(0), // 5
+                    Int(0), // 5
                     Range {
                         start: 4,
                         end: 5,
                         inclusive: false,
                     },
-                    Number(0),
+                    Int(0),
                     Number0,
                     Range {
                         start: 7,
@@ -482,7 +578,7 @@ min..max
                         local_count: 0,
                     },
                 ],
-                Some(&[Constant::Number(10.0)]),
+                Some(&[Constant::I64(10)]),
             )
         }
 
@@ -693,8 +789,8 @@ num4(
                     Number0,
                     Number1,
                     Tuple(vec![1, 2]),
-                    Number(1),
-                    Number(2), // 5
+                    Int(1),
+                    Int(2), // 5
                     Tuple(vec![4, 5]),
                     Tuple(vec![3, 6]),
                     Assign {
@@ -710,11 +806,7 @@ num4(
                         local_count: 1,
                     },
                 ],
-                Some(&[
-                    Constant::Str("x"),
-                    Constant::Number(2.0),
-                    Constant::Number(3.0),
-                ]),
+                Some(&[Constant::Str("x"), Constant::I64(2), Constant::I64(3)]),
             )
         }
 
@@ -862,7 +954,7 @@ x %= 4";
                         expression: 4,
                     }, // 5
                     Id(0),
-                    Number(1),
+                    Int(1),
                     Assign {
                         target: AssignTarget {
                             target_index: 6,
@@ -872,7 +964,7 @@ x %= 4";
                         expression: 7,
                     },
                     Id(0),
-                    Number(2), // 10
+                    Int(2), // 10
                     Assign {
                         target: AssignTarget {
                             target_index: 9,
@@ -882,7 +974,7 @@ x %= 4";
                         expression: 10,
                     },
                     Id(0),
-                    Number(3),
+                    Int(3),
                     Assign {
                         target: AssignTarget {
                             target_index: 12,
@@ -898,9 +990,9 @@ x %= 4";
                 ],
                 Some(&[
                     Constant::Str("x"),
-                    Constant::Number(2.0),
-                    Constant::Number(3.0),
-                    Constant::Number(4.0),
+                    Constant::I64(2),
+                    Constant::I64(3),
+                    Constant::I64(4),
                 ]),
             )
         }
@@ -1011,14 +1103,14 @@ x %= 4";
             check_ast(
                 source,
                 &[
-                    Number(0),
-                    Number(1),
+                    Int(0),
+                    Int(1),
                     BinaryOp {
                         op: AstOp::Divide,
                         lhs: 0,
                         rhs: 1,
                     },
-                    Number(2),
+                    Int(2),
                     BinaryOp {
                         op: AstOp::Modulo,
                         lhs: 2,
@@ -1029,11 +1121,7 @@ x %= 4";
                         local_count: 0,
                     },
                 ],
-                Some(&[
-                    Constant::Number(18.0),
-                    Constant::Number(3.0),
-                    Constant::Number(4.0),
-                ]),
+                Some(&[Constant::I64(18), Constant::I64(3), Constant::I64(4)]),
             )
         }
 
@@ -1107,8 +1195,8 @@ a = 1 +
                 &[
                     Id(0),
                     Number1,
-                    Number(1),
-                    Number(2),
+                    Int(1),
+                    Int(2),
                     BinaryOp {
                         op: AstOp::Multiply,
                         lhs: 2,
@@ -1132,11 +1220,7 @@ a = 1 +
                         local_count: 1,
                     },
                 ],
-                Some(&[
-                    Constant::Str("a"),
-                    Constant::Number(2.0),
-                    Constant::Number(3.0),
-                ]),
+                Some(&[Constant::Str("a"), Constant::I64(2), Constant::I64(3)]),
             )
         }
 
@@ -1152,8 +1236,8 @@ a = 1
                 &[
                     Id(0),
                     Number1,
-                    Number(1),
-                    Number(2),
+                    Int(1),
+                    Int(2),
                     BinaryOp {
                         op: AstOp::Multiply,
                         lhs: 2,
@@ -1177,11 +1261,7 @@ a = 1
                         local_count: 1,
                     },
                 ],
-                Some(&[
-                    Constant::Str("a"),
-                    Constant::Number(2.0),
-                    Constant::Number(3.0),
-                ]),
+                Some(&[Constant::Str("a"), Constant::I64(2), Constant::I64(3)]),
             )
         }
     }
@@ -1523,7 +1603,7 @@ a()";
                 source,
                 &[
                     Id(0),
-                    Number(1),
+                    Int(1),
                     Function(koto_parser::Function {
                         args: vec![],
                         local_count: 0,
@@ -1549,7 +1629,7 @@ a()";
                         local_count: 1,
                     },
                 ],
-                Some(&[Constant::Str("a"), Constant::Number(42.0)]),
+                Some(&[Constant::Str("a"), Constant::I64(42)]),
             )
         }
 
@@ -1680,7 +1760,7 @@ f 42";
                         expression: 11,
                     },
                     Id(0),
-                    Number(3),
+                    Int(3),
                     Call {
                         function: 13,
                         args: vec![14],
@@ -1694,7 +1774,7 @@ f 42";
                     Constant::Str("f"),
                     Constant::Str("x"),
                     Constant::Str("y"),
-                    Constant::Number(42.0),
+                    Constant::I64(42),
                 ]),
             )
         }
@@ -1755,7 +1835,7 @@ f 42";
                         expression: 9,
                     }, // 10
                     Id(0), // f
-                    Number(4),
+                    Int(4),
                     Call {
                         function: 11,
                         args: vec![12],
@@ -1770,7 +1850,7 @@ f 42";
                     Constant::Str("x"),
                     Constant::Str("y"),
                     Constant::Str("z"),
-                    Constant::Number(42.0),
+                    Constant::I64(42),
                 ]),
             )
         }
@@ -1980,7 +2060,7 @@ f x";
             check_ast(
                 source,
                 &[
-                    Number(1),
+                    Int(1),
                     Id(3), // self
                     Lookup((LookupNode::Id(0), None)),
                     Lookup((LookupNode::Root(1), Some(2))),
@@ -2011,7 +2091,7 @@ f x";
                 ],
                 Some(&[
                     Constant::Str("foo"),
-                    Constant::Number(42.0),
+                    Constant::I64(42),
                     Constant::Str("bar"),
                     Constant::Str("self"),
                     Constant::Str("x"),
@@ -2076,7 +2156,7 @@ f()";
                 source,
                 &[
                     Id(0),
-                    Number(2),
+                    Int(2),
                     Id(4),
                     Lookup((LookupNode::Id(1), None)),
                     Lookup((LookupNode::Root(2), Some(3))),
@@ -2128,7 +2208,7 @@ f()";
                 Some(&[
                     Constant::Str("f"),
                     Constant::Str("foo"),
-                    Constant::Number(42.0),
+                    Constant::I64(42),
                     Constant::Str("bar"),
                     Constant::Str("self"),
                     Constant::Str("x"),
@@ -2315,7 +2395,7 @@ y z";
                     Id(0),
                     Id(1),
                     Number0,
-                    Number(2),
+                    Int(2),
                     Range {
                         start: 2,
                         end: 3,
@@ -2364,7 +2444,7 @@ y z";
                 Some(&[
                     Constant::Str("z"),
                     Constant::Str("y"),
-                    Constant::Number(20.0),
+                    Constant::I64(20),
                     Constant::Str("x"),
                 ]),
             )
@@ -2434,7 +2514,7 @@ y z";
             check_ast(
                 source,
                 &[
-                    Number(1),
+                    Int(1),
                     Map(vec![(0, Some(0))]),
                     Yield(1),
                     Function(koto_parser::Function {
@@ -2451,7 +2531,7 @@ y z";
                         local_count: 0,
                     },
                 ],
-                Some(&[Constant::Str("foo"), Constant::Number(42.0)]),
+                Some(&[Constant::Str("foo"), Constant::I64(42)]),
             )
         }
     }
@@ -2517,7 +2597,7 @@ y z";
                 source,
                 &[
                     Id(0),
-                    Number(1),
+                    Int(1),
                     RangeTo {
                         end: 1,
                         inclusive: false,
@@ -2529,7 +2609,7 @@ y z";
                         local_count: 0,
                     },
                 ],
-                Some(&[Constant::Str("x"), Constant::Number(3.0)]),
+                Some(&[Constant::Str("x"), Constant::I64(3)]),
             )
         }
 
@@ -2540,7 +2620,7 @@ y z";
                 source,
                 &[
                     Id(0),
-                    Number(1),
+                    Int(1),
                     RangeFrom { start: 1 },
                     Number0,
                     Lookup((LookupNode::Index(3), None)),
@@ -2551,7 +2631,7 @@ y z";
                         local_count: 0,
                     },
                 ],
-                Some(&[Constant::Str("x"), Constant::Number(10.0)]),
+                Some(&[Constant::Str("x"), Constant::I64(10)]),
             )
         }
 
@@ -2634,7 +2714,7 @@ x.bar()."baz" = 1
                 source,
                 &[
                     Id(0),
-                    Number(2),
+                    Int(2),
                     Lookup((LookupNode::Call(vec![1]), None)),
                     Lookup((LookupNode::Id(1), Some(2))),
                     Lookup((LookupNode::Root(0), Some(3))),
@@ -2643,11 +2723,7 @@ x.bar()."baz" = 1
                         local_count: 0,
                     },
                 ],
-                Some(&[
-                    Constant::Str("x"),
-                    Constant::Str("foo"),
-                    Constant::Number(42.0),
-                ]),
+                Some(&[Constant::Str("x"), Constant::Str("foo"), Constant::I64(42)]),
             )
         }
 
@@ -3212,6 +3288,23 @@ finally
                 Some(&[Constant::Str("f"), Constant::Str("e")]),
             )
         }
+
+        #[test]
+        fn throw_value() {
+            let source = r#"throw "error""#;
+            check_ast(
+                source,
+                &[
+                    Str(0),
+                    Throw(0),
+                    MainBlock {
+                        body: vec![1],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[Constant::Str("error")]),
+            )
+        }
     }
 
     mod match_expression {
@@ -3231,9 +3324,9 @@ x = match y
                     Id(1),
                     Number0,
                     Number1,
-                    Number(2),
+                    Int(2),
                     Id(3), // 5
-                    Number(4),
+                    Int(4),
                     Match {
                         expression: 1,
                         arms: vec![
@@ -3265,9 +3358,9 @@ x = match y
                 Some(&[
                     Constant::Str("x"),
                     Constant::Str("y"),
-                    Constant::Number(42.0),
+                    Constant::I64(42),
                     Constant::Str("z"),
-                    Constant::Number(-1.0),
+                    Constant::I64(-1),
                 ]),
             )
         }
@@ -3284,7 +3377,7 @@ match x
                 &[
                     Id(0),
                     Str(1),
-                    Number(2),
+                    Int(2),
                     Str(3),
                     Str(4),
                     Break, // 5
@@ -3311,7 +3404,7 @@ match x
                 Some(&[
                     Constant::Str("x"),
                     Constant::Str("foo"),
-                    Constant::Number(99.0), // 5
+                    Constant::I64(99), // 5
                     Constant::Str("bar"),
                     Constant::Str("baz"),
                 ]),
@@ -3481,7 +3574,7 @@ match x
                     Id(0),
                     Id(1),
                     Id(1),
-                    Number(2),
+                    Int(2),
                     BinaryOp {
                         op: AstOp::Greater,
                         lhs: 2,
@@ -3490,7 +3583,7 @@ match x
                     Number0, // 5
                     Id(1),
                     Id(1),
-                    Number(3),
+                    Int(3),
                     BinaryOp {
                         op: AstOp::Less,
                         lhs: 7,
@@ -3498,7 +3591,7 @@ match x
                     },
                     Number1, // 10
                     Id(1),
-                    Number(4),
+                    Int(4),
                     Match {
                         expression: 0,
                         arms: vec![
@@ -3527,9 +3620,9 @@ match x
                 Some(&[
                     Constant::Str("x"),
                     Constant::Str("z"),
-                    Constant::Number(5.0),
-                    Constant::Number(10.0),
-                    Constant::Number(-1.0),
+                    Constant::I64(5),
+                    Constant::I64(10),
+                    Constant::I64(-1),
                 ]),
             )
         }
@@ -3552,8 +3645,8 @@ match x, y
                     Number0,
                     Number1,
                     TempTuple(vec![3, 4]), // 5
-                    Number(2),
-                    Number(3),
+                    Int(2),
+                    Int(3),
                     TempTuple(vec![6, 7]),
                     Id(4),
                     Number0, // 10
@@ -3591,8 +3684,8 @@ match x, y
                 Some(&[
                     Constant::Str("x"),
                     Constant::Str("y"),
-                    Constant::Number(2.0),
-                    Constant::Number(3.0),
+                    Constant::I64(2),
+                    Constant::I64(3),
                     Constant::Str("z"),
                     Constant::Str("a"),
                 ]),
@@ -3610,7 +3703,7 @@ match x.foo 42
                 source,
                 &[
                     Id(0),
-                    Number(2),
+                    Int(2),
                     Lookup((LookupNode::Call(vec![1]), None)),
                     Lookup((LookupNode::Id(1), Some(2))),
                     Lookup((LookupNode::Root(0), Some(3))),
@@ -3638,11 +3731,7 @@ match x.foo 42
                         local_count: 0,
                     },
                 ],
-                Some(&[
-                    Constant::Str("x"),
-                    Constant::Str("foo"),
-                    Constant::Number(42.0),
-                ]),
+                Some(&[Constant::Str("x"), Constant::Str("foo"), Constant::I64(42)]),
             )
         }
     }
@@ -40,11 +40,40 @@ mod parser {
             check_parsing_fails("1 + * 2");
         }
 
+        #[test]
+        fn throw_without_value() {
+            check_parsing_fails("throw");
+        }
+
         #[test]
         fn missing_comma_in_import() {
             check_parsing_fails("import foo bar");
         }
 
+        mod strings {
+            use super::*;
+
+            #[test]
+            fn unterminated_string_expression() {
+                check_parsing_fails(r#""hello {name""#);
+            }
+
+            #[test]
+            fn empty_string_expression() {
+                check_parsing_fails(r#""hello { }""#);
+            }
+
+            #[test]
+            fn unexpected_token_in_string_expression() {
+                check_parsing_fails(r#""hello {name)}""#);
+            }
+
+            #[test]
+            fn interpolated_string_as_map_key() {
+                check_parsing_fails(r#"{"{x}": 1}"#);
+            }
+        }
+
         mod indentation {
             use super::*;
 
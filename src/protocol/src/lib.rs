@@ -0,0 +1,92 @@
+//! Message framing for the Koto language server and debug adapter
+//!
+//! The Language Server Protocol and the Debug Adapter Protocol share the same base protocol,
+//! with each JSON message being preceded by a header containing the message's length in bytes.
+
+use {
+    serde_json::Value as Json,
+    std::io::{self, BufRead, Write},
+};
+
+/// Reads a message from the input, returning None when the input has been closed
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut content_length = None;
+
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+
+    let content_length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "Missing Content-Length header")
+    })?;
+
+    let mut content = vec![0; content_length];
+    input.read_exact(&mut content)?;
+
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Writes a message to the output, preceded by its header
+pub fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let content = message.to_string();
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn reading_messages() {
+        let content = r#"{"jsonrpc":"2.0","id":1,"method":"initialize"}"#;
+        let input = format!("Content-Length: {}\r\n\r\n{}", content.len(), content);
+
+        let message = read_message(&mut input.as_bytes()).unwrap().unwrap();
+        assert_eq!(message["method"], "initialize");
+    }
+
+    #[test]
+    fn reading_closed_input() {
+        assert!(read_message(&mut "".as_bytes()).unwrap().is_none());
+    }
+
+    #[test]
+    fn missing_content_length() {
+        let input = "Content-Type: application/json\r\n\r\n{}";
+        assert!(read_message(&mut input.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn written_messages_can_be_read() {
+        let message = json!({"seq": 1, "type": "event", "event": "stopped"});
+
+        let mut output = Vec::new();
+        write_message(&mut output, &message).unwrap();
+        write_message(&mut output, &message).unwrap();
+
+        let mut input = output.as_slice();
+        assert_eq!(read_message(&mut input).unwrap(), Some(message.clone()));
+        assert_eq!(read_message(&mut input).unwrap(), Some(message));
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+}
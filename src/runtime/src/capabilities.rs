@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+/// The capabilities that are made available to scripts that are run by a VM
+///
+/// By default all capabilities are available, a restricted set of capabilities can be used when
+/// running untrusted scripts, see [Vm::with_capabilities](crate::Vm::with_capabilities).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Access to the filesystem and environment, via the `io` module and `koto.current_dir`
+    pub filesystem: bool,
+    /// Running functions on other threads, via the `thread` module
+    pub threads: bool,
+    /// Which modules can be loaded from `.koto` and `.kotoc` files by `import` expressions
+    ///
+    /// Core library modules and values in the prelude can always be imported.
+    pub imports: ImportPolicy,
+}
+
+impl Capabilities {
+    /// All capabilities are available
+    pub fn all() -> Self {
+        Self {
+            filesystem: true,
+            threads: true,
+            imports: ImportPolicy::Unrestricted,
+        }
+    }
+
+    /// No capabilities are available
+    pub fn none() -> Self {
+        Self {
+            filesystem: false,
+            threads: false,
+            imports: ImportPolicy::Disabled,
+        }
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// Controls which modules can be loaded from files by `import` expressions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportPolicy {
+    /// Modules can be loaded from anywhere
+    Unrestricted,
+    /// Modules can't be loaded from files
+    Disabled,
+    /// Modules can only be loaded from files within the given directory
+    AllowedDirectory(PathBuf),
+}
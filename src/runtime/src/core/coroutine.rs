@@ -0,0 +1,53 @@
+use crate::{external_error, type_as_string, Coroutine, Value, ValueMap};
+
+pub fn make_module() -> ValueMap {
+    use Value::*;
+
+    let mut result = ValueMap::new();
+
+    result.add_fn("is_finished", |vm, args| match vm.get_args(args) {
+        [value] => match Coroutine::from_value(value) {
+            Some(coroutine) => Ok(Bool(coroutine.is_finished())),
+            None => external_error!(
+                "coroutine.is_finished: Expected coroutine as argument, found '{}'",
+                type_as_string(value),
+            ),
+        },
+        _ => external_error!("coroutine.is_finished: Expected coroutine as argument"),
+    });
+
+    result.add_fn("new", |vm, args| {
+        let args = vm.get_args(args).to_vec();
+        match args.as_slice() {
+            [f, function_args @ ..] if matches!(f, Function(_) | Generator(_)) => {
+                vm.make_coroutine(f, function_args).map(Value::from)
+            }
+            _ => external_error!("coroutine.new: Expected function as first argument"),
+        }
+    });
+
+    result.add_fn("resume", |vm, args| {
+        let (coroutine, value) = match vm.get_args(args) {
+            [coroutine] => (coroutine, Empty),
+            [coroutine, value] => (coroutine, value.clone()),
+            _ => {
+                return external_error!(
+                    "coroutine.resume: Expected coroutine and optional value as arguments"
+                )
+            }
+        };
+
+        match Coroutine::from_value(coroutine) {
+            Some(coroutine) => match coroutine.resume(value)? {
+                Some(result) => Ok(result),
+                None => Ok(Empty),
+            },
+            None => external_error!(
+                "coroutine.resume: Expected coroutine as first argument, found '{}'",
+                type_as_string(coroutine),
+            ),
+        }
+    });
+
+    result
+}
@@ -30,35 +30,41 @@ pub fn make_file_map() -> ValueMap {
     });
 
     file_map.add_instance_fn("write", |vm, args| {
-        file_fn("write", vm.get_args(args), |file_handle| {
-            match vm.get_args(args) {
-                [_, value] => {
-                    let data = format!("{}", value);
-
-                    match file_handle.file.write(data.as_bytes()) {
-                        Ok(_) => Ok(Value::Empty),
-                        Err(e) => external_error!("File.write: Error while writing to file: {}", e),
-                    }
-                }
-                _ => external_error!("File.write: Expected single value to write as argument"),
+        let data = match vm.get_args(args) {
+            [_, value] => {
+                let value = value.clone();
+                vm.value_to_string(&value)?
             }
+            _ => return external_error!("File.write: Expected single value to write as argument"),
+        };
+
+        file_fn("write", vm.get_args(args), |file_handle| match file_handle
+            .file
+            .write(data.as_bytes())
+        {
+            Ok(_) => Ok(Value::Empty),
+            Err(e) => external_error!("File.write: Error while writing to file: {}", e),
         })
     });
 
     file_map.add_instance_fn("write_line", |vm, args| {
-        file_fn("write_line", vm.get_args(args), |file_handle| {
-            let line = match vm.get_args(args) {
-                [_] => "\n".to_string(),
-                [_, value] => format!("{}\n", value),
-                _ => {
-                    return external_error!("File.write_line: Expected single value as argument");
-                }
-            };
-            match file_handle.file.write(line.as_bytes()) {
+        let line = match vm.get_args(args) {
+            [_] => "\n".to_string(),
+            [_, value] => {
+                let value = value.clone();
+                vm.value_to_string(&value)? + "\n"
+            }
+            _ => return external_error!("File.write_line: Expected single value as argument"),
+        };
+
+        file_fn(
+            "write_line",
+            vm.get_args(args),
+            |file_handle| match file_handle.file.write(line.as_bytes()) {
                 Ok(_) => Ok(Value::Empty),
                 Err(e) => external_error!("File.write_line: Error while writing to file: {}", e),
-            }
-        })
+            },
+        )
     });
 
     file_map.add_instance_fn("read_to_string", |vm, args| {
@@ -89,7 +95,7 @@ pub fn make_file_map() -> ValueMap {
                     if *n < 0.0 {
                         return external_error!("File.seek: Negative seek positions not allowed");
                     }
-                    match file_handle.file.seek(SeekFrom::Start(*n as u64)) {
+                    match file_handle.file.seek(SeekFrom::Start(i64::from(n) as u64)) {
                         Ok(_) => Ok(Value::Empty),
                         Err(e) => external_error!("File.seek: Error while seeking in file: {}", e),
                     }
@@ -1,10 +1,13 @@
-use crate::{
-    external_error, type_as_string, value,
-    value_iterator::{
-        is_iterable, make_iterator, ValueIterator, ValueIteratorOutput as Output,
-        ValueIteratorResult,
+use {
+    crate::{
+        external_error, type_as_string, value,
+        value_iterator::{
+            is_iterable, make_iterator, IntRange, ValueIterator, ValueIteratorOutput as Output,
+            ValueIteratorResult,
+        },
+        Error, Value, ValueHashMap, ValueList, ValueMap, ValueVec,
     },
-    Value, ValueHashMap, ValueList, ValueMap, ValueVec,
+    std::collections::VecDeque,
 };
 
 pub fn make_module() -> ValueMap {
@@ -76,6 +79,52 @@ pub fn make_module() -> ValueMap {
         _ => external_error!("iterator.any: Expected iterable and function as arguments"),
     });
 
+    result.add_fn("chain", |vm, args| match vm.get_args(args) {
+        [iterable_a, iterable_b] if is_iterable(iterable_a) && is_iterable(iterable_b) => {
+            let mut iter = make_iterator(iterable_a)
+                .unwrap()
+                .chain(make_iterator(iterable_b).unwrap());
+
+            Ok(Iterator(ValueIterator::make_external(move || iter.next())))
+        }
+        _ => external_error!("iterator.chain: Expected two iterables as arguments"),
+    });
+
+    result.add_fn("chunks", |vm, args| match vm.get_args(args) {
+        [iterable, Number(n)] if is_iterable(iterable) && *n >= 1.0 => {
+            let chunk_size = usize::from(n);
+            vm.check_container_size(chunk_size, 0)?;
+            let mut iter = make_iterator(iterable).unwrap().map(collect_pair).fuse();
+            let vm = vm.spawn_shared_vm();
+
+            Ok(Iterator(ValueIterator::make_external(move || {
+                let mut chunk = ValueVec::new();
+
+                for output in &mut iter {
+                    match output {
+                        Ok(Output::Value(value)) => {
+                            chunk.push(value);
+                            if chunk.len() == chunk_size {
+                                break;
+                            }
+                        }
+                        Err(error) => return Some(Err(error)),
+                        _ => unreachable!(),
+                    }
+                }
+
+                if chunk.is_empty() {
+                    None
+                } else if let Err(error) = vm.check_container_size(chunk.len(), chunk.len()) {
+                    Some(Err(error))
+                } else {
+                    Some(Ok(Output::Value(List(ValueList::with_data(chunk)))))
+                }
+            })))
+        }
+        _ => external_error!("iterator.chunks: Expected iterable and positive number as arguments"),
+    });
+
     result.add_fn("consume", |vm, args| match vm.get_args(args) {
         [iterable] if is_iterable(iterable) => {
             let iter = make_iterator(iterable).unwrap();
@@ -88,11 +137,42 @@ pub fn make_module() -> ValueMap {
     result.add_fn("count", |vm, args| match vm.get_args(args) {
         [iterable] if is_iterable(iterable) => {
             let iter = make_iterator(iterable).unwrap();
-            Ok(Number(iter.count() as f64))
+            Ok(Number(iter.count().into()))
         }
         _ => external_error!("iterator.count: Expected iterable as argument"),
     });
 
+    result.add_fn("cycle", |vm, args| match vm.get_args(args) {
+        [iterable] if is_iterable(iterable) => {
+            let mut iter = make_iterator(iterable).unwrap().map(collect_pair).fuse();
+            let vm = vm.spawn_shared_vm();
+            // Values from the first pass are cached so that they can be repeated
+            let mut cache = Vec::new();
+            let mut cache_index = 0;
+
+            Ok(Iterator(ValueIterator::make_external(move || {
+                match iter.next() {
+                    Some(Ok(Output::Value(value))) => {
+                        if let Err(error) = vm.check_container_size(cache.len() + 1, 1) {
+                            return Some(Err(error));
+                        }
+                        cache.push(value.clone());
+                        Some(Ok(Output::Value(value)))
+                    }
+                    Some(Err(error)) => Some(Err(error)),
+                    Some(_) => unreachable!(),
+                    None if cache.is_empty() => None,
+                    None => {
+                        let result = cache[cache_index % cache.len()].clone();
+                        cache_index += 1;
+                        Some(Ok(Output::Value(result)))
+                    }
+                }
+            })))
+        }
+        _ => external_error!("iterator.cycle: Expected iterable as argument"),
+    });
+
     result.add_fn("each", |vm, args| match vm.get_args(args) {
         [iterable, Function(f)] if is_iterable(iterable) => {
             let iter = make_iterator(iterable).unwrap().map(collect_pair);
@@ -119,7 +199,7 @@ pub fn make_module() -> ValueMap {
                 .unwrap()
                 .enumerate()
                 .map(|(i, iter_output)| match collect_pair(iter_output) {
-                    Ok(Output::Value(value)) => Ok(Output::ValuePair(Number(i as f64), value)),
+                    Ok(Output::Value(value)) => Ok(Output::ValuePair(Number(i.into()), value)),
                     other => other,
                 });
 
@@ -128,6 +208,66 @@ pub fn make_module() -> ValueMap {
         _ => external_error!("iterator.enumerate: Expected iterable as argument"),
     });
 
+    result.add_fn("find", |vm, args| match vm.get_args(args) {
+        [iterable, Function(f)] if is_iterable(iterable) => {
+            let iter = make_iterator(iterable).unwrap().map(collect_pair);
+            let f = f.clone();
+            let mut vm = vm.spawn_shared_vm();
+
+            for output in iter {
+                match output {
+                    Ok(Output::Value(value)) => {
+                        match vm.run_function(&f, std::slice::from_ref(&value)) {
+                            Ok(Bool(result)) => {
+                                if result {
+                                    return Ok(value);
+                                }
+                            }
+                            Ok(unexpected) => {
+                                return external_error!(
+                                    "iterator.find expects a Bool to be returned from the \
+                                 predicate, found '{}'",
+                                    value::type_as_string(&unexpected),
+                                )
+                            }
+                            Err(error) => return Err(error),
+                        }
+                    }
+                    Err(error) => return Err(error),
+                    _ => unreachable!(),
+                }
+            }
+
+            Ok(Empty)
+        }
+        _ => external_error!("iterator.find: Expected iterable and function as arguments"),
+    });
+
+    result.add_fn("flatten", |vm, args| match vm.get_args(args) {
+        [iterable] if is_iterable(iterable) => {
+            let mut iter = make_iterator(iterable).unwrap().map(collect_pair);
+            let mut inner: Option<ValueIterator> = None;
+
+            // Iterable values are flattened, other values are passed through unchanged
+            Ok(Iterator(ValueIterator::make_external(move || loop {
+                if let Some(inner_iter) = &mut inner {
+                    match inner_iter.next() {
+                        Some(output) => return Some(output),
+                        None => inner = None,
+                    }
+                }
+
+                match iter.next() {
+                    Some(Ok(Output::Value(value))) if is_iterable(&value) => {
+                        inner = Some(make_iterator(&value).unwrap());
+                    }
+                    other => return other,
+                }
+            })))
+        }
+        _ => external_error!("iterator.flatten: Expected iterable as argument"),
+    });
+
     result.add_fn("fold", |vm, args| {
         match vm.get_args(args) {
             [iterable, result, Function(f)] if is_iterable(iterable) => {
@@ -168,6 +308,81 @@ pub fn make_module() -> ValueMap {
         }
     });
 
+    result.add_fn("group_by", |vm, args| match vm.get_args(args) {
+        [iterable, Function(f)] if is_iterable(iterable) => {
+            let mut iter = make_iterator(iterable).unwrap().map(collect_pair).fuse();
+            let f = f.clone();
+            let mut vm = vm.spawn_shared_vm();
+
+            // Produces the next value along with its key
+            let mut next_entry = move || match iter.next() {
+                Some(Ok(Output::Value(value))) => Some(
+                    vm.run_function(&f, std::slice::from_ref(&value))
+                        .map(|key| (key, value)),
+                ),
+                Some(Err(error)) => Some(Err(error)),
+                Some(_) => unreachable!(),
+                None => None,
+            };
+            // The first entry of the following group
+            let mut pending: Option<Result<(Value, Value), Error>> = None;
+
+            Ok(Iterator(ValueIterator::make_external(move || {
+                let entry = match pending.take() {
+                    Some(entry) => entry,
+                    None => next_entry()?,
+                };
+                let (key, first_value) = match entry {
+                    Ok(entry) => entry,
+                    Err(error) => return Some(Err(error)),
+                };
+
+                let mut group = ValueVec::new();
+                group.push(first_value);
+
+                loop {
+                    match next_entry() {
+                        Some(Ok((next_key, value))) if next_key == key => group.push(value),
+                        Some(next) => {
+                            pending = Some(next);
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+
+                Some(Ok(Output::ValuePair(
+                    key,
+                    List(ValueList::with_data(group)),
+                )))
+            })))
+        }
+        _ => external_error!("iterator.group_by: Expected iterable and function as arguments"),
+    });
+
+    result.add_fn("intersperse", |vm, args| match vm.get_args(args) {
+        [iterable, separator] if is_iterable(iterable) => {
+            let mut iter = make_iterator(iterable)
+                .unwrap()
+                .map(collect_pair)
+                .fuse()
+                .peekable();
+            let separator = separator.clone();
+            let mut separator_is_next = false;
+
+            Ok(Iterator(ValueIterator::make_external(move || {
+                if separator_is_next && matches!(iter.peek(), Some(Ok(_))) {
+                    separator_is_next = false;
+                    Some(Ok(Output::Value(separator.clone())))
+                } else {
+                    separator_is_next = true;
+                    iter.next()
+                }
+            })))
+        }
+        _ => external_error!("iterator.intersperse: Expected iterable and value as arguments"),
+    });
+
     result.add_fn("keep", |vm, args| match vm.get_args(args) {
         [iterable, Function(f)] if is_iterable(iterable) => {
             let mut iter = make_iterator(iterable).unwrap().map(collect_pair);
@@ -272,7 +487,7 @@ pub fn make_module() -> ValueMap {
                     Ok(Output::Value(value)) => match vm.run_function(&f, &[value.clone()]) {
                         Ok(Bool(result)) => {
                             if result {
-                                return Ok(Number(i as f64));
+                                return Ok(Number(i.into()));
                             }
                         }
                         Ok(unexpected) => {
@@ -294,11 +509,94 @@ pub fn make_module() -> ValueMap {
         _ => external_error!("iterator.position: Expected iterable and function as arguments"),
     });
 
+    result.add_fn("product", |vm, args| match vm.get_args(args) {
+        [iterable] if is_iterable(iterable) => {
+            let mut result = Number(1.into());
+
+            for output in make_iterator(iterable).unwrap().map(collect_pair) {
+                let value = match output {
+                    Ok(Output::Value(value)) => value,
+                    Err(error) => return Err(error),
+                    _ => unreachable!(),
+                };
+
+                result = match (&result, &value) {
+                    (Number(a), Number(b)) => Number(a * b),
+                    (Number(a), Num2(b)) => Num2(&f64::from(a) * b),
+                    (Num2(a), Num2(b)) => Num2(a * b),
+                    (Num2(a), Number(b)) => Num2(a * &f64::from(b)),
+                    (Number(a), Num4(b)) => Num4(&f64::from(a) * b),
+                    (Num4(a), Num4(b)) => Num4(a * b),
+                    (Num4(a), Number(b)) => Num4(a * &f64::from(b)),
+                    _ => {
+                        return external_error!(
+                            "iterator.product: Unable to multiply '{}' and '{}'",
+                            type_as_string(&result),
+                            type_as_string(&value),
+                        )
+                    }
+                };
+            }
+
+            Ok(result)
+        }
+        _ => external_error!("iterator.product: Expected iterable as argument"),
+    });
+
+    result.add_fn("reversed", |vm, args| match vm.get_args(args) {
+        [Range(range)] => {
+            let reversed = if range.start <= range.end {
+                IntRange {
+                    start: range.end - 1,
+                    end: range.start - 1,
+                }
+            } else {
+                IntRange {
+                    start: range.end + 1,
+                    end: range.start + 1,
+                }
+            };
+
+            Ok(Iterator(ValueIterator::with_range(reversed)))
+        }
+        [List(l)] => {
+            let list = l.clone();
+            let mut index = list.len();
+
+            Ok(Iterator(ValueIterator::make_external(move || {
+                // The list may have been modified since the previous step
+                index = index.min(list.len());
+                if index == 0 {
+                    None
+                } else {
+                    index -= 1;
+                    list.data()
+                        .get(index)
+                        .map(|value| Ok(Output::Value(value.clone())))
+                }
+            })))
+        }
+        [Tuple(t)] => {
+            let tuple = t.clone();
+            let mut index = tuple.data().len();
+
+            Ok(Iterator(ValueIterator::make_external(move || {
+                if index == 0 {
+                    None
+                } else {
+                    index -= 1;
+                    Some(Ok(Output::Value(tuple.data()[index].clone())))
+                }
+            })))
+        }
+        _ => external_error!("iterator.reversed: Expected list, tuple, or range as argument"),
+    });
+
     result.add_fn("skip", |vm, args| match vm.get_args(args) {
         [iterable, Number(n)] if is_iterable(iterable) && *n >= 0.0 => {
             let mut iter = make_iterator(iterable).unwrap();
 
-            for _ in 0..*n as usize {
+            for _ in 0..usize::from(n) {
                 if let Some(Err(error)) = iter.next() {
                     return Err(error);
                 }
@@ -311,9 +609,74 @@ pub fn make_module() -> ValueMap {
         }
     });
 
+    result.add_fn("skip_while", |vm, args| match vm.get_args(args) {
+        [iterable, Function(f)] if is_iterable(iterable) => {
+            let mut iter = make_iterator(iterable).unwrap().map(collect_pair).fuse();
+            let f = f.clone();
+            let mut vm = vm.spawn_shared_vm();
+            let mut skipping = true;
+
+            Ok(Iterator(ValueIterator::make_external(move || {
+                if !skipping {
+                    return iter.next();
+                }
+
+                skipping = false;
+
+                for output in &mut iter {
+                    match output {
+                        Ok(Output::Value(value)) => {
+                            match vm.run_function(&f, std::slice::from_ref(&value)) {
+                                Ok(Bool(true)) => {}
+                                Ok(Bool(false)) => return Some(Ok(Output::Value(value))),
+                                Ok(unexpected) => {
+                                    return Some(external_error!(
+                                    "iterator.skip_while expects a Bool to be returned from the \
+                                     predicate, found '{}'",
+                                    value::type_as_string(&unexpected),
+                                ))
+                                }
+                                Err(error) => return Some(Err(error)),
+                            }
+                        }
+                        Err(error) => return Some(Err(error)),
+                        _ => unreachable!(),
+                    }
+                }
+
+                None
+            })))
+        }
+        _ => external_error!("iterator.skip_while: Expected iterable and function as arguments"),
+    });
+
+    result.add_fn("step", |vm, args| match vm.get_args(args) {
+        [iterable, Number(n)] if is_iterable(iterable) && *n >= 1.0 => {
+            let step_size = usize::from(n);
+            let mut iter = make_iterator(iterable).unwrap().fuse();
+            let mut first = true;
+
+            Ok(Iterator(ValueIterator::make_external(move || {
+                if first {
+                    first = false;
+                } else {
+                    for _ in 1..step_size {
+                        match iter.next() {
+                            Some(Ok(_)) => {}
+                            other => return other,
+                        }
+                    }
+                }
+
+                iter.next()
+            })))
+        }
+        _ => external_error!("iterator.step: Expected iterable and positive number as arguments"),
+    });
+
     result.add_fn("take", |vm, args| match vm.get_args(args) {
         [iterable, Number(n)] if is_iterable(iterable) && *n >= 0.0 => {
-            let mut iter = make_iterator(iterable).unwrap().take(*n as usize);
+            let mut iter = make_iterator(iterable).unwrap().take(usize::from(n));
 
             Ok(Iterator(ValueIterator::make_external(move || iter.next())))
         }
@@ -322,6 +685,43 @@ pub fn make_module() -> ValueMap {
         }
     });
 
+    result.add_fn("take_while", |vm, args| match vm.get_args(args) {
+        [iterable, Function(f)] if is_iterable(iterable) => {
+            let mut iter = make_iterator(iterable).unwrap().map(collect_pair);
+            let f = f.clone();
+            let mut vm = vm.spawn_shared_vm();
+            let mut finished = false;
+
+            Ok(Iterator(ValueIterator::make_external(move || {
+                if finished {
+                    return None;
+                }
+
+                match iter.next() {
+                    Some(Ok(Output::Value(value))) => {
+                        match vm.run_function(&f, std::slice::from_ref(&value)) {
+                            Ok(Bool(true)) => Some(Ok(Output::Value(value))),
+                            Ok(Bool(false)) => {
+                                finished = true;
+                                None
+                            }
+                            Ok(unexpected) => Some(external_error!(
+                                "iterator.take_while expects a Bool to be returned from the \
+                             predicate, found '{}'",
+                                value::type_as_string(&unexpected),
+                            )),
+                            Err(error) => Some(Err(error)),
+                        }
+                    }
+                    Some(Err(error)) => Some(Err(error)),
+                    Some(_) => unreachable!(),
+                    None => None,
+                }
+            })))
+        }
+        _ => external_error!("iterator.take_while: Expected iterable and function as arguments"),
+    });
+
     result.add_fn("to_list", |vm, args| match vm.get_args(args) {
         [iterable] if is_iterable(iterable) => {
             let mut iterator = make_iterator(iterable).unwrap();
@@ -329,7 +729,10 @@ pub fn make_module() -> ValueMap {
 
             loop {
                 match iterator.next().map(collect_pair) {
-                    Some(Ok(Output::Value(value))) => result.push(value),
+                    Some(Ok(Output::Value(value))) => {
+                        vm.check_container_size(result.len() + 1, 1)?;
+                        result.push(value);
+                    }
                     Some(Err(error)) => return Err(error),
                     Some(_) => unreachable!(),
                     None => break,
@@ -362,6 +765,8 @@ pub fn make_module() -> ValueMap {
                     Some(Err(error)) => return Err(error),
                     None => break,
                 }
+
+                vm.check_container_size(result.len(), 1)?;
             }
 
             Ok(Map(ValueMap::with_data(result)))
@@ -376,7 +781,10 @@ pub fn make_module() -> ValueMap {
 
             loop {
                 match iterator.next().map(collect_pair) {
-                    Some(Ok(Output::Value(value))) => result.push(value),
+                    Some(Ok(Output::Value(value))) => {
+                        vm.check_container_size(result.len() + 1, 1)?;
+                        result.push(value);
+                    }
                     Some(Err(error)) => return Err(error),
                     Some(_) => unreachable!(),
                     None => break,
@@ -388,6 +796,42 @@ pub fn make_module() -> ValueMap {
         _ => external_error!("iterator.to_tuple: Expected iterable as argument"),
     });
 
+    result.add_fn("windows", |vm, args| match vm.get_args(args) {
+        [iterable, Number(n)] if is_iterable(iterable) && *n >= 1.0 => {
+            let window_size = usize::from(n);
+            vm.check_container_size(window_size, 0)?;
+            let mut iter = make_iterator(iterable).unwrap().map(collect_pair).fuse();
+            let mut window = VecDeque::new();
+            let vm = vm.spawn_shared_vm();
+
+            Ok(Iterator(ValueIterator::make_external(move || {
+                if window.len() == window_size {
+                    window.pop_front();
+                }
+
+                while window.len() < window_size {
+                    match iter.next() {
+                        Some(Ok(Output::Value(value))) => window.push_back(value),
+                        Some(Err(error)) => return Some(Err(error)),
+                        Some(_) => unreachable!(),
+                        None => return None,
+                    }
+                }
+
+                if let Err(error) = vm.check_container_size(window_size, window_size) {
+                    return Some(Err(error));
+                }
+
+                Some(Ok(Output::Value(List(ValueList::with_data(
+                    window.iter().cloned().collect(),
+                )))))
+            })))
+        }
+        _ => {
+            external_error!("iterator.windows: Expected iterable and positive number as arguments")
+        }
+    });
+
     result.add_fn("zip", |vm, args| match vm.get_args(args) {
         [iterable_a, iterable_b] if is_iterable(iterable_a) && is_iterable(iterable_b) => {
             let iter_a = make_iterator(iterable_a).unwrap().map(collect_pair);
@@ -33,7 +33,7 @@ pub fn make_module() -> ValueMap {
             if *n < 0.0 {
                 return external_error!("list.get: Negative indices aren't allowed");
             }
-            let index = *n as usize;
+            let index = usize::from(n);
             match l.data().get(index) {
                 Some(value) => Ok(value.clone()),
                 None => Ok(Value::Empty),
@@ -47,10 +47,11 @@ pub fn make_module() -> ValueMap {
             if *n < 0.0 {
                 return external_error!("list.insert: Negative indices aren't allowed");
             }
-            let index = *n as usize;
+            let index = usize::from(n);
             if index > l.data().len() {
                 return external_error!("list.insert: Index out of bounds");
             }
+            vm.check_container_size(l.len() + 1, 1)?;
 
             l.data_mut().insert(index, value.clone());
             Ok(Value::Empty)
@@ -86,6 +87,7 @@ pub fn make_module() -> ValueMap {
 
     result.add_fn("push", |vm, args| match vm.get_args(args) {
         [List(l), value] => {
+            vm.check_container_size(l.len() + 1, 1)?;
             l.data_mut().push(value.clone());
             Ok(Value::Empty)
         }
@@ -97,7 +99,7 @@ pub fn make_module() -> ValueMap {
             if *n < 0.0 {
                 return external_error!("list.remove: Negative indices aren't allowed");
             }
-            let index = *n as usize;
+            let index = usize::from(n);
             if index >= l.data().len() {
                 return external_error!(
                     "list.remove: Index out of bounds - \
@@ -117,7 +119,9 @@ pub fn make_module() -> ValueMap {
             if *n < 0.0 {
                 return external_error!("list.resize: Negative sizes aren't allowed");
             }
-            l.data_mut().resize(*n as usize, value.clone());
+            let size = usize::from(n);
+            vm.check_container_size(size, size.saturating_sub(l.len()))?;
+            l.data_mut().resize(size, value.clone());
             Ok(Value::Empty)
         }
         _ => external_error!("list.resize: Expected list, number, and value as arguments"),
@@ -180,7 +184,7 @@ pub fn make_module() -> ValueMap {
     });
 
     result.add_fn("size", |vm, args| match vm.get_args(args) {
-        [List(l)] => Ok(Number(l.len() as f64)),
+        [List(l)] => Ok(Number(l.len().into())),
         _ => external_error!("list.size: Expected list as argument"),
     });
 
@@ -235,7 +239,10 @@ pub fn make_module() -> ValueMap {
                 return external_error!("list.with_size: Negative sizes aren't allowed");
             }
 
-            let result = smallvec::smallvec![value.clone(); *n as usize];
+            let size = usize::from(n);
+            vm.check_container_size(size, size)?;
+
+            let result = smallvec::smallvec![value.clone(); size];
             Ok(Value::List(ValueList::with_data(result)))
         }
         _ => external_error!("list.with_size: Expected number and value as arguments"),
@@ -32,11 +32,19 @@ pub fn make_module() -> ValueMap {
     });
 
     result.add_fn("insert", |vm, args| match vm.get_args(args) {
-        [Map(m), key] if value_is_immutable(key) => match m.data_mut().insert(key.clone(), Empty) {
-            Some(old_value) => Ok(old_value),
-            None => Ok(Empty),
-        },
+        [Map(m), key] if value_is_immutable(key) => {
+            if !m.data().contains_key(key) {
+                vm.check_container_size(m.len() + 1, 1)?;
+            }
+            match m.data_mut().insert(key.clone(), Empty) {
+                Some(old_value) => Ok(old_value),
+                None => Ok(Empty),
+            }
+        }
         [Map(m), key, value] if value_is_immutable(key) => {
+            if !m.data().contains_key(key) {
+                vm.check_container_size(m.len() + 1, 1)?;
+            }
             match m.data_mut().insert(key.clone(), value.clone()) {
                 Some(old_value) => Ok(old_value),
                 None => Ok(Empty),
@@ -99,7 +107,7 @@ pub fn make_module() -> ValueMap {
     });
 
     result.add_fn("size", |vm, args| match vm.get_args(args) {
-        [Map(m)] => Ok(Number(m.data().len() as f64)),
+        [Map(m)] => Ok(Number(m.data().len().into())),
         [other, ..] => external_error!(
             "map.size: Expected map as argument, found '{}'",
             type_as_string(other),
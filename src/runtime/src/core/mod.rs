@@ -1,5 +1,6 @@
 //! The core library for the Koto language
 
+pub mod coroutine;
 pub mod io;
 pub mod iterator;
 pub mod koto;
@@ -18,6 +19,7 @@ use crate::ValueMap;
 
 #[derive(Clone)]
 pub struct CoreLib {
+    pub coroutine: ValueMap,
     pub io: ValueMap,
     pub iterator: ValueMap,
     pub koto: ValueMap,
@@ -36,6 +38,7 @@ pub struct CoreLib {
 impl Default for CoreLib {
     fn default() -> Self {
         Self {
+            coroutine: coroutine::make_module(),
             io: io::make_module(),
             iterator: iterator::make_module(),
             koto: koto::make_module(),
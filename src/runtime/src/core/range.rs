@@ -1,4 +1,4 @@
-use crate::{external_error, Value, ValueIterator, ValueMap};
+use crate::{external_error, Value, ValueIterator, ValueMap, ValueNumber};
 
 pub fn make_module() -> ValueMap {
     use Value::*;
@@ -6,12 +6,14 @@ pub fn make_module() -> ValueMap {
     let mut result = ValueMap::new();
 
     result.add_fn("contains", |vm, args| match vm.get_args(args) {
-        [Range(r), Number(n)] => Ok(Bool(*n >= r.start as f64 && n.ceil() < r.end as f64)),
+        [Range(r), Number(n)] => Ok(Bool(
+            *n >= ValueNumber::from(r.start) && n.ceil() < ValueNumber::from(r.end),
+        )),
         _ => external_error!("range.contains: Expected range and number as arguments"),
     });
 
     result.add_fn("end", |vm, args| match vm.get_args(args) {
-        [Range(r)] => Ok(Number(r.end as f64)),
+        [Range(r)] => Ok(Number(r.end.into())),
         _ => external_error!("range.end: Expected range as argument"),
     });
 
@@ -21,12 +23,12 @@ pub fn make_module() -> ValueMap {
     });
 
     result.add_fn("size", |vm, args| match vm.get_args(args) {
-        [Range(r)] => Ok(Number((r.end - r.start) as f64)),
+        [Range(r)] => Ok(Number((r.end - r.start).into())),
         _ => external_error!("range.size: Expected range as argument"),
     });
 
     result.add_fn("start", |vm, args| match vm.get_args(args) {
-        [Range(r)] => Ok(Number(r.start as f64)),
+        [Range(r)] => Ok(Number(r.start.into())),
         _ => external_error!("range.start: Expected range as argument"),
     });
 
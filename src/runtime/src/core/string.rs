@@ -4,7 +4,7 @@ use {
     crate::{
         external_error,
         value_iterator::{ValueIterator, ValueIteratorOutput},
-        Value, ValueMap,
+        Error, Value, ValueMap,
     },
     unicode_segmentation::UnicodeSegmentation,
 };
@@ -55,10 +55,17 @@ pub fn make_module() -> ValueMap {
 
     result.add_fn("format", |vm, args| match vm.get_args(args) {
         [result @ Str(_)] => Ok(result.clone()),
-        [Str(format), format_args @ ..] => match format::format_string(format, format_args) {
-            Ok(result) => Ok(Str(result.into())),
-            Err(error) => external_error!("string.format: {}", error),
-        },
+        [Str(format), format_args @ ..] => {
+            let format = format.clone();
+            let format_args = format_args.to_vec();
+            match format::format_string(vm, &format, &format_args) {
+                Ok(result) => Ok(Str(result.into())),
+                Err(Error::ErrorWithoutLocation { message }) => {
+                    external_error!("string.format: {}", message)
+                }
+                Err(error) => Err(error),
+            }
+        }
         _ => external_error!("string.format: Expected a string as first argument"),
     });
 
@@ -97,23 +104,30 @@ pub fn make_module() -> ValueMap {
     result.add_fn("print", |vm, args| {
         match vm.get_args(args) {
             [Str(s)] => println!("{}", s.as_str()),
-            [Str(format), format_args @ ..] => match format::format_string(format, format_args) {
-                Ok(result) => println!("{}", result.as_str()),
-                Err(error) => return external_error!("string.print: {}", error),
-            },
+            [Str(format), format_args @ ..] => {
+                let format = format.clone();
+                let format_args = format_args.to_vec();
+                match format::format_string(vm, &format, &format_args) {
+                    Ok(result) => println!("{}", result.as_str()),
+                    Err(Error::ErrorWithoutLocation { message }) => {
+                        return external_error!("string.print: {}", message)
+                    }
+                    Err(error) => return Err(error),
+                }
+            }
             _ => return external_error!("string.print: Expected a string as first argument"),
         }
         Ok(Empty)
     });
 
     result.add_fn("size", |vm, args| match vm.get_args(args) {
-        [Str(s)] => Ok(Number(s.graphemes(true).count() as f64)),
+        [Str(s)] => Ok(Number(s.graphemes(true).count().into())),
         _ => external_error!("string.size: Expected string as argument"),
     });
 
     result.add_fn("slice", |vm, args| match vm.get_args(args) {
         [Str(input), Number(from)] => {
-            let bounds = (*from as usize)..input.len();
+            let bounds = (usize::from(from))..input.len();
             let result = match input.with_bounds(bounds) {
                 Ok(result) => Str(result),
                 Err(_) => Empty,
@@ -121,7 +135,7 @@ pub fn make_module() -> ValueMap {
             Ok(result)
         }
         [Str(input), Number(from), Number(to)] => {
-            let bounds = (*from as usize)..(*to as usize);
+            let bounds = (usize::from(from))..(usize::from(to));
             let result = match input.with_bounds(bounds) {
                 Ok(result) => Str(result),
                 Err(_) => Empty,
@@ -166,9 +180,12 @@ pub fn make_module() -> ValueMap {
     });
 
     result.add_fn("to_number", |vm, args| match vm.get_args(args) {
-        [Str(s)] => match s.parse::<f64>() {
-            Ok(n) => Ok(Number(n)),
-            Err(_) => external_error!("string.to_number: Failed to convert '{}'", s),
+        [Str(s)] => match s.parse::<i64>() {
+            Ok(n) => Ok(Number(n.into())),
+            Err(_) => match s.parse::<f64>() {
+                Ok(n) => Ok(Number(n.into())),
+                Err(_) => external_error!("string.to_number: Failed to convert '{}'", s),
+            },
         },
         _ => external_error!("string.to_number: Expected string as argument"),
     });
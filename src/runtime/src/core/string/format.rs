@@ -1,5 +1,5 @@
 use {
-    crate::Value,
+    crate::{external_error, Error, Value, Vm},
     koto_lexer::{is_id_continue, is_id_start},
 };
 
@@ -147,35 +147,50 @@ impl<'a> Iterator for FormatLexer<'a> {
     }
 }
 
-pub fn format_string(format_string: &str, format_args: &[Value]) -> Result<String, String> {
+/// Formats the string with the provided arguments
+///
+/// Errors from the format string are returned as external errors without a prefix,
+/// while errors that occur when calling a value's `@display` function are passed through
+/// unchanged, so that interruptions and thrown values are preserved.
+pub fn format_string(
+    vm: &mut Vm,
+    format_string: &str,
+    format_args: &[Value],
+) -> Result<String, Error> {
     let mut arg_iter = format_args.iter();
     let mut result = String::with_capacity(format_string.len());
 
+    let mut append_value = |result: &mut String, value: &Value| {
+        result.push_str(&vm.value_to_string(value)?);
+        Ok(())
+    };
+
     for token in FormatLexer::new(&format_string) {
         match token {
             FormatToken::String(s) => result.push_str(s),
             FormatToken::Placeholder => match arg_iter.next() {
-                Some(arg) => result.push_str(&arg.to_string()),
-                None => return Err("Not enough arguments for format string".to_string()),
+                Some(arg) => append_value(&mut result, arg)?,
+                None => return external_error!("Not enough arguments for format string"),
             },
             FormatToken::Positional(n) => match format_args.get(n as usize) {
-                Some(arg) => result.push_str(&arg.to_string()),
-                None => return Err(format!("Missing argument for index {}", n)),
+                Some(arg) => append_value(&mut result, arg)?,
+                None => return external_error!("Missing argument for index {}", n),
             },
             FormatToken::Identifier(id) => match format_args.first() {
                 Some(Value::Map(map)) => {
                     // TODO pass in runtime's string cache
-                    match map.data().get_with_string(id) {
-                        Some(value) => result.push_str(&value.to_string()),
-                        None => return Err(format!("Key '{}' not found in map", id)),
+                    let value = map.data().get_with_string(id).cloned();
+                    match value {
+                        Some(value) => append_value(&mut result, &value)?,
+                        None => return external_error!("Key '{}' not found in map", id),
                     }
                 }
                 Some(other) => {
-                    return Err(format!("Expected map as first argument, found {}", other))
+                    return external_error!("Expected map as first argument, found {}", other)
                 }
-                None => return Err(String::from("Expected map as first argument")),
+                None => return external_error!("Expected map as first argument"),
             },
-            FormatToken::Error => return Err("Error while parsing format string".to_string()),
+            FormatToken::Error => return external_error!("Error while parsing format string"),
         }
     }
 
@@ -294,7 +309,7 @@ mod tests {
         use super::*;
 
         fn check_format_output(format: &str, args: &[Value], expected: &str) {
-            match format_string(format, args) {
+            match format_string(&mut Vm::default(), format, args) {
                 Ok(result) => assert_eq!(result, expected),
                 Err(error) => panic!(error),
             }
@@ -302,10 +317,10 @@ mod tests {
 
         #[test]
         fn positional_placeholders() {
-            check_format_output("{} foo {0}", &[Value::Number(1.0)], "1 foo 1");
+            check_format_output("{} foo {0}", &[Value::Number(1.into())], "1 foo 1");
             check_format_output(
                 "{1} - {0} {} - {}",
-                &[Value::Number(2.0), Value::Empty],
+                &[Value::Number(2.into()), Value::Empty],
                 "() - 2 2 - ()",
             );
         }
@@ -313,8 +328,8 @@ mod tests {
         #[test]
         fn identifier_placeholders() {
             let mut map_data = ValueHashMap::new();
-            map_data.insert("x".into(), Value::Number(42.0));
-            map_data.insert("y".into(), Value::Number(-1.0));
+            map_data.insert("x".into(), Value::Number(42.into()));
+            map_data.insert("y".into(), Value::Number((-1).into()));
             let map = Value::Map(ValueMap::with_data(map_data));
 
             check_format_output("{x} - {y}", &[map], "42 - -1");
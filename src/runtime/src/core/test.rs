@@ -48,7 +48,7 @@ pub fn make_module() -> ValueMap {
 
     result.add_fn("assert_near", |vm, args| match vm.get_args(args) {
         [Number(a), Number(b), Number(allowed_diff)] => {
-            if f64_near(*a, *b, *allowed_diff) {
+            if f64_near(a.into(), b.into(), allowed_diff.into()) {
                 Ok(Empty)
             } else {
                 external_error!(
@@ -60,7 +60,7 @@ pub fn make_module() -> ValueMap {
             }
         }
         [Num2(a), Num2(b), Number(allowed_diff)] => {
-            if f64_near(a.0, b.0, *allowed_diff) && f64_near(a.1, b.1, *allowed_diff) {
+            if f64_near(a.0, b.0, allowed_diff.into()) && f64_near(a.1, b.1, allowed_diff.into()) {
                 Ok(Empty)
             } else {
                 external_error!(
@@ -72,7 +72,7 @@ pub fn make_module() -> ValueMap {
             }
         }
         [Num4(a), Num4(b), Number(allowed_diff)] => {
-            let allowed_diff = *allowed_diff as f32;
+            let allowed_diff = f32::from(allowed_diff);
             if f32_near(a.0, b.0, allowed_diff)
                 && f32_near(a.1, b.1, allowed_diff)
                 && f32_near(a.2, b.2, allowed_diff)
@@ -0,0 +1,68 @@
+use crate::{external_error, value_iterator::ValueIterator, Error, Value};
+
+/// A suspended function that can be resumed with a value, see [Vm::make_coroutine]
+///
+/// Each `yield` in the function suspends the coroutine until it's resumed, with the `yield`
+/// expression evaluating to the value that the coroutine was resumed with.
+///
+/// Coroutines share the iterator representation used for generators, so calling a generator
+/// function in a script produces a value that can be used as a coroutine by the host.
+///
+/// [Vm::make_coroutine]: crate::Vm::make_coroutine
+#[derive(Clone, Debug)]
+pub struct Coroutine(ValueIterator);
+
+impl Coroutine {
+    pub(crate) fn new(iterator: ValueIterator) -> Self {
+        Self(iterator)
+    }
+
+    /// Returns a coroutine if the value is a generator, otherwise `None`
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Iterator(iterator) if iterator.with_generator_vm(|_| ()).is_some() => {
+                Some(Self(iterator.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Resumes the coroutine, running it until its next `yield`
+    ///
+    /// The yielded value is returned, or `None` if the coroutine has finished.
+    pub fn resume(&self, value: Value) -> Result<Option<Value>, Error> {
+        let result = self.0.with_generator_vm(|vm| {
+            if vm.is_finished() {
+                return Ok(None);
+            }
+
+            let result = vm.resume(value)?;
+
+            if vm.is_finished() {
+                Ok(None)
+            } else {
+                Ok(Some(result))
+            }
+        });
+
+        match result {
+            Some(result) => result,
+            None => external_error!("The coroutine is already running"),
+        }
+    }
+
+    /// Returns true if the coroutine has finished running
+    ///
+    /// A coroutine that's currently running isn't considered to be finished.
+    pub fn is_finished(&self) -> bool {
+        self.0
+            .with_generator_vm(|vm| vm.is_finished())
+            .unwrap_or(false)
+    }
+}
+
+impl From<Coroutine> for Value {
+    fn from(coroutine: Coroutine) -> Self {
+        Value::Iterator(coroutine.0)
+    }
+}
@@ -0,0 +1,188 @@
+use {
+    crate::Value,
+    koto_bytecode::Chunk,
+    koto_parser::Span,
+    std::{fmt, path::PathBuf, sync::Arc},
+};
+
+/// A location in a script where the [Debugger] should pause execution
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakpoint {
+    /// The path of the script containing the breakpoint
+    ///
+    /// If no path is provided then the breakpoint will match any script.
+    pub path: Option<PathBuf>,
+    /// The line of the breakpoint, starting from 1
+    pub line: u32,
+}
+
+impl Breakpoint {
+    pub fn new(path: Option<PathBuf>, line: u32) -> Self {
+        Self { path, line }
+    }
+
+    pub(crate) fn matches(&self, chunk: &Chunk, line: u32) -> bool {
+        self.line == line
+            && match &self.path {
+                Some(path) => chunk.source_path.as_ref() == Some(path),
+                None => true,
+            }
+    }
+}
+
+/// The reason for the VM pausing execution
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PauseReason {
+    Breakpoint,
+    Step,
+}
+
+/// A snapshot of a frame on the VM's call stack
+#[derive(Clone, Debug)]
+pub struct DebugFrame {
+    /// The chunk that the frame is executing
+    pub chunk: Arc<Chunk>,
+    /// The instruction pointer of the frame
+    ///
+    /// For frames other than the innermost frame this is the ip of the call into the next frame.
+    pub ip: usize,
+    /// The source span associated with the frame's instruction pointer
+    pub span: Option<Span>,
+    /// The values contained in the frame's registers
+    pub registers: Vec<Value>,
+    /// The values that have been captured by the frame's function
+    pub captures: Vec<Value>,
+}
+
+impl DebugFrame {
+    pub fn source_path(&self) -> Option<&PathBuf> {
+        self.chunk.source_path.as_ref()
+    }
+
+    pub fn line(&self) -> Option<u32> {
+        self.span.map(|span| span.start.line)
+    }
+
+    /// The name of the function that was being executed in the frame, if known
+    pub fn function_name(&self) -> Option<&str> {
+        self.chunk.function_name(self.ip)
+    }
+}
+
+/// The state of the VM when execution has been paused by the [Debugger]
+#[derive(Clone, Debug)]
+pub struct DebugPause {
+    pub reason: PauseReason,
+    /// The VM's call stack, with the innermost frame first
+    pub call_stack: Vec<DebugFrame>,
+}
+
+/// The command returned by a [Debugger]'s handler, determining how execution should resume
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugCommand {
+    /// Continue until the next breakpoint
+    Continue,
+    /// Pause at the next line, following any function calls
+    StepIn,
+    /// Pause at the next line in the current frame, or in a calling frame
+    StepOver,
+    /// Pause at the next line after the current frame has returned
+    StepOut,
+    /// Stop execution with an error
+    Stop,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum StepMode {
+    In,
+    Over,
+    Out,
+}
+
+/// The function that's called when the [Debugger] pauses execution
+///
+/// The VM's breakpoints are provided so that they can be modified while execution is paused.
+pub type DebugHandler = dyn FnMut(&DebugPause, &mut Vec<Breakpoint>) -> DebugCommand + Send;
+
+/// A step debugger that can be attached to a [Vm](crate::Vm)
+///
+/// Execution is paused when a breakpoint is reached, or when stepping through lines, and the
+/// debugger's handler is then called to inspect the VM's state and decide how to continue.
+pub struct Debugger {
+    pub breakpoints: Vec<Breakpoint>,
+    pub(crate) step: Option<(StepMode, usize)>, // step mode, call stack depth
+    handler: Box<DebugHandler>,
+}
+
+impl Debugger {
+    pub fn new(
+        handler: impl FnMut(&DebugPause, &mut Vec<Breakpoint>) -> DebugCommand + Send + 'static,
+    ) -> Self {
+        Self {
+            breakpoints: vec![],
+            step: None,
+            handler: Box::new(handler),
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Removes a breakpoint, returning true if the breakpoint was found
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints.retain(|b| b != breakpoint);
+        self.breakpoints.len() != count
+    }
+
+    /// Requests that execution should be paused at the next line
+    pub fn pause(&mut self) {
+        self.step = Some((StepMode::In, 0));
+    }
+
+    pub(crate) fn should_pause(
+        &self,
+        chunk: &Chunk,
+        line: u32,
+        depth: usize,
+    ) -> Option<PauseReason> {
+        let step = match self.step {
+            Some((StepMode::In, _)) => true,
+            Some((StepMode::Over, step_depth)) => depth <= step_depth,
+            Some((StepMode::Out, step_depth)) => depth < step_depth,
+            None => false,
+        };
+
+        if step {
+            Some(PauseReason::Step)
+        } else if self.breakpoints.iter().any(|b| b.matches(chunk, line)) {
+            Some(PauseReason::Breakpoint)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn run_handler(&mut self, pause: &DebugPause, depth: usize) -> DebugCommand {
+        let command = (self.handler)(pause, &mut self.breakpoints);
+
+        self.step = match command {
+            DebugCommand::StepIn => Some((StepMode::In, depth)),
+            DebugCommand::StepOver => Some((StepMode::Over, depth)),
+            DebugCommand::StepOut => Some((StepMode::Out, depth)),
+            DebugCommand::Continue | DebugCommand::Stop => None,
+        };
+
+        command
+    }
+}
+
+impl fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Debugger")
+            .field("breakpoints", &self.breakpoints)
+            .finish()
+    }
+}
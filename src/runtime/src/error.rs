@@ -1,9 +1,64 @@
 use {
-    crate::{LoaderError, Value},
+    crate::{InterruptReason, LoaderError, Value},
     koto_bytecode::Chunk,
-    std::{fmt, sync::Arc},
+    koto_parser::Span,
+    std::{fmt, path::PathBuf, sync::Arc},
 };
 
+/// A frame from the VM's call stack at the point where an error occurred
+#[derive(Clone, Debug)]
+pub struct BacktraceFrame {
+    /// The chunk that was being executed in the frame
+    pub chunk: Arc<Chunk>,
+    /// The instruction that was being executed in the frame
+    ///
+    /// For frames other than the innermost frame this is the ip of the call into the next frame.
+    pub instruction: usize,
+}
+
+impl BacktraceFrame {
+    pub fn source_path(&self) -> Option<&PathBuf> {
+        self.chunk.source_path.as_ref()
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.chunk.debug_info.get_source_span(self.instruction)
+    }
+
+    /// The name of the function that was being executed in the frame, if known
+    pub fn function_name(&self) -> Option<&str> {
+        self.chunk.function_name(self.instruction)
+    }
+}
+
+impl fmt::Display for BacktraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.function_name() {
+            Some(name) => f.write_str(name)?,
+            None if self
+                .chunk
+                .debug_info
+                .get_function(self.instruction)
+                .is_some() =>
+            {
+                f.write_str("<anonymous>")?
+            }
+            None => f.write_str("<module>")?,
+        }
+
+        f.write_str(" - ")?;
+
+        if let Some(path) = self.source_path() {
+            write!(f, "{}:", path.display())?;
+        }
+
+        match self.span() {
+            Some(span) => write!(f, "{}:{}", span.start.line, span.start.column),
+            None => write!(f, "instruction {}", self.instruction),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Error {
     VmError {
@@ -11,6 +66,8 @@ pub enum Error {
         chunk: Arc<Chunk>,
         instruction: usize,
         extra_error: Option<Box<Error>>,
+        /// The VM's call stack when the error occurred, with the innermost frame first
+        backtrace: Vec<BacktraceFrame>,
     },
     LoaderError(LoaderError),
     TestError {
@@ -20,6 +77,75 @@ pub enum Error {
     ErrorWithoutLocation {
         message: String,
     },
+    /// A value was thrown by a script with a `throw` expression
+    ///
+    /// The thrown value is passed unchanged to `catch` blocks.
+    ThrownValue {
+        value: Value,
+        chunk: Arc<Chunk>,
+        instruction: usize,
+        /// The VM's call stack when the value was thrown, with the innermost frame first
+        backtrace: Vec<BacktraceFrame>,
+    },
+    /// Execution was stopped by the VM's execution limits, or with an interrupt handle
+    ///
+    /// Interruptions can't be caught by `try` expressions in scripts.
+    Interrupted {
+        reason: InterruptReason,
+        chunk: Arc<Chunk>,
+        instruction: usize,
+    },
+}
+
+impl Error {
+    /// Returns true if the error, or an error that it contains, is an interruption
+    pub fn is_interruption(&self) -> bool {
+        match self {
+            Error::Interrupted { .. } => true,
+            Error::VmError {
+                extra_error: Some(error),
+                ..
+            } => error.is_interruption(),
+            Error::TestError { error, .. } => error.is_interruption(),
+            _ => false,
+        }
+    }
+
+    /// Returns the value that was thrown by a script, if the error was caused by a `throw`
+    pub fn thrown_value(&self) -> Option<&Value> {
+        match self {
+            Error::ThrownValue { value, .. } => Some(value),
+            Error::VmError {
+                extra_error: Some(error),
+                ..
+            } => error.thrown_value(),
+            Error::TestError { error, .. } => error.thrown_value(),
+            _ => None,
+        }
+    }
+
+    /// Returns the VM's call stack at the point where the error occurred
+    ///
+    /// The innermost frame is first, and frames from nested VMs are included,
+    /// e.g. when an error occurs in a function that was called from an imported module.
+    pub fn backtrace(&self) -> &[BacktraceFrame] {
+        match self {
+            Error::VmError { backtrace, .. } | Error::ThrownValue { backtrace, .. } => backtrace,
+            Error::TestError { error, .. } => error.backtrace(),
+            _ => &[],
+        }
+    }
+
+    // Adds frames to the end of the error's backtrace, called while the call stack is unwound
+    pub(crate) fn extend_backtrace(&mut self, frames: Vec<BacktraceFrame>) {
+        match self {
+            Error::VmError { backtrace, .. } | Error::ThrownValue { backtrace, .. } => {
+                backtrace.extend(frames)
+            }
+            Error::TestError { error, .. } => error.extend_backtrace(frames),
+            _ => {}
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -36,6 +162,8 @@ impl fmt::Display for Error {
             Error::LoaderError(e) => f.write_str(&e.to_string()),
             Error::TestError { message, error } => write!(f, "{}: {}", message, error),
             Error::ErrorWithoutLocation { message } => f.write_str(message),
+            Error::ThrownValue { value, .. } => write!(f, "{}", value),
+            Error::Interrupted { reason, .. } => write!(f, "Execution stopped: {}", reason),
         }
     }
 }
@@ -50,6 +178,7 @@ macro_rules! make_vm_error {
             chunk: $chunk,
             instruction: $ip,
             extra_error: None,
+            backtrace: Vec::new(),
         };
         #[cfg(panic_on_runtime_error)]
         {
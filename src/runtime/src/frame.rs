@@ -18,6 +18,8 @@ pub(crate) struct Frame {
     // True if the frame should prevent errors from being caught further down the stack,
     // e.g. when an external function is calling back into the VM with a functor
     pub catch_barrier: bool,
+    // The last source line and ip that were reached in this frame while a debugger is attached
+    pub debug_position: Option<(u32, usize)>,
     // The captures that are available in this frame
     captures: Option<ValueList>,
 }
@@ -31,6 +33,14 @@ impl Frame {
             return_register_and_ip: None,
             catch_stack: vec![],
             catch_barrier: false,
+            debug_position: None,
+        }
+    }
+
+    pub fn captures(&self) -> Vec<Value> {
+        match &self.captures {
+            Some(captures) => captures.data().to_vec(),
+            None => vec![],
         }
     }
 
@@ -1,32 +1,47 @@
 //! Contains the runtime and core library for the Koto language
 
+mod capabilities;
 pub mod core;
+mod coroutine;
+mod debugger;
 mod error;
 mod external;
 mod frame;
+mod limits;
+pub mod meta_keys;
 pub mod num2;
 pub mod num4;
 pub mod value;
+mod value_conversion;
 mod value_iterator;
 mod value_list;
 mod value_map;
+mod value_number;
 mod value_string;
 mod value_tuple;
 mod vm;
 
 pub use {
+    capabilities::{Capabilities, ImportPolicy},
+    coroutine::Coroutine,
+    debugger::{
+        Breakpoint, DebugCommand, DebugFrame, DebugHandler, DebugPause, Debugger, PauseReason,
+    },
     error::*,
     external::{is_external_instance, visit_external_value, ExternalFunction, ExternalValue},
     koto_bytecode::{CompilerError, Loader, LoaderError},
     koto_parser::ParserError,
+    limits::{InterruptHandle, InterruptReason, MemoryLimits},
     num2::Num2,
     num4::Num4,
     value::{
         make_external_value, type_as_string, value_is_immutable, RuntimeFunction, Value, ValueRef,
     },
+    value_conversion::{FromValue, IntoRuntimeResult, IntoValue, TypedFunction},
     value_iterator::{IntRange, ValueIterator, ValueIteratorOutput},
     value_list::{ValueList, ValueVec},
     value_map::{ValueHashMap, ValueMap, ValueMapKey},
+    value_number::ValueNumber,
     value_string::ValueString,
     value_tuple::ValueTuple,
     vm::{Vm, VmContext},
@@ -0,0 +1,183 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+/// The reason that a VM's execution was interrupted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterruptReason {
+    /// The VM's instruction limit was reached, see [Vm::set_instruction_limit](crate::Vm)
+    InstructionLimit,
+    /// The VM's deadline passed, see [Vm::set_deadline](crate::Vm)
+    Deadline,
+    /// Execution was interrupted with an [InterruptHandle]
+    Interrupted,
+}
+
+impl fmt::Display for InterruptReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use InterruptReason::*;
+        match self {
+            InstructionLimit => f.write_str("instruction limit reached"),
+            Deadline => f.write_str("deadline exceeded"),
+            Interrupted => f.write_str("interrupted"),
+        }
+    }
+}
+
+// The number of instructions that are executed between checks of the deadline
+//
+// Checking the time is relatively expensive, so it's only checked periodically.
+pub(crate) const DEADLINE_CHECK_INTERVAL: u32 = 1024;
+
+// Limits on a VM's execution, shared with the VMs that are spawned from it
+#[derive(Default)]
+pub(crate) struct ExecutionLimits {
+    interrupted: AtomicBool,
+    has_instruction_limit: AtomicBool,
+    remaining_instructions: AtomicU64,
+    deadline: Mutex<Option<Instant>>,
+    allocated_container_size: AtomicUsize,
+}
+
+impl ExecutionLimits {
+    pub fn set_instruction_limit(&self, limit: Option<u64>) {
+        self.remaining_instructions
+            .store(limit.unwrap_or(0), Ordering::Relaxed);
+        self.has_instruction_limit
+            .store(limit.is_some(), Ordering::Relaxed);
+    }
+
+    pub fn remaining_instructions(&self) -> Option<u64> {
+        if self.has_instruction_limit.load(Ordering::Relaxed) {
+            Some(self.remaining_instructions.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_deadline(&self, deadline: Option<Instant>) {
+        *self.deadline.lock().unwrap() = deadline;
+    }
+
+    pub fn allocated_container_size(&self) -> usize {
+        self.allocated_container_size.load(Ordering::Relaxed)
+    }
+
+    pub fn reset_allocated_container_size(&self) {
+        self.allocated_container_size.store(0, Ordering::Relaxed);
+    }
+
+    // Adds to the total size of allocated containers
+    //
+    // If the new total would exceed the limit then the total is left unchanged,
+    // and the total that would have been reached is returned as an error.
+    pub fn allocate_container_size(&self, added: usize, limit: usize) -> Result<(), usize> {
+        let mut exceeded_total = usize::MAX;
+        self.allocated_container_size
+            .fetch_update(
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+                |allocated| match allocated.checked_add(added) {
+                    Some(total) if total <= limit => Some(total),
+                    Some(total) => {
+                        exceeded_total = total;
+                        None
+                    }
+                    None => None,
+                },
+            )
+            .map(|_| ())
+            .map_err(|_| exceeded_total)
+    }
+
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+
+    // Called before each instruction is executed
+    //
+    // An interruption is only reported once, while the instruction limit and deadline continue
+    // to stop execution until they're reset.
+    pub fn check(&self, check_deadline: bool) -> Option<InterruptReason> {
+        if self.interrupted.load(Ordering::Relaxed) {
+            self.interrupted.store(false, Ordering::Relaxed);
+            return Some(InterruptReason::Interrupted);
+        }
+
+        // The limits can be shared between VMs running on different threads, so the remaining
+        // instructions are decremented atomically, without wrapping past zero.
+        if self.has_instruction_limit.load(Ordering::Relaxed)
+            && self
+                .remaining_instructions
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |remaining| {
+                    remaining.checked_sub(1)
+                })
+                .is_err()
+        {
+            return Some(InterruptReason::InstructionLimit);
+        }
+
+        if check_deadline {
+            if let Some(deadline) = *self.deadline.lock().unwrap() {
+                if Instant::now() >= deadline {
+                    return Some(InterruptReason::Deadline);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// A handle that allows a VM to be interrupted from another thread
+///
+/// Interrupting the VM causes it to stop with an [Error::Interrupted](crate::Error) error,
+/// which can't be caught by the script that's being run.
+///
+/// If the VM isn't running when it's interrupted, then the next script or function that it runs
+/// will be interrupted.
+#[derive(Clone)]
+pub struct InterruptHandle {
+    limits: Arc<ExecutionLimits>,
+}
+
+impl InterruptHandle {
+    pub(crate) fn new(limits: Arc<ExecutionLimits>) -> Self {
+        Self { limits }
+    }
+
+    /// Interrupts the VM
+    pub fn interrupt(&self) {
+        self.limits.interrupt();
+    }
+}
+
+/// Limits on the memory that can be used by a VM, see [Vm::set_memory_limits](crate::Vm)
+///
+/// When a limit is exceeded, a runtime error is thrown, which can be caught by the script.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryLimits {
+    /// The maximum number of values that can be held in a VM's registers
+    pub max_value_stack_size: Option<usize>,
+    /// The maximum number of nested function calls
+    pub max_call_stack_depth: Option<usize>,
+    /// The maximum size of a single container
+    ///
+    /// Lists, Maps, and Tuples are measured by their number of elements,
+    /// and Strings are measured by their size in bytes.
+    pub max_container_size: Option<usize>,
+    /// The maximum total size of the containers that are allocated by the VM
+    ///
+    /// Each time a container is created or grown, the added size is counted against the limit,
+    /// measured in the same way as for `max_container_size`. The total is shared with VMs that
+    /// are spawned from the VM, e.g. for threads and generators.
+    ///
+    /// The total is cumulative, so memory that's released by the script isn't returned to the
+    /// budget. The total is reset each time the VM's memory limits are set.
+    pub max_total_container_size: Option<usize>,
+}
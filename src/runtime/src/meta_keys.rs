@@ -0,0 +1,49 @@
+//! Keys that maps can use to overload operators and other built-in behaviour
+//!
+//! e.g.
+//! ```koto
+//! make_vec2 = |x, y|
+//!   x: x
+//!   y: y
+//!   "@+": |self, other| make_vec2 self.x + other.x, self.y + other.y
+//!   "@display": |self| "vec2({}, {})".format self.x, self.y
+//! ```
+//!
+//! Binary operators are called with `self` (the map on the left hand side of the operation)
+//! and the right hand side value.
+//! `@negate` and `@display` are called with `self` as their only argument.
+
+pub const ADD: &str = "@+";
+pub const SUBTRACT: &str = "@-";
+pub const MULTIPLY: &str = "@*";
+pub const DIVIDE: &str = "@/";
+pub const MODULO: &str = "@%";
+pub const LESS: &str = "@<";
+pub const LESS_OR_EQUAL: &str = "@<=";
+pub const GREATER: &str = "@>";
+pub const GREATER_OR_EQUAL: &str = "@>=";
+/// If `@!=` isn't defined then the result of `@==` will be negated
+pub const EQUAL: &str = "@==";
+pub const NOT_EQUAL: &str = "@!=";
+pub const NEGATE: &str = "@negate";
+/// Used when a map is converted to a string, should return a String
+pub const DISPLAY: &str = "@display";
+
+/// Returns true if the key is one of the meta keys
+pub fn is_meta_key(key: &str) -> bool {
+    matches!(
+        key,
+        ADD | SUBTRACT
+            | MULTIPLY
+            | DIVIDE
+            | MODULO
+            | LESS
+            | LESS_OR_EQUAL
+            | GREATER
+            | GREATER_OR_EQUAL
+            | EQUAL
+            | NOT_EQUAL
+            | NEGATE
+            | DISPLAY
+    )
+}
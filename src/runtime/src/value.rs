@@ -1,7 +1,7 @@
 use {
     crate::{
         num2, num4, ExternalFunction, ExternalValue, IntRange, ValueHashMap, ValueIterator,
-        ValueList, ValueMap, ValueString, ValueTuple, ValueVec,
+        ValueList, ValueMap, ValueNumber, ValueString, ValueTuple, ValueVec,
     },
     koto_bytecode::Chunk,
     std::{
@@ -17,7 +17,7 @@ use {
 pub enum Value {
     Empty,
     Bool(bool),
-    Number(f64),
+    Number(ValueNumber),
     Num2(num2::Num2),
     Num4(num4::Num4),
     Range(IntRange),
@@ -40,7 +40,7 @@ pub enum Value {
 pub enum ValueRef<'a> {
     Empty,
     Bool(&'a bool),
-    Number(&'a f64),
+    Number(&'a ValueNumber),
     Num2(&'a num2::Num2),
     Num4(&'a num4::Num4),
     Range(&'a IntRange),
@@ -218,7 +218,7 @@ impl<'a> Hash for ValueRef<'a> {
         match self {
             Empty | ExternalDataId => {}
             Bool(b) => b.hash(state),
-            Number(n) => state.write_u64(n.to_bits()),
+            Number(n) => n.hash(state),
             Num2(n) => n.hash(state),
             Num4(n) => n.hash(state),
             Str(s) => s.hash(state),
@@ -243,6 +243,24 @@ impl From<bool> for Value {
     }
 }
 
+impl From<ValueNumber> for Value {
+    fn from(value: ValueNumber) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Number(value.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Number(value.into())
+    }
+}
+
 impl From<&str> for Value {
     fn from(value: &str) -> Self {
         Self::Str(value.into())
@@ -354,5 +372,6 @@ pub fn value_is_immutable(value: &Value) -> bool {
 
     matches!(
         value,
-        Empty | ExternalDataId | Bool(_) | Number(_) | Num2(_) | Num4(_) | Range(_) | Str(_))
+        Empty | ExternalDataId | Bool(_) | Number(_) | Num2(_) | Num4(_) | Range(_) | Str(_)
+    )
 }
@@ -0,0 +1,579 @@
+use {
+    crate::{
+        external_error, num2, num4, type_as_string, RuntimeResult, Value, ValueHashMap, ValueList,
+        ValueMap, ValueNumber, ValueString, ValueTuple, ValueVec,
+    },
+    std::{
+        collections::HashMap,
+        convert::TryInto,
+        fmt,
+        hash::{BuildHasher, Hash},
+    },
+};
+
+/// A trait for types that can be converted from Koto values
+pub trait FromValue: Sized {
+    /// The name of the expected type, used when reporting errors
+    fn type_name() -> String;
+
+    /// Converts the value, returning None if the value has an incompatible type
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+/// A trait for types that can be converted into Koto values
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+/// A trait for the return types of functions registered with `ValueMap::add_fn_typed`
+///
+/// Functions can either return a value that implements `IntoValue`, or a `Result` containing an
+/// `IntoValue` type, with errors being reported as runtime errors.
+pub trait IntoRuntimeResult {
+    fn into_runtime_result(self, function_name: &str) -> RuntimeResult;
+}
+
+impl<T: IntoValue> IntoRuntimeResult for T {
+    fn into_runtime_result(self, _function_name: &str) -> RuntimeResult {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: IntoValue, E: fmt::Display> IntoRuntimeResult for Result<T, E> {
+    fn into_runtime_result(self, function_name: &str) -> RuntimeResult {
+        match self {
+            Ok(value) => Ok(value.into_value()),
+            Err(error) => external_error!("{}: {}", function_name, error),
+        }
+    }
+}
+
+/// A Rust function that can be called with Koto values as arguments
+///
+/// Implemented for functions and closures with up to 6 arguments, where each argument type
+/// implements `FromValue`.
+pub trait TypedFunction<Args>: Send + Sync + 'static {
+    /// Calls the function after converting the arguments
+    ///
+    /// Missing trailing arguments are treated as `Empty`, which allows `Option` arguments to be
+    /// left out.
+    fn call_typed(&self, function_name: &str, args: &[Value]) -> RuntimeResult;
+}
+
+macro_rules! impl_typed_function {
+    ($($arg_type:ident $arg:ident),*) => {
+        impl<F, R, $($arg_type,)*> TypedFunction<($($arg_type,)*)> for F
+        where
+            F: Fn($($arg_type),*) -> R + Send + Sync + 'static,
+            R: IntoRuntimeResult,
+            $($arg_type: FromValue,)*
+        {
+            #[allow(unused_variables, unused_mut)]
+            fn call_typed(&self, function_name: &str, args: &[Value]) -> RuntimeResult {
+                let expected_types: &[fn() -> String] = &[$($arg_type::type_name),*];
+
+                if args.len() > expected_types.len() {
+                    return argument_error(function_name, expected_types, args);
+                }
+
+                let mut arg_iter = args.iter();
+
+                $(
+                    let $arg = match $arg_type::from_value(arg_iter.next().unwrap_or(&Value::Empty)) {
+                        Some(value) => value,
+                        None => return argument_error(function_name, expected_types, args),
+                    };
+                )*
+
+                self($($arg),*).into_runtime_result(function_name)
+            }
+        }
+    };
+}
+
+impl_typed_function!();
+impl_typed_function!(A a);
+impl_typed_function!(A a, B b);
+impl_typed_function!(A a, B b, C c);
+impl_typed_function!(A a, B b, C c, D d);
+impl_typed_function!(A a, B b, C c, D d, E e);
+impl_typed_function!(A a, B b, C c, D d, E e, G g);
+
+fn argument_error(
+    function_name: &str,
+    expected_types: &[fn() -> String],
+    args: &[Value],
+) -> RuntimeResult {
+    let found = args
+        .iter()
+        .map(type_as_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if expected_types.is_empty() {
+        external_error!(
+            "{}: Expected no arguments, found ({})",
+            function_name,
+            found
+        )
+    } else {
+        let expected = expected_types
+            .iter()
+            .map(|type_name| type_name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        external_error!(
+            "{}: Expected ({}) as arguments, found ({})",
+            function_name,
+            expected,
+            found
+        )
+    }
+}
+
+impl FromValue for Value {
+    fn type_name() -> String {
+        "Any".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::Empty
+    }
+}
+
+impl FromValue for bool {
+    fn type_name() -> String {
+        "Bool".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Bool(self)
+    }
+}
+
+macro_rules! impl_number_into_value {
+    ($type:ty) => {
+        impl IntoValue for $type {
+            fn into_value(self) -> Value {
+                Value::Number(ValueNumber::from(self))
+            }
+        }
+    };
+}
+
+macro_rules! impl_float_conversion {
+    ($type:ty) => {
+        impl FromValue for $type {
+            fn type_name() -> String {
+                "Number".to_string()
+            }
+
+            fn from_value(value: &Value) -> Option<Self> {
+                match value {
+                    Value::Number(n) => Some(n.into()),
+                    _ => None,
+                }
+            }
+        }
+
+        impl_number_into_value!($type);
+    };
+}
+
+// Integer conversions only succeed when the number is integral and fits in the target type
+macro_rules! impl_int_conversion {
+    ($type:ty) => {
+        impl FromValue for $type {
+            fn type_name() -> String {
+                "Number".to_string()
+            }
+
+            fn from_value(value: &Value) -> Option<Self> {
+                match value {
+                    Value::Number(n) => integer_from_number(n).and_then(|n| n.try_into().ok()),
+                    _ => None,
+                }
+            }
+        }
+
+        impl_number_into_value!($type);
+    };
+}
+
+fn integer_from_number(n: &ValueNumber) -> Option<i64> {
+    match n {
+        ValueNumber::I64(n) => Some(*n),
+        // i64::MAX as f64 rounds up to 2^63, so the upper bound is exclusive
+        ValueNumber::F64(n)
+            if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 =>
+        {
+            Some(*n as i64)
+        }
+        ValueNumber::F64(_) => None,
+    }
+}
+
+impl_float_conversion!(f32);
+impl_float_conversion!(f64);
+impl_int_conversion!(i32);
+impl_int_conversion!(i64);
+impl_int_conversion!(isize);
+impl_int_conversion!(u8);
+impl_int_conversion!(u32);
+impl_int_conversion!(usize);
+
+impl FromValue for String {
+    fn type_name() -> String {
+        "String".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Str(s) => Some(s.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::Str(self.into())
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::Str(self.into())
+    }
+}
+
+impl FromValue for ValueString {
+    fn type_name() -> String {
+        "String".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Str(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for ValueString {
+    fn into_value(self) -> Value {
+        Value::Str(self)
+    }
+}
+
+impl FromValue for num2::Num2 {
+    fn type_name() -> String {
+        "Num2".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Num2(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for num2::Num2 {
+    fn into_value(self) -> Value {
+        Value::Num2(self)
+    }
+}
+
+impl FromValue for num4::Num4 {
+    fn type_name() -> String {
+        "Num4".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Num4(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for num4::Num4 {
+    fn into_value(self) -> Value {
+        Value::Num4(self)
+    }
+}
+
+impl FromValue for ValueList {
+    fn type_name() -> String {
+        "List".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::List(l) => Some(l.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for ValueList {
+    fn into_value(self) -> Value {
+        Value::List(self)
+    }
+}
+
+impl FromValue for ValueMap {
+    fn type_name() -> String {
+        "Map".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Map(m) => Some(m.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for ValueMap {
+    fn into_value(self) -> Value {
+        Value::Map(self)
+    }
+}
+
+impl FromValue for ValueTuple {
+    fn type_name() -> String {
+        "Tuple".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Tuple(t) => Some(t.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for ValueTuple {
+    fn into_value(self) -> Value {
+        Value::Tuple(self)
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn type_name() -> String {
+        format!("{} or Empty", T::type_name())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Empty => Some(None),
+            _ => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        match self {
+            Some(value) => value.into_value(),
+            None => Value::Empty,
+        }
+    }
+}
+
+// Includes the type of a container's elements in the container's type name, e.g. 'List of Number'
+//
+// Containers that accept any type of element only use the container's name.
+fn container_type_name(container: &str, element_type: String) -> String {
+    if element_type == Value::type_name() {
+        container.to_string()
+    } else {
+        format!("{} of {}", container, element_type)
+    }
+}
+
+// Vecs can be converted from Lists or Tuples, and are converted into Lists
+impl<T: FromValue> FromValue for Vec<T> {
+    fn type_name() -> String {
+        container_type_name("List", T::type_name())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::List(l) => l.data().iter().map(T::from_value).collect(),
+            Value::Tuple(t) => t.data().iter().map(T::from_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::List(ValueList::with_data(
+            self.into_iter()
+                .map(IntoValue::into_value)
+                .collect::<ValueVec>(),
+        ))
+    }
+}
+
+// HashMaps can be converted from Maps that only have String keys
+impl<K, T, S> FromValue for HashMap<K, T, S>
+where
+    K: From<String> + Eq + Hash,
+    T: FromValue,
+    S: BuildHasher + Default,
+{
+    fn type_name() -> String {
+        container_type_name("Map", T::type_name())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Map(m) => m
+                .data()
+                .iter()
+                .map(|(key, value)| match key {
+                    Value::Str(s) => Some((K::from(s.to_string()), T::from_value(value)?)),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+impl<K, T, S> IntoValue for HashMap<K, T, S>
+where
+    K: IntoValue,
+    T: IntoValue,
+{
+    fn into_value(self) -> Value {
+        let mut data = ValueHashMap::with_capacity(self.len());
+        for (key, value) in self.into_iter() {
+            data.insert(key.into_value(), value.into_value());
+        }
+        Value::Map(ValueMap::with_data(data))
+    }
+}
+
+// Rust tuples can be converted from Tuples or Lists with a matching size
+macro_rules! impl_tuple_conversion {
+    ($len:expr, $($type:ident $index:tt),+) => {
+        impl<$($type: FromValue),+> FromValue for ($($type,)+) {
+            fn type_name() -> String {
+                "Tuple".to_string()
+            }
+
+            fn from_value(value: &Value) -> Option<Self> {
+                let convert = |values: &[Value]| {
+                    if values.len() == $len {
+                        Some(($($type::from_value(&values[$index])?,)+))
+                    } else {
+                        None
+                    }
+                };
+
+                match value {
+                    Value::Tuple(t) => convert(t.data()),
+                    Value::List(l) => convert(&l.data()),
+                    _ => None,
+                }
+            }
+        }
+
+        impl<$($type: IntoValue),+> IntoValue for ($($type,)+) {
+            fn into_value(self) -> Value {
+                Value::Tuple(vec![$(self.$index.into_value()),+].into())
+            }
+        }
+    };
+}
+
+impl_tuple_conversion!(2, A 0, B 1);
+impl_tuple_conversion!(3, A 0, B 1, C 2);
+impl_tuple_conversion!(4, A 0, B 1, C 2, D 3);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_conversion() {
+        let value = 42.into_value();
+        assert_eq!(value, Value::Number(42.into()));
+        assert_eq!(i64::from_value(&value), Some(42));
+        assert_eq!(f64::from_value(&value), Some(42.0));
+        assert_eq!(i64::from_value(&Value::Bool(true)), None);
+    }
+
+    #[test]
+    fn integer_conversion_out_of_range() {
+        assert_eq!(u8::from_value(&Value::Number(255.into())), Some(255));
+        assert_eq!(u8::from_value(&Value::Number(300.into())), None);
+        assert_eq!(u8::from_value(&Value::Number((-1).into())), None);
+        assert_eq!(u32::from_value(&Value::Number(2.0.into())), Some(2));
+        assert_eq!(i64::from_value(&Value::Number(1.5.into())), None);
+        assert_eq!(i64::from_value(&Value::Number(f64::NAN.into())), None);
+        assert_eq!(i64::from_value(&Value::Number(1e19.into())), None);
+    }
+
+    #[test]
+    fn vec_conversion() {
+        let value = vec![1, 2, 3].into_value();
+        assert_eq!(Vec::<i64>::from_value(&value), Some(vec![1, 2, 3]));
+        assert_eq!(Vec::<String>::from_value(&value), None);
+    }
+
+    #[test]
+    fn hash_map_conversion() {
+        let mut map = HashMap::new();
+        map.insert("foo".to_string(), (1.0, true));
+        let value = map.clone().into_value();
+        assert_eq!(HashMap::from_value(&value), Some(map));
+    }
+
+    #[test]
+    fn tuple_conversion() {
+        let value = ("foo", 1, num2::Num2(2.0, 3.0)).into_value();
+        assert_eq!(
+            <(String, u8, num2::Num2)>::from_value(&value),
+            Some(("foo".to_string(), 1, num2::Num2(2.0, 3.0)))
+        );
+        assert_eq!(<(String, u8)>::from_value(&value), None);
+    }
+
+    #[test]
+    fn option_conversion() {
+        assert_eq!(Option::<bool>::from_value(&Value::Empty), Some(None));
+        assert_eq!(
+            Option::<bool>::from_value(&Value::Bool(true)),
+            Some(Some(true))
+        );
+        assert_eq!(Option::<bool>::from_value(&Value::Number(1.into())), None);
+        assert_eq!(None::<bool>.into_value(), Value::Empty);
+    }
+}
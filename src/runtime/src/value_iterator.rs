@@ -72,7 +72,7 @@ impl Iterator for ValueIteratorInternals {
                     let result = *start + self.index as isize;
                     if result < *end {
                         self.index += 1;
-                        Some(Ok(ValueIteratorOutput::Value(Number(result as f64))))
+                        Some(Ok(ValueIteratorOutput::Value(Number(result.into()))))
                     } else {
                         None
                     }
@@ -81,7 +81,7 @@ impl Iterator for ValueIteratorInternals {
                     let result = *start - self.index as isize;
                     if result > *end {
                         self.index += 1;
-                        Some(Ok(ValueIteratorOutput::Value(Number(result as f64))))
+                        Some(Ok(ValueIteratorOutput::Value(Number(result.into()))))
                     } else {
                         None
                     }
@@ -174,6 +174,20 @@ impl ValueIterator {
             })),
         }
     }
+
+    // Calls the provided function with the generator's VM if the iterator wraps a generator
+    //
+    // None is returned if the iterator isn't a generator, or if the iterator is currently locked,
+    // e.g. when a generator tries to resume itself.
+    pub(crate) fn with_generator_vm<T>(&self, f: impl FnOnce(&mut Vm) -> T) -> Option<T> {
+        match self.0.try_lock() {
+            Ok(mut internals) => match &mut internals.iterable {
+                Iterable::Generator(vm) => Some(f(vm)),
+                _ => None,
+            },
+            Err(_) => None,
+        }
+    }
 }
 
 impl Iterator for ValueIterator {
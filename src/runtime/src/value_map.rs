@@ -1,10 +1,10 @@
 use {
     crate::{
         external::{Args, ExternalFunction},
-        RuntimeResult, Value, ValueList, ValueRef, Vm,
+        RuntimeResult, TypedFunction, Value, ValueList, ValueRef, Vm,
     },
     indexmap::{
-        map::{Iter, Keys, Values},
+        map::{Iter, Keys, Values, ValuesMut},
         IndexMap,
     },
     rustc_hash::FxHasher,
@@ -175,6 +175,11 @@ impl ValueHashMap {
         self.0.values()
     }
 
+    #[inline]
+    pub fn values_mut(&mut self) -> ValuesMut<'_, Value, Value> {
+        self.0.values_mut()
+    }
+
     #[inline]
     pub fn iter(&self) -> Iter<'_, Value, Value> {
         self.0.iter()
@@ -235,6 +240,11 @@ impl ValueMap {
         self.0.write().unwrap()
     }
 
+    // Returns true if both maps share the same data
+    pub(crate) fn is_same_instance(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
     #[inline]
     pub fn insert(&mut self, key: Value, value: Value) {
         self.data_mut().insert(key, value);
@@ -268,6 +278,24 @@ impl ValueMap {
         self.add_value(id, Value::ExternalFunction(ExternalFunction::new(f, true)));
     }
 
+    /// Adds a function that takes typed arguments
+    ///
+    /// Arguments are converted using `FromValue`, and the function's result is converted using
+    /// `IntoValue`. Calls with unexpected arguments result in an error that lists the expected
+    /// and provided argument types.
+    ///
+    /// e.g.
+    /// `map.add_fn_typed("add", |a: f64, b: f64| a + b);`
+    pub fn add_fn_typed<Args, F>(&mut self, id: &str, f: F)
+    where
+        F: TypedFunction<Args>,
+    {
+        let function_name = id.to_string();
+        self.add_fn(id, move |vm, args| {
+            f.call_typed(&function_name, vm.get_args(args))
+        });
+    }
+
     #[inline]
     pub fn add_list(&mut self, id: &str, list: ValueList) {
         self.add_value(id, Value::List(list));
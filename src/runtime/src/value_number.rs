@@ -0,0 +1,333 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops,
+};
+
+/// The Number type used by Koto
+///
+/// Integers are kept as `I64`, with operations that can't be represented losslessly as integers
+/// (e.g. division, overflowing arithmetic) promoting the result to `F64`.
+#[derive(Clone, Copy, Debug)]
+pub enum ValueNumber {
+    F64(f64),
+    I64(i64),
+}
+
+use ValueNumber::{F64, I64};
+
+impl ValueNumber {
+    pub fn abs(self) -> Self {
+        match self {
+            F64(n) => F64(n.abs()),
+            I64(n) => match n.checked_abs() {
+                Some(result) => I64(result),
+                None => F64((n as f64).abs()),
+            },
+        }
+    }
+
+    pub fn ceil(self) -> Self {
+        match self {
+            F64(n) => F64(n.ceil()),
+            I64(_) => self,
+        }
+    }
+
+    pub fn floor(self) -> Self {
+        match self {
+            F64(n) => F64(n.floor()),
+            I64(_) => self,
+        }
+    }
+
+    pub fn is_f64(&self) -> bool {
+        matches!(self, F64(_))
+    }
+
+    pub fn is_i64(&self) -> bool {
+        matches!(self, I64(_))
+    }
+
+    pub fn is_nan(&self) -> bool {
+        match self {
+            F64(n) => n.is_nan(),
+            I64(_) => false,
+        }
+    }
+
+    pub fn pow(self, other: Self) -> Self {
+        match (self, other) {
+            (I64(a), I64(b)) if b >= 0 && b <= u32::MAX as i64 => match a.checked_pow(b as u32) {
+                Some(result) => I64(result),
+                None => F64((a as f64).powf(b as f64)),
+            },
+            (a, b) => F64(f64::from(a).powf(f64::from(b))),
+        }
+    }
+
+    pub fn to_f64(self) -> Self {
+        F64(self.into())
+    }
+
+    pub fn to_i64(self) -> Self {
+        I64(self.into())
+    }
+}
+
+impl Default for ValueNumber {
+    fn default() -> Self {
+        I64(0)
+    }
+}
+
+impl fmt::Display for ValueNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            F64(n) => write!(f, "{}", n),
+            I64(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl PartialEq for ValueNumber {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (F64(a), F64(b)) => a == b,
+            (I64(a), I64(b)) => a == b,
+            (F64(a), I64(b)) => compare_float_with_int(*a, *b) == Some(Ordering::Equal),
+            (I64(a), F64(b)) => compare_float_with_int(*b, *a) == Some(Ordering::Equal),
+        }
+    }
+}
+
+impl PartialOrd for ValueNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (F64(a), F64(b)) => a.partial_cmp(b),
+            (I64(a), I64(b)) => a.partial_cmp(b),
+            (F64(a), I64(b)) => compare_float_with_int(*a, *b),
+            (I64(a), F64(b)) => compare_float_with_int(*b, *a).map(Ordering::reverse),
+        }
+    }
+}
+
+// Compares a float with an integer exactly
+//
+// Casting the integer to a float would lose precision for large integers, causing e.g.
+// 2^53 + 1 to be considered equal to 2^53 as a float, which would be inconsistent with hashing.
+fn compare_float_with_int(a: f64, b: i64) -> Option<Ordering> {
+    if a.is_nan() {
+        None
+    } else if a >= i64::MAX as f64 {
+        // i64::MAX as f64 rounds up to 2^63, which is larger than any i64
+        Some(Ordering::Greater)
+    } else if a < i64::MIN as f64 {
+        Some(Ordering::Less)
+    } else {
+        let truncated = a.trunc();
+        match (truncated as i64).cmp(&b) {
+            Ordering::Equal => (a - truncated).partial_cmp(&0.0),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl PartialEq<f64> for ValueNumber {
+    fn eq(&self, other: &f64) -> bool {
+        *self == F64(*other)
+    }
+}
+
+impl PartialOrd<f64> for ValueNumber {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        self.partial_cmp(&F64(*other))
+    }
+}
+
+impl Hash for ValueNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Floats with integer values need to hash to the same value as the matching integer,
+        // so that e.g. 1 and 1.0 can be used interchangeably as map keys.
+        match self {
+            F64(n) if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 => {
+                state.write_i64(*n as i64)
+            }
+            F64(n) => state.write_u64(n.to_bits()),
+            I64(n) => state.write_i64(*n),
+        }
+    }
+}
+
+impl ops::Neg for ValueNumber {
+    type Output = ValueNumber;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            F64(n) => F64(-n),
+            I64(n) => match n.checked_neg() {
+                Some(result) => I64(result),
+                None => F64(-(n as f64)),
+            },
+        }
+    }
+}
+
+impl ops::Neg for &ValueNumber {
+    type Output = ValueNumber;
+
+    fn neg(self) -> Self::Output {
+        -*self
+    }
+}
+
+macro_rules! number_op {
+    ($trait:ident, $fn:ident, $checked_fn:ident) => {
+        impl ops::$trait for ValueNumber {
+            type Output = ValueNumber;
+
+            fn $fn(self, other: ValueNumber) -> ValueNumber {
+                match (self, other) {
+                    (I64(a), I64(b)) => match a.$checked_fn(b) {
+                        Some(result) => I64(result),
+                        None => F64((a as f64).$fn(b as f64)),
+                    },
+                    (a, b) => F64(f64::from(a).$fn(f64::from(b))),
+                }
+            }
+        }
+
+        impl ops::$trait<&ValueNumber> for &ValueNumber {
+            type Output = ValueNumber;
+
+            fn $fn(self, other: &ValueNumber) -> ValueNumber {
+                ops::$trait::$fn(*self, *other)
+            }
+        }
+    };
+}
+
+number_op!(Add, add, checked_add);
+number_op!(Sub, sub, checked_sub);
+number_op!(Mul, mul, checked_mul);
+number_op!(Rem, rem, checked_rem);
+
+impl ops::Div for ValueNumber {
+    type Output = ValueNumber;
+
+    fn div(self, other: ValueNumber) -> ValueNumber {
+        // Division always produces a float
+        F64(f64::from(self) / f64::from(other))
+    }
+}
+
+impl ops::Div<&ValueNumber> for &ValueNumber {
+    type Output = ValueNumber;
+
+    fn div(self, other: &ValueNumber) -> ValueNumber {
+        *self / *other
+    }
+}
+
+macro_rules! impl_from_float {
+    ($type:ty) => {
+        impl From<$type> for ValueNumber {
+            fn from(n: $type) -> ValueNumber {
+                F64(n as f64)
+            }
+        }
+
+        impl From<ValueNumber> for $type {
+            fn from(n: ValueNumber) -> $type {
+                match n {
+                    F64(f) => f as $type,
+                    I64(i) => i as $type,
+                }
+            }
+        }
+
+        impl From<&ValueNumber> for $type {
+            fn from(n: &ValueNumber) -> $type {
+                <$type>::from(*n)
+            }
+        }
+    };
+}
+
+macro_rules! impl_from_int {
+    ($type:ty) => {
+        impl From<$type> for ValueNumber {
+            fn from(n: $type) -> ValueNumber {
+                I64(n as i64)
+            }
+        }
+
+        impl From<ValueNumber> for $type {
+            fn from(n: ValueNumber) -> $type {
+                match n {
+                    F64(f) => f as $type,
+                    I64(i) => i as $type,
+                }
+            }
+        }
+
+        impl From<&ValueNumber> for $type {
+            fn from(n: &ValueNumber) -> $type {
+                <$type>::from(*n)
+            }
+        }
+    };
+}
+
+impl_from_float!(f32);
+impl_from_float!(f64);
+
+impl_from_int!(i32);
+impl_from_int!(i64);
+impl_from_int!(isize);
+impl_from_int!(u8);
+impl_from_int!(u32);
+impl_from_int!(usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_arithmetic() {
+        assert!(matches!(I64(1) + I64(2), I64(3)));
+        assert!(matches!(I64(1) - I64(2), I64(-1)));
+        assert!(matches!(I64(3) * I64(4), I64(12)));
+        assert!(matches!(I64(7) % I64(4), I64(3)));
+    }
+
+    #[test]
+    fn float_promotion() {
+        assert!(matches!(I64(1) + F64(0.5), F64(_)));
+        assert!(matches!(I64(1) / I64(2), F64(_)));
+        assert!(matches!(I64(i64::MAX) + I64(1), F64(_)));
+        assert!(matches!(I64(1) % I64(0), F64(_)));
+        assert!(matches!(-I64(i64::MIN), F64(_)));
+    }
+
+    #[test]
+    fn mixed_comparisons() {
+        assert_eq!(I64(1), F64(1.0));
+        assert!(I64(1) < F64(1.5));
+        assert!(F64(-0.5) < I64(0));
+    }
+
+    #[test]
+    fn mixed_comparisons_are_exact() {
+        let large_int = I64(2_i64.pow(53) + 1);
+        let large_float = F64(2_f64.powi(53));
+        assert_ne!(large_int, large_float);
+        assert_ne!(large_float, large_int);
+        assert!(large_float < large_int);
+        assert_eq!(I64(2_i64.pow(53)), large_float);
+        assert!(I64(i64::MAX) < F64(2_f64.powi(63)));
+        assert!(I64(i64::MIN) == F64(-(2_f64.powi(63))));
+        assert_eq!(I64(1).partial_cmp(&F64(f64::NAN)), None);
+    }
+}
@@ -1,23 +1,30 @@
 use {
     crate::{
+        capabilities::{Capabilities, ImportPolicy},
         core::CoreLib,
+        coroutine::Coroutine,
+        debugger::{DebugCommand, DebugFrame, DebugPause, Debugger},
         external::{self, Args, ExternalFunction},
+        external_error,
         frame::Frame,
-        num2, num4, type_as_string,
+        limits::{ExecutionLimits, InterruptHandle, MemoryLimits, DEADLINE_CHECK_INTERVAL},
+        meta_keys, num2, num4, type_as_string,
         value::{self, deep_copy_value, RegisterSlice, RuntimeFunction},
         value_iterator::{IntRange, Iterable, ValueIterator, ValueIteratorOutput},
-        vm_error, Error, Loader, RuntimeResult, Value, ValueList, ValueMap, ValueString, ValueVec,
+        vm_error, BacktraceFrame, Error, InterruptReason, Loader, RuntimeResult, Value, ValueList,
+        ValueMap, ValueNumber, ValueString, ValueVec,
     },
-    koto_bytecode::{Chunk, Instruction, InstructionReader},
+    koto_bytecode::{Chunk, Instruction, InstructionReader, LoaderError},
     koto_parser::ConstantIndex,
     std::{
         collections::HashMap,
         fmt,
-        path::PathBuf,
+        path::{Path, PathBuf},
         sync::{
             atomic::{AtomicBool, Ordering},
-            Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+            Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
         },
+        time::Instant,
     },
 };
 
@@ -38,14 +45,34 @@ pub struct VmContext {
     loader: Loader,
     modules: HashMap<PathBuf, Option<ValueMap>>,
     spawned_stop_flags: Vec<Arc<AtomicBool>>,
+    capabilities: Capabilities,
 }
 
 impl Default for VmContext {
     fn default() -> Self {
+        Self::with_capabilities(Capabilities::default())
+    }
+}
+
+impl VmContext {
+    /// Makes a context where scripts only have access to the given capabilities
+    ///
+    /// Core library modules that provide unavailable capabilities are left out of the prelude.
+    pub fn with_capabilities(capabilities: Capabilities) -> Self {
         let core_lib = CoreLib::default();
 
+        if !capabilities.filesystem {
+            core_lib
+                .koto
+                .data_mut()
+                .remove(&Value::Str("current_dir".into()));
+        }
+
         let mut prelude = ValueMap::default();
-        prelude.add_map("io", core_lib.io.clone());
+        prelude.add_map("coroutine", core_lib.coroutine.clone());
+        if capabilities.filesystem {
+            prelude.add_map("io", core_lib.io.clone());
+        }
         prelude.add_map("iterator", core_lib.iterator.clone());
         prelude.add_map("koto", core_lib.koto.clone());
         prelude.add_map("list", core_lib.list.clone());
@@ -54,21 +81,39 @@ impl Default for VmContext {
         prelude.add_map("range", core_lib.range.clone());
         prelude.add_map("string", core_lib.string.clone());
         prelude.add_map("test", core_lib.test.clone());
-        prelude.add_map("thread", core_lib.thread.clone());
+        if capabilities.threads {
+            prelude.add_map("thread", core_lib.thread.clone());
+        }
         prelude.add_map("tuple", core_lib.tuple.clone());
 
         Self {
             prelude,
             core_lib,
             global: Default::default(),
-            loader: Default::default(),
+            loader: make_loader(&capabilities),
             modules: Default::default(),
             spawned_stop_flags: Default::default(),
+            capabilities,
         }
     }
-}
 
-impl VmContext {
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Sets the directories that are searched for modules by `import` expressions
+    ///
+    /// Modules are first looked for next to the importing script, and then in each of the
+    /// search paths in order.
+    pub fn set_module_paths(&mut self, paths: Vec<PathBuf>) {
+        self.loader.set_module_paths(paths);
+    }
+
+    /// The directories that are searched for modules, see [VmContext::set_module_paths]
+    pub fn module_paths(&self) -> &[PathBuf] {
+        self.loader.module_paths()
+    }
+
     fn spawn_new_context(&self) -> Self {
         Self {
             prelude: self.prelude.clone(),
@@ -77,11 +122,14 @@ impl VmContext {
             modules: self.modules.clone(),
             global: Default::default(),
             spawned_stop_flags: Default::default(),
+            capabilities: self.capabilities.clone(),
         }
     }
 
     fn reset(&mut self) {
-        self.loader = Default::default();
+        let module_paths = self.loader.module_paths().to_vec();
+        self.loader = make_loader(&self.capabilities);
+        self.loader.set_module_paths(module_paths);
         self.stop_spawned_vms();
     }
 
@@ -93,6 +141,14 @@ impl VmContext {
     }
 }
 
+fn make_loader(capabilities: &Capabilities) -> Loader {
+    let mut loader = Loader::default();
+    if let ImportPolicy::AllowedDirectory(dir) = &capabilities.imports {
+        loader.set_allowed_module_dir(Some(dir.clone()));
+    }
+    loader
+}
+
 impl Drop for VmContext {
     fn drop(&mut self) {
         self.stop_spawned_vms();
@@ -105,6 +161,20 @@ pub struct Vm {
     value_stack: Vec<Value>,
     call_stack: Vec<Frame>,
     stop_flag: Option<Arc<AtomicBool>>,
+    debugger: Option<Arc<Mutex<Debugger>>>,
+    limits: Option<Arc<ExecutionLimits>>,
+    // Counts down the instructions until the next deadline check
+    deadline_countdown: u32,
+    memory_limits: MemoryLimits,
+    // The call stack depth and value stack size of the VM that this VM was spawned from,
+    // so that limits apply across nested calls from external functions.
+    call_depth_base: usize,
+    value_stack_base: usize,
+    // The value that a coroutine was resumed with, loaded as the result of a yield expression
+    //
+    // Generators that are driven as iterators aren't resumed with a value, in which case the
+    // yield expression evaluates to the yielded value.
+    resume_value: Option<Value>,
 }
 
 impl Default for Vm {
@@ -115,11 +185,28 @@ impl Default for Vm {
             value_stack: Vec::with_capacity(32),
             call_stack: vec![],
             stop_flag: None,
+            debugger: None,
+            limits: None,
+            deadline_countdown: 0,
+            memory_limits: MemoryLimits::default(),
+            call_depth_base: 0,
+            value_stack_base: 0,
+            resume_value: None,
         }
     }
 }
 
 impl Vm {
+    /// Makes a VM where scripts only have access to the given capabilities
+    ///
+    /// See [VmContext::with_capabilities].
+    pub fn with_capabilities(capabilities: Capabilities) -> Self {
+        Self {
+            context: Arc::new(RwLock::new(VmContext::with_capabilities(capabilities))),
+            ..Default::default()
+        }
+    }
+
     pub fn spawn_new_vm(&mut self) -> Self {
         Self {
             context: Arc::new(RwLock::new(self.context().spawn_new_context())),
@@ -127,6 +214,13 @@ impl Vm {
             value_stack: Vec::with_capacity(32),
             call_stack: vec![],
             stop_flag: None,
+            debugger: None,
+            limits: self.limits.clone(),
+            deadline_countdown: 0,
+            memory_limits: self.memory_limits,
+            call_depth_base: self.call_depth(),
+            value_stack_base: self.value_stack_size(),
+            resume_value: None,
         }
     }
 
@@ -137,6 +231,13 @@ impl Vm {
             value_stack: Vec::with_capacity(32),
             call_stack: vec![],
             stop_flag: None,
+            debugger: self.debugger.clone(),
+            limits: self.limits.clone(),
+            deadline_countdown: 0,
+            memory_limits: self.memory_limits,
+            call_depth_base: self.call_depth(),
+            value_stack_base: self.value_stack_size(),
+            resume_value: None,
         }
     }
 
@@ -152,7 +253,136 @@ impl Vm {
             value_stack: Vec::with_capacity(32),
             call_stack: vec![],
             stop_flag: Some(stop_flag),
+            debugger: self.debugger.clone(),
+            limits: self.limits.clone(),
+            deadline_countdown: 0,
+            memory_limits: self.memory_limits,
+            call_depth_base: 0,
+            value_stack_base: 0,
+            resume_value: None,
+        }
+    }
+
+    // Spawns a VM for running a generator function, with a frame pushed for the function's body
+    //
+    // The function's arguments should then be placed in the VM's registers, starting at 0.
+    fn spawn_generator_vm(&mut self, function: &RuntimeFunction) -> Self {
+        let mut vm = self.spawn_shared_vm();
+        // Generators can be held in a suspended state for long periods,
+        // so their value stack is only allocated when it's needed.
+        vm.value_stack = Vec::new();
+        vm.push_frame(
+            function.chunk.clone(),
+            function.ip,
+            0,
+            function.captures.clone(),
+        );
+        vm
+    }
+
+    // Spawns a VM for a generator, with the call's arguments placed in the VM's registers
+    //
+    // The instance is placed in the first register for instance functions, and variadic
+    // arguments are captured in a tuple in the register following the regular arguments.
+    fn spawn_generator_vm_with_args(
+        &mut self,
+        function: &RuntimeFunction,
+        instance: Option<Value>,
+        args: &[Value],
+        instruction_ip: usize,
+    ) -> Result<Self, Error> {
+        let expected_arg_count = match (function.instance_function, function.variadic) {
+            (true, true) => function.arg_count - 2,
+            (true, false) | (false, true) => function.arg_count - 1,
+            (false, false) => function.arg_count,
+        } as usize;
+
+        // Validate the argument count
+        if function.variadic {
+            if args.len() < expected_arg_count {
+                return vm_error!(
+                    self.chunk(),
+                    instruction_ip,
+                    "Insufficient arguments for function call, expected {}, found {}",
+                    expected_arg_count,
+                    args.len(),
+                );
+            }
+        } else if args.len() != expected_arg_count {
+            return vm_error!(
+                self.chunk(),
+                instruction_ip,
+                "Incorrect argument count, expected {}, found {}",
+                expected_arg_count,
+                args.len(),
+            );
+        }
+
+        let mut generator_vm = self.spawn_generator_vm(function);
+
+        // Copy the instance value into the generator vm
+        let arg_offset = if function.instance_function {
+            match instance {
+                Some(instance) => {
+                    generator_vm.set_register(0, instance);
+                    1
+                }
+                None => {
+                    return vm_error!(
+                        self.chunk(),
+                        instruction_ip,
+                        "Missing instance for call to instance function"
+                    )
+                }
+            }
+        } else {
+            0
+        };
+
+        // Copy any regular (non-instance, non-variadic) arguments into the generator vm
+        for (arg_index, arg) in args[..expected_arg_count].iter().enumerate() {
+            generator_vm.set_register((arg_index + arg_offset) as u8, arg.clone());
+        }
+
+        // Capture the varargs into a tuple and place them in the generator vm's last arg register
+        if function.variadic {
+            let varargs = Value::Tuple(args[expected_arg_count..].into());
+            generator_vm.set_register((expected_arg_count + arg_offset) as u8, varargs);
         }
+
+        Ok(generator_vm)
+    }
+
+    /// Makes a [Coroutine] that runs the given function when it's resumed
+    ///
+    /// The function is typically a generator, with each `yield` suspending the coroutine until
+    /// it's resumed with [Coroutine::resume].
+    ///
+    /// Arguments are handled in the same way as when calling the function from a script,
+    /// with variadic arguments captured in a tuple. If the function is an instance function,
+    /// then the first argument is used as `self`.
+    pub fn make_coroutine(&mut self, function: &Value, args: &[Value]) -> Result<Coroutine, Error> {
+        let function = match function {
+            Value::Function(f) | Value::Generator(f) => f,
+            unexpected => {
+                return self.unexpected_type_error(
+                    "make_coroutine: Expected a function",
+                    unexpected,
+                    self.ip(),
+                )
+            }
+        };
+
+        // The instance for an instance function is taken from the first argument
+        let (instance, args) = match args {
+            [instance, args @ ..] if function.instance_function => (Some(instance.clone()), args),
+            _ => (None, args),
+        };
+
+        let ip = self.ip();
+        let coroutine_vm = self.spawn_generator_vm_with_args(function, instance, args, ip)?;
+
+        Ok(Coroutine::new(ValueIterator::with_vm(coroutine_vm)))
     }
 
     pub fn context(&self) -> RwLockReadGuard<VmContext> {
@@ -174,12 +404,270 @@ impl Vm {
         }
     }
 
+    /// Converts a value to a string, calling a Map's `@display` function if it's defined
+    pub fn value_to_string(&mut self, value: &Value) -> Result<String, Error> {
+        match self.get_overloaded_op(value, meta_keys::DISPLAY) {
+            Some(op) => {
+                let ip = self.ip();
+                match self.call_overloaded_op(
+                    op,
+                    meta_keys::DISPLAY,
+                    std::slice::from_ref(value),
+                    ip,
+                )? {
+                    Value::Str(s) => Ok(s.to_string()),
+                    unexpected => self.unexpected_type_error(
+                        "Expected a String to be returned from '@display'",
+                        &unexpected,
+                        ip,
+                    ),
+                }
+            }
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Attaches a debugger to the VM, or detaches the current debugger if `None` is provided
+    ///
+    /// The debugger is shared with any VMs that are subsequently spawned from this VM.
+    pub fn set_debugger(&mut self, debugger: Option<Debugger>) {
+        self.debugger = debugger.map(|debugger| Arc::new(Mutex::new(debugger)));
+    }
+
+    /// Returns the VM's debugger, allowing breakpoints to be modified while the VM is running
+    pub fn debugger(&self) -> Option<Arc<Mutex<Debugger>>> {
+        self.debugger.clone()
+    }
+
+    /// Sets the number of instructions that the VM is allowed to execute
+    ///
+    /// The instruction count is shared with any VMs that are subsequently spawned from this VM,
+    /// and is consumed by each script or function that's run until the limit is reset.
+    ///
+    /// When the limit is reached, execution stops with an [Error::Interrupted] error.
+    pub fn set_instruction_limit(&mut self, limit: Option<u64>) {
+        self.limits().set_instruction_limit(limit);
+    }
+
+    /// Returns the number of instructions that the VM can execute before reaching its limit
+    pub fn remaining_instructions(&self) -> Option<u64> {
+        self.limits
+            .as_ref()
+            .and_then(|limits| limits.remaining_instructions())
+    }
+
+    /// Sets a deadline for the VM's execution
+    ///
+    /// The deadline is shared with any VMs that are subsequently spawned from this VM.
+    ///
+    /// When the deadline passes, execution stops with an [Error::Interrupted] error.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.limits().set_deadline(deadline);
+    }
+
+    /// Returns a handle that allows the VM to be interrupted from another thread
+    ///
+    /// VMs that are subsequently spawned from this VM are also interrupted by the handle.
+    pub fn interrupt_handle(&mut self) -> InterruptHandle {
+        InterruptHandle::new(self.limits().clone())
+    }
+
+    fn limits(&mut self) -> &Arc<ExecutionLimits> {
+        self.limits.get_or_insert_with(Default::default)
+    }
+
+    /// Sets limits on the memory that the VM can use
+    ///
+    /// The limits are copied into any VMs that are subsequently spawned from this VM,
+    /// and the total size of allocated containers is reset.
+    pub fn set_memory_limits(&mut self, limits: MemoryLimits) {
+        self.memory_limits = limits;
+        self.limits().reset_allocated_container_size();
+    }
+
+    pub fn memory_limits(&self) -> MemoryLimits {
+        self.memory_limits
+    }
+
+    /// Returns the total size of the containers that have been allocated by the VM
+    ///
+    /// See [MemoryLimits::max_total_container_size].
+    pub fn allocated_container_size(&self) -> usize {
+        self.limits
+            .as_ref()
+            .map_or(0, |limits| limits.allocated_container_size())
+    }
+
+    /// Checks that a container is allowed by the VM's memory limits
+    ///
+    /// `size` is the container's size after it has been created or grown,
+    /// and `added` is the size that's being added to it.
+    ///
+    /// External functions that create or grow containers should call this before allocating.
+    pub fn check_container_size(&self, size: usize, added: usize) -> Result<(), Error> {
+        match self.container_size_error(size, added) {
+            Some(message) => external_error!(message),
+            None => Ok(()),
+        }
+    }
+
+    fn container_size_error(&self, size: usize, added: usize) -> Option<String> {
+        if let Some(max_size) = self.memory_limits.max_container_size {
+            if size > max_size {
+                return Some(format!(
+                    "Container size limit exceeded \
+                     (the limit is {}, the container's size would be {})",
+                    max_size, size
+                ));
+            }
+        }
+
+        if let (Some(max_total), Some(limits)) =
+            (self.memory_limits.max_total_container_size, &self.limits)
+        {
+            if let Err(total) = limits.allocate_container_size(added, max_total) {
+                return Some(format!(
+                    "Total container size limit exceeded \
+                     (the limit is {}, the total size would be {})",
+                    max_total, total
+                ));
+            }
+        }
+
+        None
+    }
+
+    // The call stack depth, including the frames of the VMs that this VM was spawned from
+    fn call_depth(&self) -> usize {
+        self.call_depth_base + self.call_stack.len()
+    }
+
+    // The value stack size, including the values of the VMs that this VM was spawned from
+    fn value_stack_size(&self) -> usize {
+        self.value_stack_base + self.value_stack.len()
+    }
+
+    // Checks the memory limits before a new frame is pushed onto the call stack
+    fn check_stack_limits(&self, ip: usize) -> InstructionResult {
+        let limits = &self.memory_limits;
+
+        if let Some(max_depth) = limits.max_call_stack_depth {
+            if self.call_depth() >= max_depth {
+                return vm_error!(
+                    self.chunk(),
+                    ip,
+                    "Call stack depth limit exceeded (the limit is {})",
+                    max_depth
+                );
+            }
+        }
+
+        if let Some(max_size) = limits.max_value_stack_size {
+            if self.value_stack_size() > max_size {
+                return vm_error!(
+                    self.chunk(),
+                    ip,
+                    "Value stack size limit exceeded (the limit is {})",
+                    max_size
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    // Checks the container size limit while executing an instruction
+    fn check_container_size_at(&self, size: usize, added: usize, ip: usize) -> InstructionResult {
+        match self.container_size_error(size, added) {
+            Some(message) => vm_error!(self.chunk(), ip, "{}", message),
+            None => Ok(()),
+        }
+    }
+
     pub fn reset(&mut self) {
         self.context_mut().reset();
         self.value_stack = Default::default();
         self.call_stack = Default::default();
     }
 
+    /// Removes a module from the VM's module cache
+    ///
+    /// The module will be recompiled and run the next time that it's imported.
+    pub fn invalidate_module(&mut self, path: &Path) {
+        let module_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let mut context = self.context_mut();
+        context.loader.invalidate_module(&module_path);
+        context.modules.remove(&module_path);
+    }
+
+    /// Recompiles and runs a module that was previously imported
+    ///
+    /// The module's existing map is updated in place with the module's new values, so scripts
+    /// that have already imported the module will see the changes. Items that were imported
+    /// individually, e.g. with `from foo import bar`, keep their previous values until the
+    /// importing script is run again.
+    ///
+    /// Values in the module with names in `preserved_globals` keep their current values.
+    pub fn reload_module(&mut self, path: &Path, preserved_globals: &[&str]) -> Result<(), Error> {
+        let module_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let module = match self.context().modules.get(&module_path) {
+            Some(Some(module)) => module.clone(),
+            _ => {
+                return external_error!(
+                    "Unable to reload '{}', the module hasn't been loaded",
+                    module_path.to_string_lossy()
+                )
+            }
+        };
+
+        let compile_result = self.context_mut().loader.recompile_module(&module_path);
+        let module_chunk = match compile_result {
+            Ok((chunk, _)) => chunk,
+            Err(e) => {
+                return external_error!(
+                    "Failed to reload '{}': {}",
+                    module_path.to_string_lossy(),
+                    e
+                )
+            }
+        };
+        let new_module = self.run_module(module_chunk)?;
+
+        let mut new_data = new_module.data().clone();
+        {
+            let data = module.data();
+            for name in preserved_globals.iter() {
+                if let Some(value) = data.get_with_string(name) {
+                    new_data.add_value(name, value.clone());
+                }
+            }
+        }
+        *module.data_mut() = new_data;
+
+        Ok(())
+    }
+
+    /// Reloads the imported modules that have been modified since they were loaded
+    ///
+    /// The paths of the reloaded modules are returned, see [Vm::reload_module].
+    pub fn reload_modified_modules(
+        &mut self,
+        preserved_globals: &[&str],
+    ) -> Result<Vec<PathBuf>, Error> {
+        let modified = self.context().loader.modified_modules();
+        let mut result = Vec::with_capacity(modified.len());
+
+        for module_path in modified {
+            let is_loaded = matches!(self.context().modules.get(&module_path), Some(Some(_)));
+            if is_loaded {
+                self.reload_module(&module_path, preserved_globals)?;
+                result.push(module_path);
+            }
+        }
+
+        Ok(result)
+    }
+
     pub fn run(&mut self, chunk: Arc<Chunk>) -> RuntimeResult {
         self.push_frame(chunk, 0, 0, None);
         self.execute_instructions()
@@ -193,6 +681,20 @@ impl Vm {
         }
     }
 
+    // Continues running a generator, with the yield expression that it was suspended at
+    // evaluating to the provided value
+    pub(crate) fn resume(&mut self, value: Value) -> RuntimeResult {
+        self.resume_value = Some(value);
+        let result = self.continue_running();
+        self.resume_value = None;
+        result
+    }
+
+    // Returns true if the VM has finished running, e.g. when a generator has returned
+    pub(crate) fn is_finished(&self) -> bool {
+        self.call_stack.is_empty()
+    }
+
     pub fn run_function(&mut self, function: &RuntimeFunction, args: &[Value]) -> RuntimeResult {
         if !self.call_stack.is_empty() {
             return vm_error!(
@@ -221,6 +723,8 @@ impl Vm {
             0
         };
 
+        self.check_stack_limits(current_ip)?;
+
         let arg_register = (self.value_stack.len() - frame_base) as u8;
         self.value_stack.extend_from_slice(args);
 
@@ -310,6 +814,22 @@ impl Vm {
                     break;
                 }
             }
+            if let Some(limits) = &self.limits {
+                let check_deadline = self.deadline_countdown == 0;
+                self.deadline_countdown = if check_deadline {
+                    DEADLINE_CHECK_INTERVAL
+                } else {
+                    self.deadline_countdown - 1
+                };
+
+                if let Some(reason) = limits.check(check_deadline) {
+                    return self.interrupt(reason, instruction_ip);
+                }
+            }
+            if let Some(debugger) = self.debugger.clone() {
+                // Errors from the debugger bypass any catch blocks
+                self.run_debugger(&debugger, instruction_ip)?;
+            }
             match self.execute_instruction(instruction, instruction_ip) {
                 Ok(ControlFlow::Continue) => {}
                 Ok(ControlFlow::Return(value)) => {
@@ -320,26 +840,53 @@ impl Vm {
                     result = value;
                     break;
                 }
-                Err(error) => {
+                Err(error) if error.is_interruption() => {
+                    // Interruptions can't be caught, but the call stack still needs to be unwound
+                    return self.interrupt_with_error(error);
+                }
+                Err(mut error) => {
                     let mut recover_register_and_ip = None;
+                    // Frames are added to the error's backtrace as the call stack is unwound
+                    let mut backtrace = Vec::new();
+                    let mut frame_ip = instruction_ip;
 
                     while let Some(frame) = self.call_stack.last() {
                         if let Some((error_register, catch_ip)) = frame.catch_stack.last() {
                             recover_register_and_ip = Some((*error_register, *catch_ip));
                             break;
                         } else {
+                            backtrace.push(BacktraceFrame {
+                                chunk: frame.chunk.clone(),
+                                instruction: frame_ip,
+                            });
+
                             if frame.catch_barrier {
+                                error.extend_backtrace(backtrace);
                                 return Err(error);
                             }
 
                             self.pop_frame(Value::Empty)?;
+
+                            // Step back from the caller's return ip to the call instruction
+                            frame_ip = match self.call_stack.last() {
+                                Some(Frame {
+                                    return_register_and_ip: Some((_, return_ip)),
+                                    ..
+                                }) => return_ip.saturating_sub(1),
+                                _ => 0,
+                            };
                         }
                     }
 
                     if let Some((register, ip)) = recover_register_and_ip {
-                        self.set_register(register, Value::Str(error.to_string().into()));
+                        let catch_value = match error.thrown_value() {
+                            Some(thrown_value) => thrown_value.clone(),
+                            None => Value::Str(error.to_string().into()),
+                        };
+                        self.set_register(register, catch_value);
                         self.set_ip(ip);
                     } else {
+                        error.extend_backtrace(backtrace);
                         return Err(error);
                     }
                 }
@@ -351,6 +898,91 @@ impl Vm {
         Ok(result)
     }
 
+    fn interrupt(&mut self, reason: InterruptReason, ip: usize) -> RuntimeResult {
+        let error = Error::Interrupted {
+            reason,
+            chunk: self.chunk(),
+            instruction: ip,
+        };
+        self.interrupt_with_error(error)
+    }
+
+    // Unwinds the call stack up to the nearest catch barrier, bypassing catch blocks
+    fn interrupt_with_error(&mut self, error: Error) -> RuntimeResult {
+        while let Some(frame) = self.call_stack.last() {
+            if frame.catch_barrier {
+                break;
+            }
+            self.pop_frame(Value::Empty)?;
+        }
+        Err(error)
+    }
+
+    // Called before each instruction when a debugger is attached,
+    // pausing execution when a new line is reached that matches a breakpoint or step request.
+    fn run_debugger(&mut self, debugger: &Mutex<Debugger>, ip: usize) -> Result<(), Error> {
+        let line = match self.reader.chunk.debug_info.get_source_span(ip) {
+            Some(span) => span.start.line,
+            None => return Ok(()),
+        };
+
+        // Execution is considered to have reached a new line when the line changes,
+        // or when a jump back lands on the line that was already being executed.
+        let frame = self.frame_mut();
+        let previous_position = frame.debug_position.replace((line, ip));
+        if matches!(previous_position, Some((previous_line, previous_ip))
+            if previous_line == line && previous_ip < ip)
+        {
+            return Ok(());
+        }
+
+        let depth = self.call_stack.len();
+        let mut debugger = debugger.lock().unwrap();
+
+        if let Some(reason) = debugger.should_pause(&self.reader.chunk, line, depth) {
+            let pause = DebugPause {
+                reason,
+                call_stack: self.debug_call_stack(ip),
+            };
+
+            if debugger.run_handler(&pause, depth) == DebugCommand::Stop {
+                return vm_error!(self.chunk(), ip, "Execution stopped by the debugger");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn debug_call_stack(&self, ip: usize) -> Vec<DebugFrame> {
+        let mut result = Vec::with_capacity(self.call_stack.len());
+        let mut frame_ip = Some(ip);
+        let mut registers_end = self.value_stack.len();
+
+        for frame in self.call_stack.iter().rev() {
+            // The ip for frames other than the innermost frame is taken from the return ip,
+            // stepping back to the call instruction.
+            let ip = frame_ip.unwrap_or_else(|| match frame.return_register_and_ip {
+                Some((_, return_ip)) => return_ip.saturating_sub(1),
+                None => 0,
+            });
+            frame_ip = None;
+
+            let registers_start = frame.register_base.min(registers_end);
+
+            result.push(DebugFrame {
+                chunk: frame.chunk.clone(),
+                ip,
+                span: frame.chunk.debug_info.get_source_span(ip),
+                registers: self.value_stack[registers_start..registers_end].to_vec(),
+                captures: frame.captures(),
+            });
+
+            registers_end = registers_start;
+        }
+
+        result
+    }
+
     fn execute_instruction(
         &mut self,
         instruction: Instruction,
@@ -379,12 +1011,17 @@ impl Vm {
                 Ok(())
             }
             Instruction::SetNumber { register, value } => {
-                self.set_register(register, Number(value));
+                self.set_register(register, Number(value.into()));
                 Ok(())
             }
-            Instruction::LoadNumber { register, constant } => {
-                let n = self.reader.chunk.constants.get_number(constant);
-                self.set_register(register, Number(n));
+            Instruction::LoadFloat { register, constant } => {
+                let n = self.reader.chunk.constants.get_f64(constant);
+                self.set_register(register, Number(n.into()));
+                Ok(())
+            }
+            Instruction::LoadInt { register, constant } => {
+                let n = self.reader.chunk.constants.get_i64(constant);
+                self.set_register(register, Number(n.into()));
                 Ok(())
             }
             Instruction::LoadString { register, constant } => {
@@ -399,6 +1036,11 @@ impl Vm {
             Instruction::Import { register, constant } => {
                 self.run_import(register, constant, instruction_ip)
             }
+            Instruction::ImportNested {
+                register,
+                module,
+                constant,
+            } => self.run_import_nested(register, module, constant, instruction_ip),
             Instruction::MakeTuple {
                 register,
                 start,
@@ -503,8 +1145,12 @@ impl Vm {
             Instruction::GreaterOrEqual { register, lhs, rhs } => {
                 self.run_greater_or_equal(register, lhs, rhs, &instruction, instruction_ip)
             }
-            Instruction::Equal { register, lhs, rhs } => self.run_equal(register, lhs, rhs),
-            Instruction::NotEqual { register, lhs, rhs } => self.run_not_equal(register, lhs, rhs),
+            Instruction::Equal { register, lhs, rhs } => {
+                self.run_equal(register, lhs, rhs, instruction_ip)
+            }
+            Instruction::NotEqual { register, lhs, rhs } => {
+                self.run_not_equal(register, lhs, rhs, instruction_ip)
+            }
             Instruction::Jump { offset } => {
                 self.jump_ip(offset);
                 Ok(())
@@ -561,6 +1207,12 @@ impl Vm {
                 control_flow = ControlFlow::Yield(self.clone_register(register));
                 Ok(())
             }
+            Instruction::LoadResumeValue { register } => {
+                if let Some(value) = self.resume_value.take() {
+                    self.set_register(register, value);
+                }
+                Ok(())
+            }
             Instruction::Size { register, value } => self.run_size(register, value),
             Instruction::IsTuple { register, value } => {
                 let result = matches!(self.get_register(value), Tuple(_));
@@ -649,6 +1301,17 @@ impl Vm {
             Instruction::Debug { register, constant } => {
                 self.run_debug(register, constant, instruction_ip)
             }
+            Instruction::StringConcat {
+                register,
+                start,
+                count,
+            } => self.run_string_concat(register, start, count, instruction_ip),
+            Instruction::Throw { register } => Err(Error::ThrownValue {
+                value: self.clone_register(register),
+                chunk: self.chunk(),
+                instruction: instruction_ip,
+                backtrace: Vec::new(),
+            }),
         }?;
 
         Ok(control_flow)
@@ -715,6 +1378,33 @@ impl Vm {
         Ok(())
     }
 
+    fn run_string_concat(
+        &mut self,
+        register: u8,
+        start: u8,
+        count: u8,
+        instruction_ip: usize,
+    ) -> InstructionResult {
+        let mut result = String::new();
+
+        for source_register in start as usize..start as usize + count as usize {
+            let previous_size = result.len();
+            match self.clone_register(source_register as u8) {
+                Value::Str(s) => result.push_str(&s),
+                value => {
+                    let s = self.value_to_string(&value)?;
+                    result.push_str(&s);
+                }
+            }
+
+            let added = result.len() - previous_size;
+            self.check_container_size_at(result.len(), added, instruction_ip)?;
+        }
+
+        self.set_register(register, Value::Str(result.into()));
+        Ok(())
+    }
+
     fn run_make_range(
         &mut self,
         register: u8,
@@ -732,12 +1422,12 @@ impl Vm {
             (Some(Number(start)), Some(Number(end))) => {
                 let (start, end) = if inclusive {
                     if start <= end {
-                        (*start as isize, *end as isize + 1)
+                        (isize::from(start), isize::from(end) + 1)
                     } else {
-                        (*start as isize, *end as isize - 1)
+                        (isize::from(start), isize::from(end) - 1)
                     }
                 } else {
-                    (*start as isize, *end as isize)
+                    (isize::from(start), isize::from(end))
                 };
 
                 Range(IntRange { start, end })
@@ -752,9 +1442,9 @@ impl Vm {
                     );
                 }
                 let end = if inclusive {
-                    *end as usize + 1
+                    usize::from(end) + 1
                 } else {
-                    *end as usize
+                    usize::from(end)
                 };
                 IndexRange(value::IndexRange {
                     start: 0,
@@ -771,7 +1461,7 @@ impl Vm {
                     );
                 }
                 IndexRange(value::IndexRange {
-                    start: *start as usize,
+                    start: usize::from(start),
                     end: None,
                 })
             }
@@ -1113,11 +1803,27 @@ impl Vm {
     fn run_negate(&mut self, register: u8, value: u8, instruction_ip: usize) -> InstructionResult {
         use Value::*;
 
-        let result = match &self.get_register(value) {
+        let value = self.get_register(value);
+        if let Some(op) = self.get_overloaded_op(value, meta_keys::NEGATE) {
+            let args = [value.clone()];
+            let result = self.call_overloaded_op(op, meta_keys::NEGATE, &args, instruction_ip)?;
+            self.set_register(register, result);
+            return Ok(());
+        }
+
+        let result = match value {
             Bool(b) => Bool(!b),
             Number(n) => Number(-n),
             Num2(v) => Num2(-v),
             Num4(v) => Num4(-v),
+            Map(_) => {
+                return vm_error!(
+                    self.chunk(),
+                    instruction_ip,
+                    "Negate: the Map doesn't define '{}'",
+                    meta_keys::NEGATE
+                );
+            }
             unexpected => {
                 return self.unexpected_type_error(
                     "Negate: expected negatable value",
@@ -1141,17 +1847,23 @@ impl Vm {
     ) -> InstructionResult {
         use Value::*;
 
+        if self.run_overloaded_binary_op(register, lhs, rhs, instruction, instruction_ip)? {
+            return Ok(());
+        }
+
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
         let result = match (lhs_value, rhs_value) {
             (Number(a), Number(b)) => Number(a + b),
-            (Number(a), Num2(b)) => Num2(a + b),
+            (Number(a), Num2(b)) => Num2(&f64::from(a) + b),
             (Num2(a), Num2(b)) => Num2(a + b),
-            (Num2(a), Number(b)) => Num2(a + b),
-            (Number(a), Num4(b)) => Num4(a + b),
+            (Num2(a), Number(b)) => Num2(a + &f64::from(b)),
+            (Number(a), Num4(b)) => Num4(&f64::from(a) + b),
             (Num4(a), Num4(b)) => Num4(a + b),
-            (Num4(a), Number(b)) => Num4(a + b),
+            (Num4(a), Number(b)) => Num4(a + &f64::from(b)),
             (List(a), List(b)) => {
+                let size = a.len() + b.len();
+                self.check_container_size_at(size, size, instruction_ip)?;
                 let mut result = ValueVec::new();
                 result.extend(a.data().iter().chain(b.data().iter()).cloned());
                 List(ValueList::with_data(result))
@@ -1159,9 +1871,12 @@ impl Vm {
             (Map(a), Map(b)) => {
                 let mut result = a.data().clone();
                 result.extend(&b.data());
+                self.check_container_size_at(result.len(), result.len(), instruction_ip)?;
                 Map(ValueMap::with_data(result))
             }
             (Str(a), Str(b)) => {
+                let size = a.len() + b.len();
+                self.check_container_size_at(size, size, instruction_ip)?;
                 let result = a.to_string() + b.as_ref();
                 Str(result.into())
             }
@@ -1184,16 +1899,20 @@ impl Vm {
     ) -> InstructionResult {
         use Value::*;
 
+        if self.run_overloaded_binary_op(register, lhs, rhs, instruction, instruction_ip)? {
+            return Ok(());
+        }
+
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
         let result = match (lhs_value, rhs_value) {
             (Number(a), Number(b)) => Number(a - b),
-            (Number(a), Num2(b)) => Num2(a - b),
+            (Number(a), Num2(b)) => Num2(&f64::from(a) - b),
             (Num2(a), Num2(b)) => Num2(a - b),
-            (Num2(a), Number(b)) => Num2(a - b),
-            (Number(a), Num4(b)) => Num4(a - b),
+            (Num2(a), Number(b)) => Num2(a - &f64::from(b)),
+            (Number(a), Num4(b)) => Num4(&f64::from(a) - b),
             (Num4(a), Num4(b)) => Num4(a - b),
-            (Num4(a), Number(b)) => Num4(a - b),
+            (Num4(a), Number(b)) => Num4(a - &f64::from(b)),
             _ => {
                 return self.binary_op_error(lhs_value, rhs_value, instruction, instruction_ip);
             }
@@ -1213,16 +1932,20 @@ impl Vm {
     ) -> InstructionResult {
         use Value::*;
 
+        if self.run_overloaded_binary_op(register, lhs, rhs, instruction, instruction_ip)? {
+            return Ok(());
+        }
+
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
         let result = match (lhs_value, rhs_value) {
             (Number(a), Number(b)) => Number(a * b),
-            (Number(a), Num2(b)) => Num2(a * b),
+            (Number(a), Num2(b)) => Num2(&f64::from(a) * b),
             (Num2(a), Num2(b)) => Num2(a * b),
-            (Num2(a), Number(b)) => Num2(a * b),
-            (Number(a), Num4(b)) => Num4(a * b),
+            (Num2(a), Number(b)) => Num2(a * &f64::from(b)),
+            (Number(a), Num4(b)) => Num4(&f64::from(a) * b),
             (Num4(a), Num4(b)) => Num4(a * b),
-            (Num4(a), Number(b)) => Num4(a * b),
+            (Num4(a), Number(b)) => Num4(a * &f64::from(b)),
             _ => {
                 return self.binary_op_error(lhs_value, rhs_value, instruction, instruction_ip);
             }
@@ -1242,16 +1965,20 @@ impl Vm {
     ) -> InstructionResult {
         use Value::*;
 
+        if self.run_overloaded_binary_op(register, lhs, rhs, instruction, instruction_ip)? {
+            return Ok(());
+        }
+
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
         let result = match (lhs_value, rhs_value) {
             (Number(a), Number(b)) => Number(a / b),
-            (Number(a), Num2(b)) => Num2(a / b),
+            (Number(a), Num2(b)) => Num2(&f64::from(a) / b),
             (Num2(a), Num2(b)) => Num2(a / b),
-            (Num2(a), Number(b)) => Num2(a / b),
-            (Number(a), Num4(b)) => Num4(a / b),
+            (Num2(a), Number(b)) => Num2(a / &f64::from(b)),
+            (Number(a), Num4(b)) => Num4(&f64::from(a) / b),
             (Num4(a), Num4(b)) => Num4(a / b),
-            (Num4(a), Number(b)) => Num4(a / b),
+            (Num4(a), Number(b)) => Num4(a / &f64::from(b)),
             _ => {
                 return self.binary_op_error(lhs_value, rhs_value, instruction, instruction_ip);
             }
@@ -1271,16 +1998,20 @@ impl Vm {
     ) -> InstructionResult {
         use Value::*;
 
+        if self.run_overloaded_binary_op(register, lhs, rhs, instruction, instruction_ip)? {
+            return Ok(());
+        }
+
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
         let result = match (lhs_value, rhs_value) {
             (Number(a), Number(b)) => Number(a % b),
-            (Number(a), Num2(b)) => Num2(a % b),
+            (Number(a), Num2(b)) => Num2(&f64::from(a) % b),
             (Num2(a), Num2(b)) => Num2(a % b),
-            (Num2(a), Number(b)) => Num2(a % b),
-            (Number(a), Num4(b)) => Num4(a % b),
+            (Num2(a), Number(b)) => Num2(a % &f64::from(b)),
+            (Number(a), Num4(b)) => Num4(&f64::from(a) % b),
             (Num4(a), Num4(b)) => Num4(a % b),
-            (Num4(a), Number(b)) => Num4(a % b),
+            (Num4(a), Number(b)) => Num4(a % &f64::from(b)),
             _ => {
                 return self.binary_op_error(lhs_value, rhs_value, instruction, instruction_ip);
             }
@@ -1300,6 +2031,10 @@ impl Vm {
     ) -> InstructionResult {
         use Value::*;
 
+        if self.run_overloaded_binary_op(register, lhs, rhs, instruction, instruction_ip)? {
+            return Ok(());
+        }
+
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
         let result = match (lhs_value, rhs_value) {
@@ -1324,6 +2059,10 @@ impl Vm {
     ) -> InstructionResult {
         use Value::*;
 
+        if self.run_overloaded_binary_op(register, lhs, rhs, instruction, instruction_ip)? {
+            return Ok(());
+        }
+
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
         let result = match (lhs_value, rhs_value) {
@@ -1348,6 +2087,10 @@ impl Vm {
     ) -> InstructionResult {
         use Value::*;
 
+        if self.run_overloaded_binary_op(register, lhs, rhs, instruction, instruction_ip)? {
+            return Ok(());
+        }
+
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
         let result = match (lhs_value, rhs_value) {
@@ -1372,6 +2115,10 @@ impl Vm {
     ) -> InstructionResult {
         use Value::*;
 
+        if self.run_overloaded_binary_op(register, lhs, rhs, instruction, instruction_ip)? {
+            return Ok(());
+        }
+
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
         let result = match (lhs_value, rhs_value) {
@@ -1386,18 +2133,52 @@ impl Vm {
         Ok(())
     }
 
-    fn run_equal(&mut self, register: u8, lhs: u8, rhs: u8) -> InstructionResult {
+    fn run_equal(
+        &mut self,
+        register: u8,
+        lhs: u8,
+        rhs: u8,
+        instruction_ip: usize,
+    ) -> InstructionResult {
         let lhs_value = self.get_register(lhs);
-        let rhs_value = self.get_register(rhs);
-        let result = (lhs_value == rhs_value).into();
+        let result = match self.get_overloaded_op(lhs_value, meta_keys::EQUAL) {
+            Some(op) => {
+                let args = [lhs_value.clone(), self.clone_register(rhs)];
+                self.call_overloaded_op(op, meta_keys::EQUAL, &args, instruction_ip)?
+            }
+            None => (lhs_value == self.get_register(rhs)).into(),
+        };
         self.set_register(register, result);
         Ok(())
     }
 
-    fn run_not_equal(&mut self, register: u8, lhs: u8, rhs: u8) -> InstructionResult {
+    fn run_not_equal(
+        &mut self,
+        register: u8,
+        lhs: u8,
+        rhs: u8,
+        instruction_ip: usize,
+    ) -> InstructionResult {
         let lhs_value = self.get_register(lhs);
-        let rhs_value = self.get_register(rhs);
-        let result = (lhs_value != rhs_value).into();
+        let result = if let Some(op) = self.get_overloaded_op(lhs_value, meta_keys::NOT_EQUAL) {
+            let args = [lhs_value.clone(), self.clone_register(rhs)];
+            self.call_overloaded_op(op, meta_keys::NOT_EQUAL, &args, instruction_ip)?
+        } else if let Some(op) = self.get_overloaded_op(lhs_value, meta_keys::EQUAL) {
+            // Fall back to negating the result of '@=='
+            let args = [lhs_value.clone(), self.clone_register(rhs)];
+            match self.call_overloaded_op(op, meta_keys::EQUAL, &args, instruction_ip)? {
+                Value::Bool(b) => Value::Bool(!b),
+                unexpected => {
+                    return self.unexpected_type_error(
+                        "NotEqual: expected a Bool to be returned from '@=='",
+                        &unexpected,
+                        instruction_ip,
+                    );
+                }
+            }
+        } else {
+            (lhs_value != self.get_register(rhs)).into()
+        };
         self.set_register(register, result);
         Ok(())
     }
@@ -1464,7 +2245,7 @@ impl Vm {
             Range(IntRange { start, end }) => (end - start) as usize,
             _ => 1,
         };
-        self.set_register(register, Number(result as f64));
+        self.set_register(register, Number(result.into()));
 
         Ok(())
     }
@@ -1495,68 +2276,148 @@ impl Vm {
             if let Some(value) = maybe_in_prelude {
                 self.set_register(result_register, value);
             } else {
+                if self.context().capabilities.imports == ImportPolicy::Disabled {
+                    return vm_error!(
+                        self.chunk(),
+                        instruction_ip,
+                        "Failed to import '{}': importing modules isn't allowed",
+                        import_name
+                    );
+                }
+
                 let source_path = self.reader.chunk.source_path.clone();
                 let compile_result = self
                     .context_mut()
                     .loader
                     .compile_module(&import_name, source_path);
-                let (module_chunk, module_path) = match compile_result {
-                    Ok(chunk) => chunk,
-                    Err(e) => {
-                        return vm_error!(
-                            self.chunk(),
-                            instruction_ip,
-                            "Failed to import '{}': {}",
-                            import_name,
-                            e
-                        )
-                    }
-                };
-                let maybe_module = self.context().modules.get(&module_path).cloned();
-                match maybe_module {
-                    Some(Some(module)) => self.set_register(result_register, Value::Map(module)),
-                    Some(None) => {
-                        return vm_error!(
-                            self.chunk(),
-                            instruction_ip,
-                            "Recursive import of module '{}'",
-                            import_name
-                        )
-                    }
-                    None => {
-                        // Insert a placeholder for the new module, preventing recursive imports
-                        self.context_mut().modules.insert(module_path.clone(), None);
-
-                        // Run the module chunk
-                        let mut vm = self.spawn_new_vm();
-                        match vm.run(module_chunk) {
-                            Ok(_) => {
-                                if let Some(main) = vm.get_global_function("main") {
-                                    if let Err(error) = vm.run_function(&main, &[]) {
-                                        self.context_mut().modules.remove(&module_path);
-                                        return Err(error);
-                                    }
-                                }
-                            }
-                            Err(error) => {
-                                self.context_mut().modules.remove(&module_path);
-                                return Err(error);
-                            }
-                        }
+                let module = self.load_module(&import_name, compile_result, instruction_ip)?;
+                self.set_register(result_register, Value::Map(module));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Imports an item from a module that was imported by a preceding import instruction
+    //
+    // Items that are available in the module are imported directly, otherwise if the module
+    // was loaded from a package then a nested module with a matching name is loaded.
+    fn run_import_nested(
+        &mut self,
+        result_register: u8,
+        module_register: u8,
+        import_constant: ConstantIndex,
+        instruction_ip: usize,
+    ) -> InstructionResult {
+        if let Value::Map(module) = self.clone_register(module_register) {
+            let import_name = self.value_string_from_constant(import_constant);
+
+            let maybe_item = module.data().get_with_string(&import_name).cloned();
+            if let Some(item) = maybe_item {
+                self.set_register(result_register, item);
+                return Ok(());
+            }
+
+            let package_path =
+                self.context()
+                    .modules
+                    .iter()
+                    .find_map(|(path, loaded)| match loaded {
+                        Some(loaded) if loaded.is_same_instance(&module) => Some(path.clone()),
+                        _ => None,
+                    });
+
+            if let Some(package_path) = package_path {
+                let compile_result = self
+                    .context_mut()
+                    .loader
+                    .compile_nested_module(&import_name, &package_path);
+                let module = self.load_module(&import_name, compile_result, instruction_ip)?;
+                self.set_register(result_register, Value::Map(module));
+                return Ok(());
+            }
+        }
+
+        self.run_access(
+            result_register,
+            module_register,
+            import_constant,
+            instruction_ip,
+        )
+    }
 
+    // Runs a compiled module, or retrieves the module from the cache if it's already been run
+    fn load_module(
+        &mut self,
+        import_name: &str,
+        compile_result: Result<(Arc<Chunk>, PathBuf), LoaderError>,
+        instruction_ip: usize,
+    ) -> Result<ValueMap, Error> {
+        let (module_chunk, module_path) = match compile_result {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                return vm_error!(
+                    self.chunk(),
+                    instruction_ip,
+                    "Failed to import '{}': {}",
+                    import_name,
+                    e
+                )
+            }
+        };
+        let maybe_module = self.context().modules.get(&module_path).cloned();
+        match maybe_module {
+            Some(Some(module)) => Ok(module),
+            Some(None) => vm_error!(
+                self.chunk(),
+                instruction_ip,
+                "Recursive import of module '{}'",
+                import_name
+            ),
+            None => {
+                // Insert a placeholder for the new module, preventing recursive imports
+                self.context_mut().modules.insert(module_path.clone(), None);
+
+                match self.run_module(module_chunk) {
+                    Ok(module_global) => {
                         // Cache the resulting module's global map
-                        let module_global = vm.context().global.clone();
                         self.context_mut()
                             .modules
                             .insert(module_path, Some(module_global.clone()));
-
-                        self.set_register(result_register, Value::Map(module_global));
+                        Ok(module_global)
+                    }
+                    Err(error) => {
+                        self.context_mut().modules.remove(&module_path);
+                        Err(error)
                     }
                 }
             }
         }
+    }
 
-        Ok(())
+    // Runs a module's chunk in a new VM, returning the module's global map
+    //
+    // Modules that were imported while running the module are added to this VM's module cache.
+    fn run_module(&mut self, module_chunk: Arc<Chunk>) -> Result<ValueMap, Error> {
+        let mut vm = self.spawn_new_vm();
+        vm.run(module_chunk)?;
+        if let Some(main) = vm.get_global_function("main") {
+            vm.run_function(&main, &[])?;
+        }
+
+        let module_context = vm.context();
+        let mut context = self.context_mut();
+        context.loader.merge_cache(&module_context.loader);
+        for (path, module) in module_context.modules.iter() {
+            if let Some(module) = module {
+                context
+                    .modules
+                    .entry(path.clone())
+                    .or_insert_with(|| Some(module.clone()));
+            }
+        }
+
+        Ok(module_context.global.clone())
     }
 
     fn run_make_num2(
@@ -1570,13 +2431,16 @@ impl Vm {
 
         let result = if element_count == 1 {
             match self.get_register(element_register) {
-                Number(n) => num2::Num2(*n, *n),
+                Number(n) => {
+                    let n = f64::from(n);
+                    num2::Num2(n, n)
+                }
                 Num2(n) => *n,
                 List(list) => {
                     let mut result = num2::Num2::default();
                     for (i, value) in list.data().iter().take(2).enumerate() {
                         match value {
-                            Number(n) => result[i] = *n,
+                            Number(n) => result[i] = n.into(),
                             unexpected => {
                                 return self.unexpected_type_error(
                                     "num2: Expected Number",
@@ -1600,7 +2464,7 @@ impl Vm {
             let mut result = num2::Num2::default();
             for i in 0..element_count {
                 match self.get_register(element_register + i) {
-                    Number(n) => result[i as usize] = *n,
+                    Number(n) => result[i as usize] = n.into(),
                     unexpected => {
                         return self.unexpected_type_error(
                             "num2: Expected Number, Num2, or List",
@@ -1628,7 +2492,7 @@ impl Vm {
         let result = if element_count == 1 {
             match self.get_register(element_register) {
                 Number(n) => {
-                    let n = *n as f32;
+                    let n = f32::from(n);
                     num4::Num4(n, n, n, n)
                 }
                 Num2(n) => num4::Num4(n[0] as f32, n[1] as f32, 0.0, 0.0),
@@ -1637,7 +2501,7 @@ impl Vm {
                     let mut result = num4::Num4::default();
                     for (i, value) in list.data().iter().take(4).enumerate() {
                         match value {
-                            Number(n) => result[i] = *n as f32,
+                            Number(n) => result[i] = n.into(),
                             unexpected => {
                                 return self.unexpected_type_error(
                                     "num4: Expected Number",
@@ -1661,7 +2525,7 @@ impl Vm {
             let mut result = num4::Num4::default();
             for i in 0..element_count {
                 match self.get_register(element_register + i) {
-                    Number(n) => result[i as usize] = *n as f32,
+                    Number(n) => result[i as usize] = n.into(),
                     unexpected => {
                         return self.unexpected_type_error(
                             "num4: Expected Number, Num4, or List",
@@ -1688,6 +2552,14 @@ impl Vm {
 
         let value = self.clone_register(value_register);
 
+        if let List(list) = self.get_register(list_register) {
+            let added_size = match &value {
+                Range(range) => (range.start.max(range.end) - range.start.min(range.end)) as usize,
+                _ => 1,
+            };
+            self.check_container_size_at(list.len() + added_size, added_size, instruction_ip)?;
+        }
+
         match self.get_register_mut(list_register) {
             List(list) => match value {
                 Range(range) => {
@@ -1730,7 +2602,7 @@ impl Vm {
                 let list_len = list.len();
                 match index_value {
                     Number(index) => {
-                        let u_index = index as usize;
+                        let u_index = usize::from(index);
                         if index >= 0.0 && u_index < list_len {
                             list.data_mut()[u_index] = value;
                         } else {
@@ -1831,8 +2703,13 @@ impl Vm {
         Ok(())
     }
 
-    fn validate_index(&self, n: f64, size: usize, instruction_ip: usize) -> InstructionResult {
-        let index = n as usize;
+    fn validate_index(
+        &self,
+        n: ValueNumber,
+        size: usize,
+        instruction_ip: usize,
+    ) -> InstructionResult {
+        let index = usize::from(n);
 
         if n < 0.0 {
             vm_error!(
@@ -1938,7 +2815,7 @@ impl Vm {
         match (value, index) {
             (List(l), Number(n)) => {
                 self.validate_index(n, l.len(), instruction_ip)?;
-                self.set_register(result_register, l.data()[n as usize].clone());
+                self.set_register(result_register, l.data()[usize::from(n)].clone());
             }
 
             (List(l), Range(IntRange { start, end })) => {
@@ -1960,7 +2837,7 @@ impl Vm {
             }
             (Tuple(t), Number(n)) => {
                 self.validate_index(n, t.data().len(), instruction_ip)?;
-                self.set_register(result_register, t.data()[n as usize].clone());
+                self.set_register(result_register, t.data()[usize::from(n)].clone());
             }
 
             (Tuple(t), Range(IntRange { start, end })) => {
@@ -1976,9 +2853,9 @@ impl Vm {
                 self.set_register(result_register, Tuple(t.data()[start..end].into()))
             }
             (Num2(n), Number(i)) => {
-                let i = i.floor() as usize;
+                let i = usize::from(i.floor());
                 match i {
-                    0 | 1 => self.set_register(result_register, Number(n[i])),
+                    0 | 1 => self.set_register(result_register, Number(n[i].into())),
                     other => {
                         return vm_error!(
                             self.chunk(),
@@ -1990,7 +2867,7 @@ impl Vm {
                 }
             }
             (Num4(n), Number(i)) => {
-                let i = i.floor() as usize;
+                let i = usize::from(i.floor());
                 match i {
                     0 | 1 | 2 | 3 => self.set_register(result_register, Number(n[i].into())),
                     other => {
@@ -2230,87 +3107,15 @@ impl Vm {
         instance_register: Option<u8>,
         instruction_ip: usize,
     ) -> InstructionResult {
-        let RuntimeFunction {
-            chunk,
-            ip: function_ip,
-            arg_count: function_arg_count,
-            instance_function,
-            variadic,
-            captures,
-        } = function;
-
-        // Spawn a VM for the generator
-        let mut generator_vm = self.spawn_shared_vm();
-        // Push a frame for running the generator function
-        generator_vm.push_frame(
-            chunk,
-            function_ip,
-            0, // arguments will be copied starting in register 0
-            captures,
-        );
-
-        let expected_arg_count = match (instance_function, variadic) {
-            (true, true) => function_arg_count - 2,
-            (true, false) | (false, true) => function_arg_count - 1,
-            (false, false) => function_arg_count,
-        };
-
-        // Copy the instance value into the generator vm
-        let arg_offset = if instance_function {
-            if let Some(instance_register) = instance_register {
-                let instance = self.clone_register(instance_register);
-                // Place the instance in the first register of the generator vm
-                generator_vm.set_register(0, instance);
-                1
-            } else {
-                return vm_error!(
-                    self.chunk(),
-                    instruction_ip,
-                    "Missing instance for call to instance function"
-                );
-            }
+        let instance = if function.instance_function {
+            instance_register.map(|register| self.clone_register(register))
         } else {
-            0
+            None
         };
+        let args = self.register_slice(frame_base + 1, call_arg_count).to_vec();
 
-        // Check for variadic arguments, and validate argument count
-        if variadic {
-            if call_arg_count >= expected_arg_count {
-                // Capture the varargs into a tuple and place them in the
-                // generator vm's last arg register
-                let varargs_start = frame_base + 1 + expected_arg_count;
-                let varargs_count = call_arg_count - expected_arg_count;
-                let varargs =
-                    Value::Tuple(self.register_slice(varargs_start, varargs_count).into());
-                generator_vm.set_register(expected_arg_count + arg_offset, varargs);
-            } else {
-                return vm_error!(
-                    self.chunk(),
-                    instruction_ip,
-                    "Insufficient arguments for function call, expected {}, found {}",
-                    expected_arg_count,
-                    call_arg_count,
-                );
-            }
-        } else if call_arg_count != expected_arg_count {
-            return vm_error!(
-                self.chunk(),
-                instruction_ip,
-                "Incorrect argument count, expected {}, found {}",
-                expected_arg_count,
-                call_arg_count,
-            );
-        }
-
-        // Copy any regular (non-instance, non-variadic) arguments into the generator vm
-        for (arg_index, arg) in self
-            .register_slice(frame_base + 1, expected_arg_count)
-            .iter()
-            .cloned()
-            .enumerate()
-        {
-            generator_vm.set_register(arg_index as u8 + arg_offset, arg);
-        }
+        let generator_vm =
+            self.spawn_generator_vm_with_args(&function, instance, &args, instruction_ip)?;
 
         // The args have been cloned into the generator vm, so at this point they can be removed
         self.truncate_registers(frame_base);
@@ -2413,6 +3218,8 @@ impl Vm {
                     );
                 }
 
+                self.check_stack_limits(instruction_ip)?;
+
                 // Set info for when the current frame is returned to
                 self.frame_mut().return_register_and_ip = Some((result_register, self.ip()));
 
@@ -2428,7 +3235,7 @@ impl Vm {
     }
 
     fn run_debug(
-        &self,
+        &mut self,
         register: u8,
         constant: ConstantIndex,
         instruction_ip: usize,
@@ -2442,8 +3249,14 @@ impl Vm {
             (None, Some(path)) => format!("[{}: #ERR] ", path.display()),
             (None, None) => "[#ERR] ".to_string(),
         };
-        let value = self.get_register(register);
-        println!("{}{}: {}", prefix, self.get_constant_str(constant), value);
+        let value = self.clone_register(register);
+        let value_string = self.value_to_string(&value)?;
+        println!(
+            "{}{}: {}",
+            prefix,
+            self.get_constant_str(constant),
+            value_string
+        );
         Ok(())
     }
 
@@ -2610,14 +3423,96 @@ impl Vm {
         op: &Instruction,
         ip: usize,
     ) -> InstructionResult {
-        vm_error!(
-            self.chunk(),
-            ip,
-            "Unable to perform operation {} with '{}' and '{}'",
-            op,
-            type_as_string(lhs),
-            type_as_string(rhs),
-        )
+        match (lhs, overloaded_op_key(op)) {
+            (Value::Map(_), Some(key)) => vm_error!(
+                self.chunk(),
+                ip,
+                "Unable to perform operation {} with '{}' and '{}', the Map doesn't define '{}'",
+                op,
+                type_as_string(lhs),
+                type_as_string(rhs),
+                key,
+            ),
+            _ => vm_error!(
+                self.chunk(),
+                ip,
+                "Unable to perform operation {} with '{}' and '{}'",
+                op,
+                type_as_string(lhs),
+                type_as_string(rhs),
+            ),
+        }
+    }
+
+    // Returns the function that a Map defines for an overloaded operator
+    fn get_overloaded_op(&self, value: &Value, key: &str) -> Option<Value> {
+        match value {
+            Value::Map(map) => map.data().get_with_string(key).cloned(),
+            _ => None,
+        }
+    }
+
+    fn call_overloaded_op(
+        &mut self,
+        op: Value,
+        key: &str,
+        args: &[Value],
+        instruction_ip: usize,
+    ) -> RuntimeResult {
+        let result = match &op {
+            Value::Function(function) => self.spawn_shared_vm().run_function(function, args),
+            Value::ExternalFunction(function) => {
+                let mut vm = self.spawn_shared_vm();
+                vm.value_stack.extend_from_slice(args);
+                (function.function)(
+                    &mut vm,
+                    &Args {
+                        register: 0,
+                        count: args.len() as u8,
+                    },
+                )
+            }
+            unexpected => {
+                return self.unexpected_type_error(
+                    &format!("Expected a function for '{}'", key),
+                    unexpected,
+                    instruction_ip,
+                );
+            }
+        };
+
+        match result {
+            Err(Error::ErrorWithoutLocation { message }) => {
+                vm_error!(self.chunk(), instruction_ip, message)
+            }
+            _ => result,
+        }
+    }
+
+    // Runs the lhs Map's overloaded operator if it's defined, returning true if it was run
+    fn run_overloaded_binary_op(
+        &mut self,
+        register: u8,
+        lhs: u8,
+        rhs: u8,
+        instruction: &Instruction,
+        instruction_ip: usize,
+    ) -> Result<bool, Error> {
+        let key = match overloaded_op_key(instruction) {
+            Some(key) => key,
+            None => return Ok(false),
+        };
+
+        let lhs_value = self.get_register(lhs);
+        match self.get_overloaded_op(lhs_value, key) {
+            Some(op) => {
+                let args = [lhs_value.clone(), self.clone_register(rhs)];
+                let result = self.call_overloaded_op(op, key, &args, instruction_ip)?;
+                self.set_register(register, result);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
     }
 }
 
@@ -2627,6 +3522,25 @@ impl fmt::Debug for Vm {
     }
 }
 
+fn overloaded_op_key(instruction: &Instruction) -> Option<&'static str> {
+    use Instruction::*;
+
+    let result = match instruction {
+        Add { .. } => meta_keys::ADD,
+        Subtract { .. } => meta_keys::SUBTRACT,
+        Multiply { .. } => meta_keys::MULTIPLY,
+        Divide { .. } => meta_keys::DIVIDE,
+        Modulo { .. } => meta_keys::MODULO,
+        Less { .. } => meta_keys::LESS,
+        LessOrEqual { .. } => meta_keys::LESS_OR_EQUAL,
+        Greater { .. } => meta_keys::GREATER,
+        GreaterOrEqual { .. } => meta_keys::GREATER_OR_EQUAL,
+        _ => return None,
+    };
+
+    Some(result)
+}
+
 fn signed_index_to_unsigned(index: i8, size: usize) -> usize {
     if index < 0 {
         size - (index.abs() as usize)
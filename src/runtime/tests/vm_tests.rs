@@ -2,8 +2,12 @@ mod vm {
     use {
         koto_bytecode::chunk_to_string_annotated,
         koto_runtime::{
-            external_error, num2, num4, type_as_string, IntRange, Loader, Value, Value::*,
-            ValueHashMap, ValueList, ValueMap, Vm,
+            external_error, meta_keys, num2, num4, type_as_string, Error, IntRange, Loader, Value,
+            Value::*, ValueHashMap, ValueList, ValueMap, ValueNumber, Vm,
+        },
+        std::{
+            fs,
+            path::{Path, PathBuf},
         },
     };
 
@@ -11,7 +15,7 @@ mod vm {
         let mut vm = Vm::default();
         let mut prelude = vm.context_mut().prelude.clone();
 
-        prelude.add_value("test_value", Number(42.0));
+        prelude.add_value("test_value", Number(42.into()));
         prelude.add_fn("assert", |vm, args| {
             for value in vm.get_args(args).iter() {
                 match value {
@@ -31,6 +35,15 @@ mod vm {
             Ok(Empty)
         });
 
+        prelude.add_map("external_overloads", {
+            let mut map = ValueMap::new();
+            map.add_instance_fn(meta_keys::ADD, |vm, args| match vm.get_args(args) {
+                [Map(_), Number(n)] => Ok(Number(n + &100.into())),
+                _ => external_error!("Expected a Number"),
+            });
+            map
+        });
+
         let print_chunk = |script: &str, chunk| {
             println!("{}\n", script);
             let script_lines = script.lines().collect::<Vec<_>>();
@@ -61,14 +74,70 @@ mod vm {
         }
     }
 
+    fn run_script(vm: &mut Vm, script: &str) -> Result<Value, Error> {
+        run_script_with_path(vm, script, None)
+    }
+
+    fn run_script_with_path(
+        vm: &mut Vm,
+        script: &str,
+        path: Option<PathBuf>,
+    ) -> Result<Value, Error> {
+        let chunk = Loader::default().compile_script(script, &path).unwrap();
+        vm.run(chunk)
+    }
+
+    fn check_error(result: Result<Value, Error>, expected_message: &str) {
+        match result {
+            Err(error) => {
+                let message = error.to_string();
+                assert!(
+                    message.contains(expected_message),
+                    "Expected '{}', found '{}'",
+                    expected_message,
+                    message
+                );
+            }
+            Ok(value) => panic!("Expected an error, found '{}'", value),
+        }
+    }
+
+    // A temporary directory containing a test's scripts, which is removed when dropped
+    struct ScriptDir(tempfile::TempDir);
+
+    impl std::ops::Deref for ScriptDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            self.0.path()
+        }
+    }
+
+    // Makes a temporary directory for a test, containing the provided files
+    //
+    // The files' paths are relative to the directory, with parent directories being created
+    // as needed. Each directory is unique, with the name used as a prefix.
+    fn make_script_dir(name: &str, files: &[(&str, &str)]) -> ScriptDir {
+        let dir = tempfile::Builder::new()
+            .prefix(&format!("koto_{}_", name))
+            .tempdir()
+            .unwrap();
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        ScriptDir(dir)
+    }
+
     fn number_list<T>(values: &[T]) -> Value
     where
         T: Copy,
-        f64: From<T>,
+        ValueNumber: From<T>,
     {
         let values = values
             .iter()
-            .map(|n| Number(f64::from(*n)))
+            .map(|n| Number((*n).into()))
             .collect::<Vec<_>>();
         value_list(&values)
     }
@@ -76,11 +145,11 @@ mod vm {
     fn number_tuple<T>(values: &[T]) -> Value
     where
         T: Copy,
-        f64: From<T>,
+        ValueNumber: From<T>,
     {
         let values = values
             .iter()
-            .map(|n| Number(f64::from(*n)))
+            .map(|n| Number((*n).into()))
             .collect::<Vec<_>>();
         value_tuple(&values)
     }
@@ -125,7 +194,7 @@ mod vm {
 
         #[test]
         fn number() {
-            test_script("24.0", Number(24.0));
+            test_script("24.0", Number(24.into()));
         }
 
         #[test]
@@ -139,12 +208,12 @@ mod vm {
 
         #[test]
         fn add_multiply() {
-            test_script("1 + 2 * 3 + 4", Number(11.0));
+            test_script("1 + 2 * 3 + 4", Number(11.into()));
         }
 
         #[test]
         fn subtract_divide_modulo() {
-            test_script("(20 - 2) / 3 % 4", Number(2.0));
+            test_script("(20 - 2) / 3 % 4", Number(2.into()));
         }
 
         #[test]
@@ -175,7 +244,7 @@ mod vm {
             let script = "
 a = 1 * 3
 a + 1";
-            test_script(script, Number(4.0));
+            test_script(script, Number(4.into()));
         }
 
         #[test]
@@ -183,7 +252,7 @@ a + 1";
             let script = "
 a = 99
 -a";
-            test_script(script, Number(-99.0));
+            test_script(script, Number((-99).into()));
         }
     }
 
@@ -273,7 +342,7 @@ a = 1
             let script = "
 a = [1, 2, 3]
 a[1]";
-            test_script(script, Number(2.0));
+            test_script(script, Number(2.into()));
         }
 
         #[test]
@@ -391,7 +460,7 @@ l = [1, 2, 3]
 l2 = l
 l[1] = -1
 l2[1]";
-            test_script(script, Number(-1.0));
+            test_script(script, Number((-1).into()));
         }
 
         #[test]
@@ -401,7 +470,7 @@ l = [1, 2, 3]
 l2 = copy l
 l[1] = -1
 l2[1]";
-            test_script(script, Number(2.0));
+            test_script(script, Number(2.into()));
         }
     }
 
@@ -430,7 +499,10 @@ x";
             let script = "
 a, b, c = [7, 8]
 a, b, c";
-            test_script(script, value_tuple(&[Number(7.0), Number(8.0), Empty]));
+            test_script(
+                script,
+                value_tuple(&[Number(7.into()), Number(8.into()), Empty]),
+            );
         }
 
         #[test]
@@ -458,7 +530,7 @@ else if 1 < 2
 else
   99
 x";
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
 
         #[test]
@@ -471,7 +543,7 @@ else if 1 < 2
 else
   99
 x";
-            test_script(script, Number(-1.0));
+            test_script(script, Number((-1).into()));
         }
 
         #[test]
@@ -484,7 +556,7 @@ else if 2 < 1
 else
   99
 x";
-            test_script(script, Number(99.0));
+            test_script(script, Number(99.into()));
         }
 
         #[test]
@@ -517,7 +589,7 @@ if false
 else if true
   99
 ";
-            test_script(script, Number(99.0));
+            test_script(script, Number(99.into()));
         }
 
         #[test]
@@ -534,7 +606,7 @@ else if true
 else
   0
 ";
-            test_script(script, Number(100.0));
+            test_script(script, Number(100.into()));
         }
     }
 
@@ -549,7 +621,7 @@ x = match 0 == 1
   false then 99
 x
 ";
-            test_script(script, Number(99.0));
+            test_script(script, Number(99.into()));
         }
 
         #[test]
@@ -562,7 +634,7 @@ match x % 3, x % 5
   _, 0 then "Buzz"
   _ then x
 "#;
-            test_script(script, Number(11.0));
+            test_script(script, Number(11.into()));
         }
 
         #[test]
@@ -576,7 +648,7 @@ match x
   y if y == "hello"
     42
 "#;
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
 
         #[test]
@@ -588,7 +660,7 @@ match 42
   21 or 42 then 33
   _ then 44
 ";
-            test_script(script, Number(33.0));
+            test_script(script, Number(33.into()));
         }
 
         #[test]
@@ -599,7 +671,7 @@ match (1, (2, 3), 4)
   (_, (a, b), _) then a + b
   _ then 123
 ";
-            test_script(script, Number(5.0));
+            test_script(script, Number(5.into()));
         }
 
         #[test]
@@ -611,7 +683,7 @@ match [1, [2, 3], [4, 5, 6]]
   [1, [x, 3], [_, 5, y]] then x + y
   _ then 123
 ";
-            test_script(script, Number(8.0));
+            test_script(script, Number(8.into()));
         }
 
         #[test]
@@ -623,7 +695,7 @@ match x
   [x, y] or [x, y, z] then 99
   _ then -1
 ";
-            test_script(script, Number(123.0));
+            test_script(script, Number(123.into()));
         }
 
         #[test]
@@ -636,7 +708,7 @@ match x
   [1, ...] then 1
   _ then 123
 ";
-            test_script(script, Number(1.0));
+            test_script(script, Number(1.into()));
         }
 
         #[test]
@@ -688,7 +760,7 @@ match 0, 1
   0, _ or 1, _ then -4 # The first alternative (0, _) should match
   _ then -5
 ";
-            test_script(script, Number(-4.0));
+            test_script(script, Number((-4).into()));
         }
 
         #[test]
@@ -701,7 +773,7 @@ match 0, 1
   0, _ or 1, _ then -4
   _ then -5
 ";
-            test_script(script, Number(-3.0));
+            test_script(script, Number((-3).into()));
         }
 
         #[test]
@@ -719,7 +791,7 @@ m = match "hello"
     value_2: 7
 m.value_1 + m.value_2
 "#;
-            test_script(script, Number(24.0));
+            test_script(script, Number(24.into()));
         }
     }
 
@@ -731,7 +803,7 @@ m.value_1 + m.value_2
             let script = "
 import test_value
 test_value";
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
 
         #[test]
@@ -751,6 +823,58 @@ assert (1 + 1 == 2) (2 < 3)";
         }
     }
 
+    mod operator_overloading {
+        use super::*;
+
+        #[test]
+        fn add() {
+            let script = r#"
+x = {value: 10, "@+": |self, other| self.value + other}
+x + 32"#;
+            test_script(script, Number(42.into()));
+        }
+
+        #[test]
+        fn add_takes_priority_over_map_merging() {
+            let script = r#"
+x = {"@+": |self, other| "added"}
+x + {}"#;
+            test_script(script, Str("added".into()));
+        }
+
+        #[test]
+        fn external_function() {
+            let script = "
+import external_overloads
+external_overloads + 1";
+            test_script(script, Number(101.into()));
+        }
+
+        #[test]
+        fn less() {
+            let script = r#"
+x = {value: 10, "@<": |self, other| self.value < other}
+x < 11"#;
+            test_script(script, Bool(true));
+        }
+
+        #[test]
+        fn not_equal_derived_from_equal() {
+            let script = r#"
+x = {value: 10, "@==": |self, other| self.value == other}
+(x == 10), (x != 10)"#;
+            test_script(script, value_tuple(&[Bool(true), Bool(false)]));
+        }
+
+        #[test]
+        fn negate() {
+            let script = r#"
+x = {value: 10, "@negate": |self| -self.value}
+-x"#;
+            test_script(script, Number((-10).into()));
+        }
+    }
+
     mod functions {
         use super::*;
 
@@ -759,7 +883,7 @@ assert (1 + 1 == 2) (2 < 3)";
             let script = "
 f = || 42
 f()";
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
 
         #[test]
@@ -767,7 +891,7 @@ f()";
             let script = "
 square = |x| x * x
 square 8";
-            test_script(script, Number(64.0));
+            test_script(script, Number(64.into()));
         }
 
         #[test]
@@ -776,7 +900,7 @@ square 8";
 add = |a, b|
   a + b
 add 5 6";
-            test_script(script, Number(11.0));
+            test_script(script, Number(11.into()));
         }
 
         #[test]
@@ -785,7 +909,7 @@ add 5 6";
 add = |a, b|
   a + b
 add(5, 6)";
-            test_script(script, Number(11.0));
+            test_script(script, Number(11.into()));
         }
 
         #[test]
@@ -794,7 +918,7 @@ add(5, 6)";
 add = |a, b|
   a + b
 add(5, add 6 7)";
-            test_script(script, Number(18.0));
+            test_script(script, Number(18.into()));
         }
 
         #[test]
@@ -803,7 +927,7 @@ add(5, add 6 7)";
 f = |a, b...|
   a + b.fold 0 |x, y| x + y
 f 5 10 20 30";
-            test_script(script, Number(65.0));
+            test_script(script, Number(65.into()));
         }
 
         #[test]
@@ -813,7 +937,7 @@ add = |a, b|
   add2 = |x, y| x + y
   add2 a b
 add 10 20";
-            test_script(script, Number(30.0));
+            test_script(script, Number(30.into()));
         }
 
         #[test]
@@ -821,7 +945,7 @@ add 10 20";
             let script = "
 add = |a, b| a + b
 add 10 (add 20 30)";
-            test_script(script, Number(60.0));
+            test_script(script, Number(60.into()));
         }
 
         #[test]
@@ -834,7 +958,7 @@ f = |n|
     f n - 1
 f 4
 ";
-            test_script(script, Number(0.0));
+            test_script(script, Number(0.into()));
         }
 
         #[test]
@@ -849,7 +973,7 @@ fib = |n|
     (fib n - 1) + (fib n - 2)
 fib 4
 ";
-            test_script(script, Number(3.0));
+            test_script(script, Number(3.into()));
         }
 
         #[test]
@@ -891,7 +1015,7 @@ f = |x|
     return x * -1
   x
 f -42";
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
 
         #[test]
@@ -902,7 +1026,7 @@ f = ||
     foo: 42
     bar: 99
 f().bar";
-            test_script(script, Number(99.0));
+            test_script(script, Number(99.into()));
         }
 
         #[test]
@@ -912,7 +1036,7 @@ f = |x|
   inner = || x * x
   inner()
 f 3";
-            test_script(script, Number(9.0));
+            test_script(script, Number(9.into()));
         }
 
         #[test]
@@ -924,7 +1048,7 @@ f = ||
   data = () # reassignment doesn't affect the original copy of data
 f()
 data[1]";
-            test_script(script, Number(99.0));
+            test_script(script, Number(99.into()));
         }
 
         #[test]
@@ -938,7 +1062,7 @@ capture_test = |a, b, c|
   b, c = (), () # inner and inner2 have captured their own copies of b and c
   inner()
 capture_test 1 2 3";
-            test_script(script, Number(6.0));
+            test_script(script, Number(6.into()));
         }
 
         #[test]
@@ -955,7 +1079,7 @@ assert c() == 2
 assert c2() == 1
 assert c() == 3
 c2()";
-            test_script(script, Number(2.0));
+            test_script(script, Number(2.into()));
         }
 
         #[test]
@@ -977,7 +1101,7 @@ f = ||
   export x = 42
 f()
 x";
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
 
         #[test]
@@ -986,7 +1110,7 @@ x";
 f = |n| n
 a, b = f 1, f 2
 a";
-            test_script(script, Number(1.0));
+            test_script(script, Number(1.into()));
         }
 
         #[test]
@@ -1001,7 +1125,7 @@ f2 = ||
     x
 f3 = |x| f2() x
 f3 1";
-            test_script(script, Number(1.0));
+            test_script(script, Number(1.into()));
         }
 
         #[test]
@@ -1014,7 +1138,7 @@ f2 = ||
     x
 f3 = |x| f2() x
 f3 1";
-            test_script(script, Number(1.0));
+            test_script(script, Number(1.into()));
         }
 
         #[test]
@@ -1025,7 +1149,7 @@ f3 1";
 f = || 1, 2, 3
 f().fold 0 |x, n| x += n
 ";
-            test_script(script, Number(6.0));
+            test_script(script, Number(6.into()));
         }
     }
 
@@ -1039,7 +1163,7 @@ count = 0
 while count < 10
   count += 1
 count";
-            test_script(script, Number(10.0));
+            test_script(script, Number(10.into()));
         }
 
         #[test]
@@ -1049,7 +1173,7 @@ count = 10
 until count == 20
   count += 1
 count";
-            test_script(script, Number(20.0));
+            test_script(script, Number(20.into()));
         }
 
         #[test]
@@ -1059,7 +1183,7 @@ count = 32
 for _ in 0..10
   count += 1
 count";
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
 
         #[test]
@@ -1069,7 +1193,7 @@ sum = 0
 for a in [10, 20, 30, 40]
   sum += a
 sum";
-            test_script(script, Number(100.0));
+            test_script(script, Number(100.into()));
         }
 
         #[test]
@@ -1081,7 +1205,7 @@ for i in 1..10
     break
   sum += i
 sum";
-            test_script(script, Number(10.0));
+            test_script(script, Number(10.into()));
         }
 
         #[test]
@@ -1094,7 +1218,7 @@ for i in [1, 2, 3]
       break
     sum += i
 sum";
-            test_script(script, Number(12.0));
+            test_script(script, Number(12.into()));
         }
 
         #[test]
@@ -1106,7 +1230,7 @@ for i in 1..10
     continue
   sum += i
 sum";
-            test_script(script, Number(15.0));
+            test_script(script, Number(15.into()));
         }
 
         #[test]
@@ -1119,7 +1243,7 @@ for i in [2, 4, 6]
       continue
     sum += i
 sum";
-            test_script(script, Number(24.0));
+            test_script(script, Number(24.into()));
         }
 
         #[test]
@@ -1131,7 +1255,7 @@ while (i += 1) < 1000000
     break
   sum += 1
 sum";
-            test_script(script, Number(5.0));
+            test_script(script, Number(5.into()));
         }
 
         #[test]
@@ -1143,7 +1267,7 @@ while (i += 1) < 10
     continue
   sum += 1
 sum";
-            test_script(script, Number(6.0));
+            test_script(script, Number(6.into()));
         }
 
         #[test]
@@ -1157,7 +1281,7 @@ loop
   else
     break
 i";
-            test_script(script, Number(5.0));
+            test_script(script, Number(5.into()));
         }
 
         #[test]
@@ -1170,7 +1294,7 @@ f = ||
         return i
   -1
 f()";
-            test_script(script, Number(5.0));
+            test_script(script, Number(5.into()));
         }
 
         #[test]
@@ -1181,7 +1305,7 @@ for a, b in ((1, 2), (3, 4))
   sum += a + b
 sum
 ";
-            test_script(script, Number(10.0));
+            test_script(script, Number(10.into()));
         }
     }
 
@@ -1196,7 +1320,7 @@ sum
         #[test]
         fn from_literals() {
             let mut result_data = ValueHashMap::new();
-            result_data.add_value("foo", Number(42.0));
+            result_data.add_value("foo", Number(42.into()));
             result_data.add_value("bar", Str("baz".into()));
 
             test_script(
@@ -1210,7 +1334,7 @@ sum
             let script = "
 m = {foo: -1}
 m.foo";
-            test_script(script, Number(-1.0));
+            test_script(script, Number((-1).into()));
         }
 
         #[test]
@@ -1219,7 +1343,7 @@ m.foo";
 m = {}
 m.foo = 42
 m.foo";
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
 
         #[test]
@@ -1228,7 +1352,7 @@ m.foo";
 m = {bar: -1}
 m.bar = 99
 m.bar";
-            test_script(script, Number(99.0));
+            test_script(script, Number(99.into()));
         }
 
         #[test]
@@ -1237,7 +1361,7 @@ m.bar";
 foo, baz = 42, -1
 m = {foo, bar: 99, baz}
 m.baz";
-            test_script(script, Number(-1.0));
+            test_script(script, Number((-1).into()));
         }
 
         #[test]
@@ -1247,7 +1371,7 @@ make_o = ||
   {foo: 42, get_foo: |self| self.foo}
 o = make_o()
 o.get_foo()";
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
 
         #[test]
@@ -1259,7 +1383,7 @@ make_o = ||
 o = make_o()
 o.set_foo 10 20
 o.foo";
-            test_script(script, Number(30.0));
+            test_script(script, Number(30.into()));
         }
 
         #[test]
@@ -1296,7 +1420,7 @@ m = {foo: 42}
 m2 = m
 m.foo = -1
 m2.foo";
-            test_script(script, Number(-1.0));
+            test_script(script, Number((-1).into()));
         }
 
         #[test]
@@ -1306,7 +1430,7 @@ m = {foo: 42}
 m2 = copy m
 m.foo = -1
 m2.foo";
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
     }
 
@@ -1318,7 +1442,7 @@ m2.foo";
             let script = "
 m = {x: [100, 200]}
 m.x[1]";
-            test_script(script, Number(200.0));
+            test_script(script, Number(200.into()));
         }
 
         #[test]
@@ -1327,7 +1451,7 @@ m.x[1]";
 m = {foo: 99}
 l = [m, m, m]
 l[2].foo";
-            test_script(script, Number(99.0));
+            test_script(script, Number(99.into()));
         }
 
         #[test]
@@ -1337,7 +1461,7 @@ m = {bar: 0}
 l = [m, m, m]
 l[1].bar = -1
 l[1].bar";
-            test_script(script, Number(-1.0));
+            test_script(script, Number((-1).into()));
         }
 
         #[test]
@@ -1347,7 +1471,7 @@ m = {foo: [1, 2, 3]}
 l = [m, m, m]
 l[2].foo[0] = 99
 l[2].foo[0]";
-            test_script(script, Number(99.0));
+            test_script(script, Number(99.into()));
         }
 
         #[test]
@@ -1355,7 +1479,7 @@ l[2].foo[0]";
             let script = "
 m = {get_map: || { foo: -1 }}
 m.get_map().foo";
-            test_script(script, Number(-1.0));
+            test_script(script, Number((-1).into()));
         }
 
         #[test]
@@ -1366,7 +1490,7 @@ m =
     xs.fold x |a, b| a + b
 m.foo 1 2 3
 ";
-            test_script(script, Number(6.0));
+            test_script(script, Number(6.into()));
         }
 
         #[test]
@@ -1378,7 +1502,7 @@ m =
   offset: 10
 m.foo 1 2 3
 ";
-            test_script(script, Number(16.0));
+            test_script(script, Number(16.into()));
         }
 
         #[test]
@@ -1403,7 +1527,7 @@ m = {foo: {bar: -1}}
 m2 = copy m.foo
 m.foo.bar = 99
 m2.bar";
-            test_script(script, Number(-1.0));
+            test_script(script, Number((-1).into()));
         }
 
         #[test]
@@ -1413,7 +1537,7 @@ m = {foo: {bar: 88}, get_foo: |self| self.foo}
 m2 = copy (m.get_foo())
 m.get_foo().bar = 99
 m2.bar";
-            test_script(script, Number(88.0));
+            test_script(script, Number(88.into()));
         }
 
         #[test]
@@ -1425,7 +1549,7 @@ make_map = ||
 m = make_map()
 m.foo
 ";
-            test_script(script, Number(42.0));
+            test_script(script, Number(42.into()));
         }
 
         #[test]
@@ -1440,7 +1564,7 @@ result = {}
   .consume()
 result.size()
 "#;
-            test_script(script, Number(5.0));
+            test_script(script, Number(5.into()));
         }
 
         #[test]
@@ -1461,7 +1585,7 @@ foo = |range, x| range.size() + x
 min, max = 0, 10
 foo min..max 20
 "#;
-            test_script(script, Number(30.0));
+            test_script(script, Number(30.into()));
         }
     }
 
@@ -1486,7 +1610,7 @@ fold = |xs, f|
     result = f result x
   result
 fold 0..5 |n, _| n + 1";
-            test_script(script, Number(5.0));
+            test_script(script, Number(5.into()));
         }
     }
 
@@ -1603,7 +1727,7 @@ x = num2 1 -2
             let script = "
 x = num2 4 5
 x[1]";
-            test_script(script, Number(5.0));
+            test_script(script, Number(5.into()));
         }
     }
 
@@ -1684,7 +1808,7 @@ x = num4 1 -2 3 -4
             let script = "
 x = num4 9 8 7 6
 x[3]";
-            test_script(script, Number(6.0));
+            test_script(script, Number(6.into()));
         }
     }
 
@@ -1719,6 +1843,29 @@ x[3]";
             test_script(r#""héllö42" >= "héllö11""#, Bool(true));
             test_script(r#""hello1" >= "hello42""#, Bool(false));
         }
+
+        #[test]
+        fn interpolation() {
+            let script = r#"
+x = 42
+"x: {x}, x * 2: {x * 2}"
+"#;
+            test_script(script, string("x: 42, x * 2: 84"));
+        }
+
+        #[test]
+        fn interpolation_with_display_overload() {
+            let script = r#"
+foo = {"@display": |self| "Foo"}
+"{foo}!"
+"#;
+            test_script(script, string("Foo!"));
+        }
+
+        #[test]
+        fn interpolation_with_escaped_brace() {
+            test_script(r#""\{x}""#, string("{x}"));
+        }
     }
 
     mod error_recovery {
@@ -1734,7 +1881,7 @@ try
 catch _
   x + 1
 ";
-            test_script(script, Number(3.0));
+            test_script(script, Number(3.into()));
         }
 
         #[test]
@@ -1747,7 +1894,7 @@ catch e
 finally
   99
 ";
-            test_script(script, Number(99.0));
+            test_script(script, Number(99.into()));
         }
 
         #[test]
@@ -1765,7 +1912,1280 @@ try
 catch _
   x += 1
 ";
-            test_script(script, Number(4.0));
+            test_script(script, Number(4.into()));
+        }
+
+        #[test]
+        fn throw_and_catch_map() {
+            let script = r#"
+try
+  throw {code: 42, message: "Oops"}
+catch error
+  error.code
+"#;
+            test_script(script, Number(42.into()));
+        }
+
+        #[test]
+        fn throw_from_nested_function() {
+            let script = r#"
+f = |x|
+  if x > 1
+    throw "x is too big"
+  x
+try
+  f 2
+catch error
+  error
+"#;
+            test_script(script, string("x is too big"));
+        }
+
+        #[test]
+        fn catch_runtime_error_as_string() {
+            let script = "
+try
+  x
+catch error
+  error
+";
+            test_script(script, string("'x' not found"));
+        }
+
+        #[test]
+        fn uncaught_thrown_value() {
+            let mut vm = Vm::default();
+            let chunk = Loader::default()
+                .compile_script("throw 1, 2", &None)
+                .unwrap();
+
+            match vm.run(chunk) {
+                Err(error) => assert_eq!(error.thrown_value(), Some(&number_tuple(&[1, 2]))),
+                Ok(value) => panic!("Expected an error, found '{}'", value),
+            }
+        }
+    }
+    mod debugger {
+        use {
+            super::*,
+            koto_runtime::{Breakpoint, DebugCommand, Debugger, PauseReason},
+            std::sync::{Arc, Mutex},
+        };
+
+        // Runs the script with a debugger that responds to each pause with the next command,
+        // returning the lines that execution paused at along with the pause reasons.
+        fn debug_script(
+            script: &str,
+            breakpoints: &[u32],
+            commands: &[DebugCommand],
+        ) -> Vec<(u32, PauseReason)> {
+            let pauses = Arc::new(Mutex::new(Vec::new()));
+
+            let mut debugger = Debugger::new({
+                let pauses = pauses.clone();
+                let mut commands = Vec::from(commands).into_iter();
+                move |pause, _| {
+                    let line = pause.call_stack[0].line().unwrap();
+                    pauses.lock().unwrap().push((line, pause.reason));
+                    commands.next().unwrap_or(DebugCommand::Continue)
+                }
+            });
+            for line in breakpoints {
+                debugger.add_breakpoint(Breakpoint::new(None, *line));
+            }
+            if breakpoints.is_empty() {
+                debugger.pause();
+            }
+
+            let mut vm = Vm::default();
+            vm.set_debugger(Some(debugger));
+            let chunk = Loader::default().compile_script(script, &None).unwrap();
+            vm.run(chunk).unwrap();
+
+            let result = pauses.lock().unwrap().clone();
+            result
+        }
+
+        const SCRIPT: &str = "
+f = |x|
+  y = x * 2
+  y + 1
+a = f 1
+b = f a
+a + b
+";
+
+        #[test]
+        fn breakpoints() {
+            use PauseReason::Breakpoint;
+            let pauses = debug_script(SCRIPT, &[3, 6], &[]);
+            assert_eq!(
+                pauses,
+                vec![(3, Breakpoint), (6, Breakpoint), (3, Breakpoint)]
+            );
+        }
+
+        #[test]
+        fn step_in() {
+            use {DebugCommand::*, PauseReason::Step};
+            let pauses = debug_script(SCRIPT, &[], &[StepIn, StepIn, StepIn, StepIn, Continue]);
+            assert_eq!(
+                pauses,
+                vec![(2, Step), (5, Step), (3, Step), (4, Step), (6, Step)]
+            );
+        }
+
+        #[test]
+        fn breakpoints_in_loops() {
+            use PauseReason::Breakpoint;
+            let script = "
+x = 0
+loop
+  if (x += 1) == 3 then break
+x + 1
+";
+            // The loop jumps back to the start of line 4, which should be treated as a new visit
+            let pauses = debug_script(script, &[4], &[]);
+            assert_eq!(pauses, vec![(4, Breakpoint); 3]);
+        }
+
+        #[test]
+        fn step_over_and_out() {
+            use {DebugCommand::*, PauseReason::*};
+            let pauses = debug_script(SCRIPT, &[3], &[StepOut, StepOver, StepOut, Continue]);
+            assert_eq!(
+                pauses,
+                vec![(3, Breakpoint), (6, Step), (3, Breakpoint), (7, Step)]
+            );
+        }
+
+        #[test]
+        fn inspecting_registers() {
+            let registers = Arc::new(Mutex::new(Vec::new()));
+
+            let mut debugger = Debugger::new({
+                let registers = registers.clone();
+                move |pause, _| {
+                    *registers.lock().unwrap() = pause.call_stack[0].registers.clone();
+                    DebugCommand::Continue
+                }
+            });
+            debugger.add_breakpoint(Breakpoint::new(None, 3));
+
+            let mut vm = Vm::default();
+            vm.set_debugger(Some(debugger));
+            let chunk = Loader::default()
+                .compile_script("a = 42\nb = 99\na + b", &None)
+                .unwrap();
+            vm.run(chunk).unwrap();
+
+            let registers = registers.lock().unwrap();
+            assert_eq!(registers[0], Number(42.into()));
+            assert_eq!(registers[1], Number(99.into()));
+        }
+
+        #[test]
+        fn stop() {
+            let mut debugger = Debugger::new(|_, _| DebugCommand::Stop);
+            debugger.pause();
+
+            let mut vm = Vm::default();
+            vm.set_debugger(Some(debugger));
+            let chunk = Loader::default()
+                .compile_script("try\n  1 + 1\ncatch _\n  2", &None)
+                .unwrap();
+            assert!(vm.run(chunk).is_err());
+        }
+    }
+
+    mod execution_limits {
+        use {
+            super::*,
+            koto_runtime::InterruptReason,
+            std::{
+                thread,
+                time::{Duration, Instant},
+            },
+        };
+
+        fn check_interruption(result: Result<Value, Error>, expected: InterruptReason) {
+            match result {
+                Err(Error::Interrupted { reason, .. }) => assert_eq!(reason, expected),
+                Err(error) if error.is_interruption() => {}
+                Err(error) => panic!("Expected an interruption, found '{}'", error),
+                Ok(value) => panic!("Expected an interruption, found '{}'", value),
+            }
+        }
+
+        #[test]
+        fn instruction_limit() {
+            let mut vm = Vm::default();
+            vm.set_instruction_limit(Some(1000));
+            check_interruption(
+                run_script(&mut vm, "loop\n  x = 1"),
+                InterruptReason::InstructionLimit,
+            );
+            assert_eq!(vm.remaining_instructions(), Some(0));
+
+            // The limit remains in place until it's reset
+            check_interruption(
+                run_script(&mut vm, "1 + 1"),
+                InterruptReason::InstructionLimit,
+            );
+
+            vm.set_instruction_limit(Some(1000));
+            assert_eq!(run_script(&mut vm, "1 + 1").unwrap(), Number(2.into()));
+            assert!(vm.remaining_instructions().unwrap() < 1000);
+
+            vm.set_instruction_limit(None);
+            assert_eq!(vm.remaining_instructions(), None);
+        }
+
+        #[test]
+        fn instruction_limit_shared_between_threads() {
+            let mut vm = Vm::default();
+            vm.set_instruction_limit(Some(10_000));
+
+            let threads = (0..4)
+                .map(|_| {
+                    let mut thread_vm = vm.spawn_shared_vm();
+                    thread::spawn(move || {
+                        check_interruption(
+                            run_script(&mut thread_vm, "loop\n  x = 1"),
+                            InterruptReason::InstructionLimit,
+                        );
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for thread in threads {
+                thread.join().unwrap();
+            }
+
+            // The shared budget is exhausted without wrapping around
+            assert_eq!(vm.remaining_instructions(), Some(0));
+        }
+
+        #[test]
+        fn interruptions_cant_be_caught() {
+            let script = "
+try
+  loop
+    x = 1
+catch error
+  42
+";
+            let mut vm = Vm::default();
+            vm.set_instruction_limit(Some(1000));
+            check_interruption(
+                run_script(&mut vm, script),
+                InterruptReason::InstructionLimit,
+            );
+        }
+
+        #[test]
+        fn instruction_limit_in_core_lib_callback() {
+            let script = "
+try
+  [1, 2, 3].transform |n|
+    loop
+      n
+catch error
+  42
+";
+            let mut vm = Vm::default();
+            vm.set_instruction_limit(Some(1000));
+            check_interruption(
+                run_script(&mut vm, script),
+                InterruptReason::InstructionLimit,
+            );
+        }
+
+        #[test]
+        fn deadline() {
+            let mut vm = Vm::default();
+            vm.set_deadline(Some(Instant::now() + Duration::from_millis(20)));
+            check_interruption(
+                run_script(&mut vm, "loop\n  x = 1"),
+                InterruptReason::Deadline,
+            );
+
+            vm.set_deadline(None);
+            assert_eq!(run_script(&mut vm, "1 + 1").unwrap(), Number(2.into()));
+        }
+
+        #[test]
+        fn interrupt_from_another_thread() {
+            let mut vm = Vm::default();
+            let handle = vm.interrupt_handle();
+
+            let interrupt_thread = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                handle.interrupt();
+            });
+
+            check_interruption(
+                run_script(&mut vm, "loop\n  x = 1"),
+                InterruptReason::Interrupted,
+            );
+            interrupt_thread.join().unwrap();
+
+            // The interruption only applies once
+            assert_eq!(run_script(&mut vm, "1 + 1").unwrap(), Number(2.into()));
+        }
+
+        #[test]
+        fn interrupt_in_display_called_by_string_format() {
+            let script = "
+foo = {\"@display\": |self|
+  loop
+    x = 1
+}
+try
+  \"{}\".format foo
+catch error
+  42
+";
+            let mut vm = Vm::default();
+            let handle = vm.interrupt_handle();
+
+            let interrupt_thread = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                handle.interrupt();
+            });
+
+            check_interruption(run_script(&mut vm, script), InterruptReason::Interrupted);
+            interrupt_thread.join().unwrap();
+        }
+    }
+
+    mod memory_limits {
+        use {super::*, koto_runtime::MemoryLimits};
+
+        fn vm_with_limits(limits: MemoryLimits) -> Vm {
+            let mut vm = Vm::default();
+            vm.set_memory_limits(limits);
+            vm
+        }
+
+        #[test]
+        fn call_stack_depth() {
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_call_stack_depth: Some(100),
+                ..Default::default()
+            });
+            check_error(
+                run_script(&mut vm, "f = |n| f n + 1\nf 0"),
+                "Call stack depth limit exceeded",
+            );
+
+            let script = "
+f = |n|
+  if n == 0
+    0
+  else
+    f n - 1
+f 50
+";
+            assert_eq!(run_script(&mut vm, script).unwrap(), Number(0.into()));
+        }
+
+        #[test]
+        fn call_stack_depth_across_core_lib_callbacks() {
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_call_stack_depth: Some(100),
+                ..Default::default()
+            });
+            check_error(
+                run_script(&mut vm, "f = |n| [n].each(|x| f x + 1).to_list()\nf 0"),
+                "Call stack depth limit exceeded",
+            );
+        }
+
+        #[test]
+        fn call_stack_depth_error_can_be_caught() {
+            let script = "
+f = |n| f n + 1
+try
+  f 0
+catch error
+  42
+";
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_call_stack_depth: Some(100),
+                ..Default::default()
+            });
+            assert_eq!(run_script(&mut vm, script).unwrap(), Number(42.into()));
+        }
+
+        #[test]
+        fn value_stack_size() {
+            let script = "
+f = |n|
+  a, b, c, d = 1, 2, 3, 4
+  f n + a + b + c + d
+f 0
+";
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_value_stack_size: Some(1000),
+                ..Default::default()
+            });
+            check_error(
+                run_script(&mut vm, script),
+                "Value stack size limit exceeded",
+            );
+        }
+
+        #[test]
+        fn list_resize() {
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_container_size: Some(100),
+                ..Default::default()
+            });
+            check_error(
+                run_script(&mut vm, "x = []\nx.resize(1000000000000, 0)"),
+                "Container size limit exceeded",
+            );
+            check_error(
+                run_script(&mut vm, "x = []\nx.resize(101, 0)"),
+                "Container size limit exceeded",
+            );
+            assert_eq!(
+                run_script(&mut vm, "x = []\nx.resize(100, 0)\nx.size()").unwrap(),
+                Number(100.into())
+            );
+        }
+
+        #[test]
+        fn list_push() {
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_container_size: Some(100),
+                ..Default::default()
+            });
+            check_error(
+                run_script(&mut vm, "x = []\nloop\n  x.push 1"),
+                "Container size limit exceeded",
+            );
+            check_error(
+                run_script(&mut vm, "[0..1000000000000]"),
+                "Container size limit exceeded",
+            );
+            check_error(
+                run_script(&mut vm, "(0..1000).to_list()"),
+                "Container size limit exceeded",
+            );
+        }
+
+        #[test]
+        fn string_concatenation() {
+            let script = "
+x = \"abc\"
+loop
+  x = x + x
+";
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_container_size: Some(1000),
+                ..Default::default()
+            });
+            check_error(run_script(&mut vm, script), "Container size limit exceeded");
+        }
+
+        #[test]
+        fn map_insert() {
+            let script = "
+x = {}
+for i in 0..1000
+  x.insert i, i
+";
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_container_size: Some(100),
+                ..Default::default()
+            });
+            check_error(run_script(&mut vm, script), "Container size limit exceeded");
+        }
+
+        #[test]
+        fn iterator_cycle() {
+            let script = "
+for x in (0..1000).cycle()
+  x
+";
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_container_size: Some(100),
+                ..Default::default()
+            });
+            check_error(run_script(&mut vm, script), "Container size limit exceeded");
+        }
+
+        #[test]
+        fn total_container_size() {
+            // Each list is within the container size limit, but the total isn't
+            let script = "
+lists = []
+for i in 0..100
+  x = []
+  x.resize(50, \"x\")
+  lists.push x
+";
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_container_size: Some(100),
+                max_total_container_size: Some(1000),
+                ..Default::default()
+            });
+            check_error(
+                run_script(&mut vm, script),
+                "Total container size limit exceeded",
+            );
+            assert!(vm.allocated_container_size() <= 1000);
+        }
+
+        #[test]
+        fn total_container_size_is_shared_and_reset() {
+            let script = "
+x = \"\"
+for i in 0..10
+  x = x + \"abcdefghij\"
+x.size()
+";
+            let mut vm = vm_with_limits(MemoryLimits {
+                max_total_container_size: Some(2000),
+                ..Default::default()
+            });
+            assert_eq!(run_script(&mut vm, script).unwrap(), Number(100.into()));
+            let allocated = vm.allocated_container_size();
+            assert!(allocated > 0);
+
+            // Containers allocated in spawned VMs count towards the same total
+            let mut spawned_vm = vm.spawn_shared_vm();
+            run_script(&mut spawned_vm, script).unwrap();
+            assert!(vm.allocated_container_size() > allocated);
+
+            check_error(
+                run_script(&mut vm, "x = []\nloop\n  x.push 1"),
+                "Total container size limit exceeded",
+            );
+
+            // Setting the memory limits resets the total
+            vm.set_memory_limits(vm.memory_limits());
+            assert_eq!(vm.allocated_container_size(), 0);
+            assert_eq!(run_script(&mut vm, script).unwrap(), Number(100.into()));
+        }
+    }
+
+    mod capabilities {
+        use {
+            super::*,
+            koto_runtime::{Capabilities, ImportPolicy},
+        };
+
+        // An 'allowed' directory with a module and script,
+        // along with a module and script outside of the 'allowed' directory
+        const SCRIPT_FILES: &[(&str, &str)] = &[
+            ("allowed/foo.koto", "export x = 42"),
+            ("allowed/main.koto", ""),
+            ("bar.koto", "export x = 99"),
+            ("main.koto", ""),
+        ];
+
+        #[test]
+        fn all_capabilities_by_default() {
+            let mut vm = Vm::default();
+            assert_eq!(
+                run_script(&mut vm, "import io, koto, thread\nkoto.type io").unwrap(),
+                Str("Map".into())
+            );
+        }
+
+        #[test]
+        fn no_filesystem() {
+            let mut vm = Vm::with_capabilities(Capabilities {
+                filesystem: false,
+                ..Capabilities::all()
+            });
+            check_error(run_script(&mut vm, "import io"), "Failed to import 'io'");
+            check_error(
+                run_script(&mut vm, "import koto\nkoto.current_dir()"),
+                "current_dir",
+            );
+            assert_eq!(
+                run_script(&mut vm, "import koto\nkoto.type 1").unwrap(),
+                Str("Number".into())
+            );
+        }
+
+        #[test]
+        fn no_threads() {
+            let mut vm = Vm::with_capabilities(Capabilities {
+                threads: false,
+                ..Capabilities::all()
+            });
+            check_error(
+                run_script(&mut vm, "import thread"),
+                "Failed to import 'thread'",
+            );
+        }
+
+        #[test]
+        fn no_imports() {
+            let dir = make_script_dir("capabilities_no_imports", SCRIPT_FILES);
+            let script_path = dir.join("main.koto");
+            let mut vm = Vm::with_capabilities(Capabilities::none());
+            check_error(
+                run_script_with_path(&mut vm, "import bar", Some(script_path.clone())),
+                "importing modules isn't allowed",
+            );
+
+            // Core library modules can still be imported
+            assert_eq!(
+                run_script_with_path(
+                    &mut vm,
+                    "import string\nstring.size \"abc\"",
+                    Some(script_path)
+                )
+                .unwrap(),
+                Number(3.into())
+            );
+        }
+
+        #[test]
+        fn imports_from_allowed_directory() {
+            let dir = make_script_dir("capabilities_allowed_directory", SCRIPT_FILES);
+            let mut vm = Vm::with_capabilities(Capabilities {
+                imports: ImportPolicy::AllowedDirectory(dir.join("allowed")),
+                ..Capabilities::none()
+            });
+            assert_eq!(
+                run_script_with_path(
+                    &mut vm,
+                    "import foo\nfoo.x",
+                    Some(dir.join("allowed").join("main.koto"))
+                )
+                .unwrap(),
+                Number(42.into())
+            );
+            check_error(
+                run_script_with_path(&mut vm, "import bar\nbar.x", Some(dir.join("main.koto"))),
+                "outside of the allowed directory",
+            );
+        }
+    }
+
+    mod module_paths {
+        use super::*;
+
+        // A 'project' directory with a script,
+        // and a 'lib' directory containing a module and a package
+        const SCRIPT_FILES: &[(&str, &str)] = &[
+            ("project/main.koto", ""),
+            ("project/local.koto", "export x = 1"),
+            ("lib/shared.koto", "export x = 42"),
+            ("lib/utils/strings.koto", "export x = 99"),
+            ("lib/utils/text/main.koto", "export x = -1"),
+        ];
+
+        #[test]
+        fn import_from_search_path() {
+            let dir = make_script_dir("module_paths_search_path", SCRIPT_FILES);
+            let mut vm = Vm::default();
+            vm.context_mut().set_module_paths(vec![dir.join("lib")]);
+
+            let script = "
+import local, shared
+local.x + shared.x
+";
+            assert_eq!(
+                run_script_with_path(&mut vm, script, Some(dir.join("project").join("main.koto")))
+                    .unwrap(),
+                Number(43.into())
+            );
+        }
+
+        #[test]
+        fn import_nested_module_from_package() {
+            let dir = make_script_dir("module_paths_package", SCRIPT_FILES);
+            let mut vm = Vm::default();
+            vm.context_mut().set_module_paths(vec![dir.join("lib")]);
+
+            let script = "
+import utils.strings
+from utils.text import x
+strings.x + x
+";
+            assert_eq!(
+                run_script_with_path(&mut vm, script, Some(dir.join("project").join("main.koto")))
+                    .unwrap(),
+                Number(98.into())
+            );
+        }
+
+        #[test]
+        fn missing_module_error_lists_searched_paths() {
+            let dir = make_script_dir("module_paths_missing_module", SCRIPT_FILES);
+            let mut vm = Vm::default();
+            vm.context_mut().set_module_paths(vec![dir.join("lib")]);
+
+            let error = run_script_with_path(
+                &mut vm,
+                "import missing",
+                Some(dir.join("project").join("main.koto")),
+            )
+            .unwrap_err()
+            .to_string();
+
+            assert!(error.contains("Unable to find module 'missing'"));
+            for searched in &[dir.join("project"), dir.join("lib")] {
+                let searched = searched.canonicalize().unwrap();
+                assert!(
+                    error.contains(searched.to_string_lossy().as_ref()),
+                    "Expected '{}' to be listed in '{}'",
+                    searched.to_string_lossy(),
+                    error
+                );
+            }
+        }
+
+        #[test]
+        fn module_paths_are_kept_after_reset() {
+            let mut vm = Vm::default();
+            vm.context_mut()
+                .set_module_paths(vec![PathBuf::from("foo"), PathBuf::from("bar")]);
+            vm.reset();
+            assert_eq!(
+                vm.context().module_paths(),
+                &[PathBuf::from("foo"), PathBuf::from("bar")]
+            );
+        }
+    }
+
+    mod hot_reloading {
+        use {
+            super::*,
+            std::{path::Path, thread, time::Duration},
+        };
+
+        const SCRIPT_FILES: &[(&str, &str)] = &[("main.koto", "")];
+
+        // Rewrites a module, waiting until its modification time has changed
+        // (some filesystems only record modification times with coarse precision)
+        fn rewrite_module(path: &Path, script: &str) {
+            let modified = || fs::metadata(path).unwrap().modified().unwrap();
+            let before = modified();
+            loop {
+                fs::write(path, script).unwrap();
+                if modified() != before {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+
+        fn import_module(vm: &mut Vm, dir: &Path, name: &str) -> ValueMap {
+            match run_script_with_path(
+                vm,
+                &format!("import {0}\n{0}", name),
+                Some(dir.join("main.koto")),
+            ) {
+                Ok(Map(module)) => module,
+                other => panic!(
+                    "Expected a module, found '{:?}'",
+                    other.map(|v| v.to_string())
+                ),
+            }
+        }
+
+        fn get(module: &ValueMap, key: &str) -> Value {
+            module.data().get_with_string(key).unwrap().clone()
+        }
+
+        #[test]
+        fn reload_modified_modules() {
+            let dir = make_script_dir("hot_reloading_modified", SCRIPT_FILES);
+            let module_path = dir.join("foo.koto");
+            fs::write(&module_path, "export x = 1").unwrap();
+
+            let mut vm = Vm::default();
+            let module = import_module(&mut vm, &dir, "foo");
+            assert!(vm.reload_modified_modules(&[]).unwrap().is_empty());
+
+            rewrite_module(&module_path, "export x = 2");
+            assert_eq!(
+                vm.reload_modified_modules(&[]).unwrap(),
+                vec![module_path.canonicalize().unwrap()]
+            );
+            assert_eq!(get(&module, "x"), Number(2.into()));
+            assert!(vm.reload_modified_modules(&[]).unwrap().is_empty());
+        }
+
+        #[test]
+        fn reload_module_with_preserved_globals() {
+            let dir = make_script_dir("hot_reloading_preserved", SCRIPT_FILES);
+            let module_path = dir.join("foo.koto");
+            fs::write(&module_path, "export count = 0\nexport y = 1").unwrap();
+
+            let mut vm = Vm::default();
+            let script = "
+import foo
+foo.count = 42
+";
+            run_script_with_path(&mut vm, script, Some(dir.join("main.koto"))).unwrap();
+
+            fs::write(&module_path, "export count = 0\nexport y = 2").unwrap();
+            vm.reload_module(&module_path, &["count"]).unwrap();
+
+            let module = import_module(&mut vm, &dir, "foo");
+            assert_eq!(get(&module, "count"), Number(42.into()));
+            assert_eq!(get(&module, "y"), Number(2.into()));
+        }
+
+        #[test]
+        fn reload_module_imported_by_another_module() {
+            let dir = make_script_dir("hot_reloading_nested", SCRIPT_FILES);
+            let module_path = dir.join("bar.koto");
+            fs::write(dir.join("foo.koto"), "export bar = import bar").unwrap();
+            fs::write(&module_path, "export x = 1").unwrap();
+
+            let mut vm = Vm::default();
+            let foo = import_module(&mut vm, &dir, "foo");
+
+            rewrite_module(&module_path, "export x = 2");
+            vm.reload_modified_modules(&[]).unwrap();
+
+            match get(&foo, "bar") {
+                Map(bar) => assert_eq!(get(&bar, "x"), Number(2.into())),
+                unexpected => panic!("Expected a module, found '{}'", unexpected),
+            }
+        }
+
+        #[test]
+        fn failed_reload_keeps_previous_module() {
+            let dir = make_script_dir("hot_reloading_failed", SCRIPT_FILES);
+            let module_path = dir.join("foo.koto");
+            fs::write(&module_path, "export x = 1").unwrap();
+
+            let mut vm = Vm::default();
+            let module = import_module(&mut vm, &dir, "foo");
+
+            rewrite_module(&module_path, "export x = (");
+            assert!(vm.reload_modified_modules(&[]).is_err());
+            assert_eq!(get(&module, "x"), Number(1.into()));
+
+            // The module isn't reported as modified again until it's changed
+            assert!(vm.reload_modified_modules(&[]).unwrap().is_empty());
+        }
+
+        #[test]
+        fn invalidated_module_is_loaded_again_on_import() {
+            let dir = make_script_dir("hot_reloading_invalidated", SCRIPT_FILES);
+            let module_path = dir.join("foo.koto");
+            fs::write(&module_path, "export x = 1").unwrap();
+
+            let mut vm = Vm::default();
+            let module = import_module(&mut vm, &dir, "foo");
+
+            fs::write(&module_path, "export x = 2").unwrap();
+            vm.invalidate_module(&module_path);
+
+            let reloaded = import_module(&mut vm, &dir, "foo");
+            assert_eq!(get(&module, "x"), Number(1.into()));
+            assert_eq!(get(&reloaded, "x"), Number(2.into()));
+        }
+    }
+
+    mod backtraces {
+        use super::*;
+
+        fn script_error(script: &str, path: Option<PathBuf>) -> Error {
+            match run_script_with_path(&mut Vm::default(), script, path) {
+                Err(error) => error,
+                Ok(value) => panic!("Expected an error, found '{}'", value),
+            }
+        }
+
+        // Returns the function name and line for each frame in the error's backtrace
+        fn backtrace_names_and_lines(error: &Error) -> Vec<(Option<String>, u32)> {
+            error
+                .backtrace()
+                .iter()
+                .map(|frame| {
+                    (
+                        frame.function_name().map(String::from),
+                        frame.span().unwrap().start.line,
+                    )
+                })
+                .collect()
+        }
+
+        fn name(name: &str) -> Option<String> {
+            Some(name.to_string())
+        }
+
+        #[test]
+        fn nested_functions() {
+            let script = "
+f = |x|
+  g = |y| y.foo()
+  g x
+f 1
+";
+            let error = script_error(script, None);
+            assert_eq!(
+                backtrace_names_and_lines(&error),
+                vec![(name("g"), 3), (name("f"), 4), (None, 5)]
+            );
+        }
+
+        #[test]
+        fn map_functions() {
+            let script = "
+m =
+  foo: || throw 42
+m.foo()
+";
+            let error = script_error(script, None);
+            assert_eq!(error.thrown_value(), Some(&Number(42.into())));
+            assert_eq!(
+                backtrace_names_and_lines(&error),
+                vec![(name("foo"), 3), (None, 4)]
+            );
+        }
+
+        #[test]
+        fn functions_called_from_external_functions() {
+            let script = "
+f = ||
+  (1..3)
+    .each |n| n.foo()
+    .to_list()
+f()
+";
+            let error = script_error(script, None);
+            assert_eq!(
+                backtrace_names_and_lines(&error),
+                vec![(None, 4), (name("f"), 5), (None, 6)]
+            );
+            assert!(error.backtrace()[0]
+                .to_string()
+                .starts_with("<anonymous> - 4:"));
+        }
+
+        #[test]
+        fn caught_errors_have_no_effect_on_backtrace() {
+            let script = "
+f = ||
+  try
+    x.foo()
+  catch _
+    y.foo()
+f()
+";
+            let error = script_error(script, None);
+            assert_eq!(
+                backtrace_names_and_lines(&error),
+                vec![(name("f"), 6), (None, 7)]
+            );
+        }
+
+        #[test]
+        fn frames_in_imported_modules() {
+            let script = "
+import helper
+helper.call || throw \"error\"
+";
+            let dir = make_script_dir(
+                "backtraces",
+                &[
+                    ("helper.koto", "\nexport call = |f|\n  f()\n"),
+                    ("main.koto", script),
+                ],
+            );
+
+            let error = script_error(script, Some(dir.join("main.koto")));
+            let paths = error
+                .backtrace()
+                .iter()
+                .map(|frame| frame.source_path().unwrap().file_name().unwrap().to_owned())
+                .collect::<Vec<_>>();
+            assert_eq!(paths, vec!["main.koto", "helper.koto", "main.koto"]);
+            assert_eq!(
+                backtrace_names_and_lines(&error),
+                vec![(None, 3), (name("call"), 3), (None, 3)]
+            );
+        }
+    }
+
+    mod coroutines {
+        use {super::*, koto_runtime::Coroutine};
+
+        #[test]
+        fn resume_from_host() {
+            let script = "
+export update = |position|
+  loop
+    position += yield position
+";
+            let mut vm = Vm::default();
+            run_script(&mut vm, script).unwrap();
+            let update = vm.get_global_value("update").unwrap();
+
+            let coroutine = vm.make_coroutine(&update, &[Number(10.into())]).unwrap();
+            assert_eq!(coroutine.resume(Empty).unwrap(), Some(Number(10.into())));
+            assert_eq!(
+                coroutine.resume(Number(1.into())).unwrap(),
+                Some(Number(11.into()))
+            );
+            assert_eq!(
+                coroutine.resume(Number(5.into())).unwrap(),
+                Some(Number(16.into()))
+            );
+            assert!(!coroutine.is_finished());
+        }
+
+        #[test]
+        fn finished_coroutine() {
+            let script = "
+export f = ||
+  x = yield 1
+  yield x * 2
+";
+            let mut vm = Vm::default();
+            run_script(&mut vm, script).unwrap();
+            let f = vm.get_global_value("f").unwrap();
+
+            let coroutine = vm.make_coroutine(&f, &[]).unwrap();
+            assert_eq!(coroutine.resume(Empty).unwrap(), Some(Number(1.into())));
+            assert_eq!(
+                coroutine.resume(Number(21.into())).unwrap(),
+                Some(Number(42.into()))
+            );
+            assert!(!coroutine.is_finished());
+            assert_eq!(coroutine.resume(Empty).unwrap(), None);
+            assert!(coroutine.is_finished());
+            assert_eq!(coroutine.resume(Empty).unwrap(), None);
+        }
+
+        #[test]
+        fn many_suspended_coroutines() {
+            let script = "
+export f = |id|
+  n = 0
+  loop
+    n += yield id + n
+";
+            let mut vm = Vm::default();
+            run_script(&mut vm, script).unwrap();
+            let f = vm.get_global_value("f").unwrap();
+
+            let coroutines = (0..1000)
+                .map(|id| vm.make_coroutine(&f, &[Number(id.into())]).unwrap())
+                .collect::<Vec<_>>();
+
+            for coroutine in coroutines.iter() {
+                coroutine.resume(Empty).unwrap();
+            }
+
+            for (id, coroutine) in coroutines.iter().enumerate() {
+                assert_eq!(
+                    coroutine.resume(Number(100.into())).unwrap(),
+                    Some(Number((id as i64 + 100).into()))
+                );
+            }
+        }
+
+        #[test]
+        fn generator_from_script() {
+            let mut vm = Vm::default();
+            let generator = run_script(&mut vm, "f = ||\n  yield (yield 1) + 1\nf()").unwrap();
+
+            let coroutine = Coroutine::from_value(&generator).unwrap();
+            assert_eq!(coroutine.resume(Empty).unwrap(), Some(Number(1.into())));
+            assert_eq!(
+                coroutine.resume(Number(41.into())).unwrap(),
+                Some(Number(42.into()))
+            );
+
+            assert!(Coroutine::from_value(&Number(1.into())).is_none());
+        }
+
+        #[test]
+        fn incorrect_argument_count() {
+            let mut vm = Vm::default();
+            run_script(&mut vm, "export f = |x| yield x").unwrap();
+            let f = vm.get_global_value("f").unwrap();
+
+            assert!(vm.make_coroutine(&f, &[]).is_err());
+        }
+
+        #[test]
+        fn variadic_function() {
+            let mut vm = Vm::default();
+            run_script(&mut vm, "export f = |x, rest...| yield x + rest.size()").unwrap();
+            let f = vm.get_global_value("f").unwrap();
+
+            let coroutine = vm
+                .make_coroutine(&f, &[Number(10.into()), Empty, Empty])
+                .unwrap();
+            assert_eq!(coroutine.resume(Empty).unwrap(), Some(Number(12.into())));
+
+            let coroutine = vm.make_coroutine(&f, &[Number(10.into())]).unwrap();
+            assert_eq!(coroutine.resume(Empty).unwrap(), Some(Number(10.into())));
+
+            assert!(vm.make_coroutine(&f, &[]).is_err());
+        }
+
+        #[test]
+        fn instance_function() {
+            let script = "
+export foo =
+  x: 42
+  f: |self, n| yield self.x + n
+";
+            let mut vm = Vm::default();
+            run_script(&mut vm, script).unwrap();
+            let foo = vm.get_global_value("foo").unwrap();
+            let f = match &foo {
+                Map(map) => map.data().get_with_string("f").cloned().unwrap(),
+                _ => unreachable!(),
+            };
+
+            let coroutine = vm.make_coroutine(&f, &[foo, Number(1.into())]).unwrap();
+            assert_eq!(coroutine.resume(Empty).unwrap(), Some(Number(43.into())));
+
+            assert!(vm.make_coroutine(&f, &[]).is_err());
+        }
+
+        #[test]
+        fn yield_value_when_iterating() {
+            let mut vm = Vm::default();
+            let script = "
+g = ||
+  x = yield 1
+  yield x
+g().to_list()
+";
+            assert_eq!(
+                run_script(&mut vm, script).unwrap(),
+                List(ValueList::from_slice(&[Number(1.into()), Number(1.into())]))
+            );
+        }
+    }
+
+    mod typed_functions {
+        use {super::*, std::collections::HashMap};
+
+        fn run_typed_script(script: &str) -> Result<Value, Error> {
+            let mut vm = Vm::default();
+            let mut prelude = vm.context_mut().prelude.clone();
+
+            let mut typed = ValueMap::new();
+            typed.add_fn_typed("add", |a: f64, b: f64| a + b);
+            typed.add_fn_typed("repeat", |s: String, n: Option<usize>| {
+                s.repeat(n.unwrap_or(2))
+            });
+            typed.add_fn_typed("sum", |values: Vec<i64>| values.iter().sum::<i64>());
+            typed.add_fn_typed("swap", |(a, b): (Value, Value)| (b, a));
+            typed.add_fn_typed("keys", |map: HashMap<String, Value>| {
+                let mut keys = map.keys().cloned().collect::<Vec<_>>();
+                keys.sort();
+                keys
+            });
+            typed.add_fn_typed("checked_div", |a: i64, b: i64| {
+                if b == 0 {
+                    Err("Division by zero")
+                } else {
+                    Ok(a / b)
+                }
+            });
+            typed.add_fn_typed("nothing", || ());
+            prelude.add_map("typed", typed);
+
+            run_script(&mut vm, &format!("import typed\n{}", script))
+        }
+
+        fn check_script_output(script: &str, expected_output: Value) {
+            assert_eq!(run_typed_script(script).unwrap(), expected_output);
+        }
+
+        fn check_script_error(script: &str, expected_error: &str) {
+            check_error(run_typed_script(script), expected_error);
+        }
+
+        #[test]
+        fn number_arguments() {
+            check_script_output("typed.add 1 2", Number(3.into()));
+        }
+
+        #[test]
+        fn optional_argument() {
+            check_script_output("typed.repeat \"ab\"", Str("abab".into()));
+            check_script_output("typed.repeat \"ab\" 3", Str("ababab".into()));
+        }
+
+        #[test]
+        fn vec_argument() {
+            check_script_output("typed.sum [1, 2, 3]", Number(6.into()));
+            check_script_output("typed.sum (1, 2, 3)", Number(6.into()));
+        }
+
+        #[test]
+        fn tuple_argument_and_return_value() {
+            check_script_output(
+                "typed.swap (1, \"x\")",
+                Tuple(vec![Str("x".into()), Number(1.into())].into()),
+            );
+        }
+
+        #[test]
+        fn hash_map_argument() {
+            check_script_output(
+                "typed.keys {foo: 1, bar: 2}",
+                List(ValueList::from_slice(&[
+                    Str("bar".into()),
+                    Str("foo".into()),
+                ])),
+            );
+        }
+
+        #[test]
+        fn result_return_value() {
+            check_script_output("typed.checked_div 9 3", Number(3.into()));
+            check_script_error("typed.checked_div 1 0", "checked_div: Division by zero");
+        }
+
+        #[test]
+        fn unit_return_value() {
+            check_script_output("typed.nothing()", Empty);
+        }
+
+        #[test]
+        fn unexpected_argument_type() {
+            check_script_error(
+                "typed.add 1 true",
+                "add: Expected (Number, Number) as arguments, found (Number, Bool)",
+            );
+            check_script_error(
+                "typed.sum [1, \"x\"]",
+                "sum: Expected (List of Number) as arguments, found (List)",
+            );
+            check_script_error(
+                "typed.checked_div 1.5 1",
+                "checked_div: Expected (Number, Number) as arguments, found (Number, Number)",
+            );
+            check_script_error(
+                "typed.repeat \"x\" -1",
+                "repeat: Expected (String, Number or Empty) as arguments",
+            );
+        }
+
+        #[test]
+        fn unexpected_argument_count() {
+            check_script_error(
+                "typed.add 1",
+                "add: Expected (Number, Number) as arguments, found (Number)",
+            );
+            check_script_error(
+                "typed.nothing 1",
+                "nothing: Expected no arguments, found (Number)",
+            );
         }
     }
 }
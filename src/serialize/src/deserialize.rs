@@ -0,0 +1,510 @@
+use {
+    koto_runtime::{
+        type_as_string, Value, ValueHashMap, ValueList, ValueMap, ValueNumber, ValueVec,
+    },
+    serde::de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer},
+        DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
+        SeqAccess, VariantAccess, Visitor,
+    },
+    std::fmt,
+};
+
+/// Deserializes a Rust value from a Koto value
+///
+/// e.g.
+/// ```ignore
+/// #[derive(Deserialize)]
+/// struct Config {
+///     name: String,
+///     size: (u32, u32),
+/// }
+///
+/// let config: Config = from_value(&koto.run()?)?;
+/// ```
+pub fn from_value<T>(value: &Value) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(ValueDeserializer::new(value.clone()))
+}
+
+/// An error that occurred while deserializing from a Koto value
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Self(message.to_string())
+    }
+}
+
+/// A Koto value that can be deserialized from any self-describing serde format
+pub struct DeserializableValue(pub Value);
+
+impl<'de> de::Deserialize<'de> for DeserializableValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_any(DeserializableValueVisitor)
+            .map(DeserializableValue)
+    }
+}
+
+struct DeserializableValueVisitor;
+
+impl<'de> Visitor<'de> for DeserializableValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value that can be represented in Koto")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Value, E> {
+        Ok(Value::Number(n.into()))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Value, E> {
+        // Integers that are too large to be represented as an i64 are converted to floats
+        if n <= i64::MAX as u64 {
+            Ok(Value::Number((n as i64).into()))
+        } else {
+            Ok(Value::Number((n as f64).into()))
+        }
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<Value, E> {
+        Ok(Value::Number(n.into()))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::Str(s.into()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Value, E> {
+        Ok(Value::Str(s.into()))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Empty)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Empty)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut result = ValueVec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(DeserializableValue(value)) = seq.next_element()? {
+            result.push(value);
+        }
+        Ok(Value::List(ValueList::with_data(result)))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut result = ValueHashMap::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((DeserializableValue(key), DeserializableValue(value))) = map.next_entry()? {
+            result.insert(key, value);
+        }
+        Ok(Value::Map(ValueMap::with_data(result)))
+    }
+}
+
+/// A serde Deserializer that reads from a Koto value
+///
+/// Lists, Tuples, Num2s and Num4s are deserialized as sequences, Maps are deserialized as maps,
+/// and Ranges are deserialized as maps with `start` and `end` entries.
+///
+/// Enums can be deserialized from Strings for unit variants, or from Maps containing a single
+/// entry, with the variant name as the key.
+pub struct ValueDeserializer {
+    value: Value,
+}
+
+impl ValueDeserializer {
+    pub fn new(value: Value) -> Self {
+        Self { value }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ValueDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+fn visit_values<'de, V, I>(visitor: V, values: I) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+    I: Iterator<Item = Value>,
+{
+    let mut seq = SeqDeserializer::new(values.map(ValueDeserializer::new));
+    let result = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(result)
+}
+
+fn visit_entries<'de, V, I>(visitor: V, entries: I) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+    I: Iterator<Item = (Value, Value)>,
+{
+    let mut map = MapDeserializer::new(
+        entries.map(|(key, value)| (ValueDeserializer::new(key), ValueDeserializer::new(value))),
+    );
+    let result = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(result)
+}
+
+// Integral floats are deserialized as integers, e.g. a value produced by division like `1920 / 2`
+// can be deserialized into an integer field.
+//
+// The visitor is responsible for checking that the integer is in range for the target type.
+macro_rules! deserialize_integer {
+    ($fn_name:ident) => {
+        fn $fn_name<V>(self, visitor: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            match self.value {
+                // i64::MAX as f64 rounds up to 2^63, so the upper bound is exclusive
+                Value::Number(ValueNumber::F64(n))
+                    if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 =>
+                {
+                    visitor.visit_i64(n as i64)
+                }
+                _ => self.deserialize_any(visitor),
+            }
+        }
+    };
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Empty => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Number(ValueNumber::F64(n)) => visitor.visit_f64(n),
+            Value::Number(ValueNumber::I64(n)) => visitor.visit_i64(n),
+            Value::Num2(n) => visit_values(visitor, vec![n.0, n.1].into_iter().map(Value::from)),
+            Value::Num4(n) => visit_values(
+                visitor,
+                vec![n.0, n.1, n.2, n.3]
+                    .into_iter()
+                    .map(|x| Value::Number(x.into())),
+            ),
+            Value::Range(r) => visit_entries(
+                visitor,
+                vec![
+                    (Value::from("start"), Value::Number(r.start.into())),
+                    (Value::from("end"), Value::Number(r.end.into())),
+                ]
+                .into_iter(),
+            ),
+            Value::List(l) => {
+                let values = l.data().iter().cloned().collect::<Vec<_>>();
+                visit_values(visitor, values.into_iter())
+            }
+            Value::Tuple(t) => visit_values(visitor, t.data().to_vec().into_iter()),
+            Value::Map(m) => {
+                let entries = m
+                    .data()
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect::<Vec<_>>();
+                visit_entries(visitor, entries.into_iter())
+            }
+            Value::Str(s) => visitor.visit_str(&s),
+            unexpected => Err(de::Error::custom(format!(
+                "Unable to deserialize a value of type '{}'",
+                type_as_string(&unexpected)
+            ))),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Empty => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match &self.value {
+            Value::Str(_) => visitor.visit_enum(EnumDeserializer {
+                variant: self.value,
+                value: None,
+            }),
+            Value::Map(m) if m.len() == 1 => {
+                let (variant, value) = m
+                    .data()
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .next()
+                    .unwrap();
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            unexpected => Err(de::Error::custom(format!(
+                "Expected a String or a Map with a single entry for an enum, found '{}'",
+                type_as_string(unexpected)
+            ))),
+        }
+    }
+
+    deserialize_integer!(deserialize_i8);
+    deserialize_integer!(deserialize_i16);
+    deserialize_integer!(deserialize_i32);
+    deserialize_integer!(deserialize_i64);
+    deserialize_integer!(deserialize_u8);
+    deserialize_integer!(deserialize_u16);
+    deserialize_integer!(deserialize_u32);
+    deserialize_integer!(deserialize_u64);
+
+    serde::forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: Value,
+    value: Option<Value>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantDeserializer), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(ValueDeserializer::new(self.variant))?;
+        Ok((variant, VariantDeserializer { value: self.value }))
+    }
+}
+
+struct VariantDeserializer {
+    value: Option<Value>,
+}
+
+impl VariantDeserializer {
+    fn into_value(self) -> Result<Value, Error> {
+        self.value
+            .ok_or_else(|| de::Error::custom("Missing value for enum variant"))
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.value {
+            None | Some(Value::Empty) => Ok(()),
+            Some(unexpected) => Err(de::Error::custom(format!(
+                "Expected no value for a unit variant, found '{}'",
+                type_as_string(&unexpected)
+            ))),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(ValueDeserializer::new(self.into_value()?))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        ValueDeserializer::new(self.into_value()?).deserialize_any(visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        ValueDeserializer::new(self.into_value()?).deserialize_any(visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        koto_runtime::{Loader, Vm},
+        serde::Deserialize,
+        std::collections::HashMap,
+    };
+
+    fn run_script(script: &str) -> Value {
+        let chunk = Loader::default().compile_script(script, &None).unwrap();
+        Vm::default().run(chunk).unwrap()
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Mode {
+        Fast,
+        Slow { delay: f64 },
+        Repeat(u8),
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Config {
+        name: String,
+        size: (u32, u32),
+        scale: Option<f64>,
+        offset: [f64; 2],
+        tags: Vec<String>,
+        limits: HashMap<String, i64>,
+        modes: Vec<Mode>,
+        range: std::ops::Range<i32>,
+    }
+
+    #[test]
+    fn integral_floats_as_integers() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Size {
+            width: u32,
+            height: i64,
+        }
+
+        let size: Size = from_value(&run_script("{width: 1920 / 2, height: 1080 / 2}")).unwrap();
+        assert_eq!(
+            size,
+            Size {
+                width: 960,
+                height: 540
+            }
+        );
+
+        assert!(from_value::<u32>(&run_script("3 / 2")).is_err());
+        assert!(from_value::<u8>(&run_script("600 / 2")).is_err());
+        assert!(from_value::<u32>(&run_script("-2 / 2")).is_err());
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        let script = r#"
+config =
+  name: "test"
+  size: (800, 600)
+  scale: ()
+  offset: num2 1 2
+  tags: ["a", "b"]
+  limits: {x: 1, y: -1}
+  modes: ["Fast", {Slow: {delay: 0.5}}, {Repeat: 3}]
+  range: 10..20
+config
+"#;
+        let config: Config = from_value(&run_script(script)).unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                name: "test".to_string(),
+                size: (800, 600),
+                scale: None,
+                offset: [1.0, 2.0],
+                tags: vec!["a".to_string(), "b".to_string()],
+                limits: vec![("x".to_string(), 1), ("y".to_string(), -1)]
+                    .into_iter()
+                    .collect(),
+                modes: vec![Mode::Fast, Mode::Slow { delay: 0.5 }, Mode::Repeat(3)],
+                range: 10..20,
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        assert!(from_value::<String>(&Value::Bool(true)).is_err());
+        assert!(from_value::<u8>(&Value::Number(1000.into())).is_err());
+        assert!(from_value::<(i64, i64)>(&run_script("[1, 2, 3]")).is_err());
+        assert!(from_value::<DeserializableValue>(&run_script("|| 42")).is_err());
+    }
+
+    #[test]
+    fn deserializable_value_round_trip() {
+        let value = run_script("{foo: [1, 2.5, true, ()], bar: {baz: \"x\"}}");
+        let result = DeserializableValue::deserialize(ValueDeserializer::new(value.clone()))
+            .unwrap()
+            .0;
+        assert_eq!(result, value);
+    }
+}
@@ -1,46 +1,205 @@
 //! Serde serialization support for Koto value types
 
+mod deserialize;
+
+pub use deserialize::{from_value, DeserializableValue, Error, ValueDeserializer};
+
 use {
-    koto_runtime::Value,
-    serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer},
+    koto_runtime::{meta_keys, type_as_string, Value, ValueMap, ValueNumber},
+    serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer},
 };
 
-pub struct SerializableValue<'a>(pub &'a Value);
+/// Options that control how values are serialized
+#[derive(Clone, Copy, Debug)]
+pub struct SerializeOptions {
+    /// Whether or not the output should be formatted for readability
+    ///
+    /// This is used by the serialization libraries when choosing between their pretty and
+    /// compact output functions.
+    pub pretty: bool,
+    /// Whether or not map entries should be sorted by key
+    pub sort_keys: bool,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        Self {
+            pretty: true,
+            sort_keys: false,
+        }
+    }
+}
+
+impl SerializeOptions {
+    /// Makes serialization options from a Koto map
+    ///
+    /// e.g. `{pretty: false, sort_keys: true}`
+    ///
+    /// Options that aren't included in the map keep their default values.
+    pub fn from_map(map: &ValueMap) -> Result<Self, String> {
+        let mut result = Self::default();
+
+        for (key, value) in map.data().iter() {
+            let option = match (key, value) {
+                (Value::Str(key), Value::Bool(_)) => match key.as_str() {
+                    "pretty" => &mut result.pretty,
+                    "sort_keys" => &mut result.sort_keys,
+                    _ => return Err(format!("Unexpected option '{}'", key)),
+                },
+                (Value::Str(key), unexpected) => {
+                    return Err(format!(
+                        "Expected a Bool for the '{}' option, found '{}'",
+                        key,
+                        type_as_string(unexpected)
+                    ))
+                }
+                (unexpected, _) => return Err(format!("Unexpected option '{}'", unexpected)),
+            };
+
+            *option = matches!(value, Value::Bool(true));
+        }
+
+        Ok(result)
+    }
+
+    /// Makes serialization options from the arguments that follow the value to be serialized
+    ///
+    /// The arguments should either be empty, or contain a single map of options.
+    /// Only the options listed in `supported_options` are accepted.
+    ///
+    /// Errors are prefixed with the function's name, e.g. `json.to_string`.
+    pub fn from_args(
+        fn_name: &str,
+        args: &[Value],
+        supported_options: &[&str],
+    ) -> Result<Self, String> {
+        match args {
+            [] => Ok(Self::default()),
+            [Value::Map(options)] => {
+                for (key, _) in options.data().iter() {
+                    if let Value::Str(key) = key {
+                        if !supported_options.contains(&key.as_str()) {
+                            return Err(format!(
+                                "{}: The '{}' option isn't supported",
+                                fn_name, key
+                            ));
+                        }
+                    }
+                }
+
+                Self::from_map(options).map_err(|e| format!("{}: {}", fn_name, e))
+            }
+            _ => Err(format!(
+                "{} expects a value, and an optional map of options as arguments",
+                fn_name
+            )),
+        }
+    }
+}
+
+/// A wrapper for Koto values that implements serde's `Serialize`
+///
+/// Values are serialized as follows:
+///   - Lists and Tuples are serialized as sequences.
+///   - Maps are serialized as maps, with keys converted to strings.
+///   - Num2s and Num4s are serialized as sequences of numbers.
+///   - Ranges are serialized as maps with `start` and `end` entries.
+///   - External values are serialized as strings.
+///
+/// Functions, generators, and iterators can't be serialized, and produce an error.
+pub struct SerializableValue<'a> {
+    value: &'a Value,
+    options: SerializeOptions,
+}
+
+impl<'a> SerializableValue<'a> {
+    pub fn new(value: &'a Value) -> Self {
+        Self::with_options(value, SerializeOptions::default())
+    }
+
+    pub fn with_options(value: &'a Value, options: SerializeOptions) -> Self {
+        Self { value, options }
+    }
+
+    fn nested(&self, value: &'a Value) -> Self {
+        Self::with_options(value, self.options)
+    }
+
+    fn serialize_map<S>(&self, m: &ValueMap, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let data = m.data();
+        // Meta keys like "@+" and "@display" define the map's behaviour rather than its data,
+        // so they're left out of the serialized output.
+        let mut entries = data
+            .iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .filter(|(key, _)| !meta_keys::is_meta_key(key))
+            .collect::<Vec<_>>();
+        if self.options.sort_keys {
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+
+        let mut map = s.serialize_map(Some(entries.len()))?;
+        for (key, value) in entries.iter() {
+            map.serialize_entry(key, &self.nested(value))?;
+        }
+        map.end()
+    }
+}
 
 impl<'a> Serialize for SerializableValue<'a> {
     fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        match self.0 {
+        match self.value {
             Value::Empty => s.serialize_unit(),
             Value::Bool(b) => s.serialize_bool(*b),
-            Value::Number(n) => s.serialize_f64(*n),
+            Value::Number(ValueNumber::F64(n)) => s.serialize_f64(*n),
+            Value::Number(ValueNumber::I64(n)) => s.serialize_i64(*n),
+            Value::Num2(n) => {
+                let mut seq = s.serialize_seq(Some(2))?;
+                seq.serialize_element(&n.0)?;
+                seq.serialize_element(&n.1)?;
+                seq.end()
+            }
+            Value::Num4(n) => {
+                let mut seq = s.serialize_seq(Some(4))?;
+                seq.serialize_element(&n.0)?;
+                seq.serialize_element(&n.1)?;
+                seq.serialize_element(&n.2)?;
+                seq.serialize_element(&n.3)?;
+                seq.end()
+            }
+            Value::Range(r) => {
+                let mut map = s.serialize_map(Some(2))?;
+                map.serialize_entry("start", &r.start)?;
+                map.serialize_entry("end", &r.end)?;
+                map.end()
+            }
             Value::List(l) => {
                 let mut seq = s.serialize_seq(Some(l.len()))?;
                 for element in l.data().iter() {
-                    seq.serialize_element(&SerializableValue(element))?;
+                    seq.serialize_element(&self.nested(element))?;
                 }
                 seq.end()
             }
             Value::Tuple(t) => {
                 let mut seq = s.serialize_seq(Some(t.data().len()))?;
                 for element in t.data().iter() {
-                    seq.serialize_element(&SerializableValue(element))?;
-                }
-                seq.end()
-            }
-            Value::Map(m) => {
-                let mut seq = s.serialize_map(Some(m.data().len()))?;
-                for (key, value) in m.data().iter() {
-                    seq.serialize_entry(&key.to_string(), &SerializableValue(value))?;
+                    seq.serialize_element(&self.nested(element))?;
                 }
                 seq.end()
             }
+            Value::Map(m) => self.serialize_map(m, s),
             Value::Str(string) => s.serialize_str(string),
             Value::ExternalValue(value) => s.serialize_str(&value.read().unwrap().to_string()),
-            // TODO, is it ok to do nothing for non-fundamental types like Range and Num4?
-            _ => s.serialize_unit(),
+            unexpected => Err(ser::Error::custom(format!(
+                "Unable to serialize a value of type '{}'",
+                type_as_string(unexpected)
+            ))),
         }
     }
 }